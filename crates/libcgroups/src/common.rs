@@ -266,6 +266,180 @@ pub fn get_all_pids(path: &Path) -> Result<Vec<Pid>> {
     Ok(result)
 }
 
+/// Returns the absolute paths of a cgroup in all hierarchies it is part of. On a
+/// unified system this is a single path, on a legacy or hybrid system there is
+/// one path per mounted cgroup v1 hierarchy.
+pub fn get_cgroup_hierarchy_paths(cgroup_path: &Path) -> Result<Vec<PathBuf>> {
+    let cgroup_path = cgroup_path.strip_prefix("/").unwrap_or(cgroup_path);
+    let roots = match get_cgroup_setup()? {
+        CgroupSetup::Unified => vec![PathBuf::from(DEFAULT_CGROUP_ROOT)],
+        CgroupSetup::Legacy | CgroupSetup::Hybrid => list_v1_mount_points()?,
+    };
+
+    Ok(roots.iter().map(|root| root.join(cgroup_path)).collect())
+}
+
+#[cfg(feature = "v1")]
+fn list_v1_mount_points() -> Result<Vec<PathBuf>> {
+    v1::util::list_subsystem_mount_points()
+}
+
+#[cfg(not(feature = "v1"))]
+fn list_v1_mount_points() -> Result<Vec<PathBuf>> {
+    bail!("cgroup v1 feature is required, but was not enabled during compile time");
+}
+
+/// Checks if a cgroup has been created already, e.g. by a manager which wants
+/// the container to join it, from its paths in all hierarchies. A cgroup only
+/// exists if it exists in every hierarchy, a path which exists but is not a
+/// cgroup is an error.
+pub fn cgroup_exists(hierarchy_paths: &[PathBuf]) -> Result<bool> {
+    for path in hierarchy_paths {
+        if !path.exists() {
+            return Ok(false);
+        }
+        if !path.join(CGROUP_PROCS).exists() {
+            bail!("{:?} exists, but is not a cgroup", path);
+        }
+    }
+
+    Ok(!hierarchy_paths.is_empty())
+}
+
+/// Utilization clamps are a percentage with at most two decimal places or
+/// "max", which is the same as 100. They are set as `cpu.uclamp.min` and
+/// `cpu.uclamp.max` of cgroup v2 only.
+pub fn validate_uclamp(value: &str) -> Result<()> {
+    if value == "max" {
+        return Ok(());
+    }
+
+    let decimals = value.split_once('.').map_or(0, |(_, d)| d.len());
+    let numeric = value.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    match value.parse::<f64>() {
+        Ok(percent) if numeric && decimals <= 2 && (0.0..=100.0).contains(&percent) => Ok(()),
+        _ => bail!(
+            "utilization clamp {:?} is not a percentage between 0 and 100 or max",
+            value
+        ),
+    }
+}
+
+/// Returns the memory limit in bytes which is in effect for the resources,
+/// the lower one of `memory.limit` and of `memory.max` in the unified
+/// resources, or `None` if neither of them limits the memory.
+pub fn effective_memory_max(resources: &LinuxResources) -> Result<Option<u64>> {
+    let limit = match resources.memory().as_ref().and_then(|m| m.limit()) {
+        Some(limit @ 1..=i64::MAX) => Some(limit as u64),
+        _ => None,
+    };
+    let max = match resources
+        .unified()
+        .as_ref()
+        .and_then(|unified| unified.get("memory.max"))
+        .map(|max| max.trim())
+    {
+        None | Some("max") => None,
+        Some(max) => Some(
+            max.parse::<u64>()
+                .with_context(|| format!("invalid memory.max value: {}", max))?,
+        ),
+    };
+
+    Ok(match (limit, max) {
+        (Some(limit), Some(max)) => Some(limit.min(max)),
+        (limit, max) => limit.or(max),
+    })
+}
+
+/// Parses a value of `memory.min`, the memory which is guaranteed to the
+/// processes of a cgroup. More than the memory limit `max` can not be
+/// guaranteed, so a value above it is rejected.
+pub fn parse_memory_min(value: &str, max: Option<u64>) -> Result<u64> {
+    let min = value
+        .trim()
+        .parse::<u64>()
+        .with_context(|| format!("invalid guaranteed memory value: {}", value))?;
+
+    if let Some(max) = max {
+        if min > max {
+            bail!(
+                "guaranteed memory of {} exceeds the memory limit of {}",
+                min,
+                max
+            );
+        }
+    }
+
+    Ok(min)
+}
+
+/// Parses a value of `memory.high`, above which the processes of a cgroup are
+/// throttled and put under reclaim pressure. `max` and -1 disable throttling,
+/// which is returned as `None`. Throttling has to start before the memory
+/// limit `max` is reached, so a value above it is rejected.
+pub fn parse_memory_high(value: &str, max: Option<u64>) -> Result<Option<u64>> {
+    let high = match value.trim() {
+        "max" | "-1" => return Ok(None),
+        high => high
+            .parse::<u64>()
+            .with_context(|| format!("invalid memory.high value: {}", value))?,
+    };
+
+    if let Some(max) = max {
+        if high > max {
+            bail!(
+                "memory.high of {} exceeds the memory limit of {}",
+                high,
+                max
+            );
+        }
+    }
+
+    Ok(Some(high))
+}
+
+/// Removes the cgroup at `path` and all of its descendants which do not contain
+/// any processes. Returns the removed cgroups, descendants before their parents.
+/// A cgroup which still contains processes, either directly or through one of its
+/// descendants, is never removed.
+pub fn remove_empty_cgroups(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    if path.exists() {
+        remove_if_empty(path, &mut removed)?;
+    }
+    Ok(removed)
+}
+
+fn remove_if_empty(path: &Path, removed: &mut Vec<PathBuf>) -> Result<bool> {
+    let mut is_empty = true;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && !remove_if_empty(&entry.path(), removed)? {
+            is_empty = false;
+        }
+    }
+
+    let procs = path.join(CGROUP_PROCS);
+    if procs.exists() && !fs::read_to_string(&procs)?.trim().is_empty() {
+        is_empty = false;
+    }
+
+    if !is_empty {
+        return Ok(false);
+    }
+
+    // A process may have joined the cgroup since cgroup.procs has been read. In
+    // that case the kernel refuses to remove the cgroup, so it is kept.
+    if let Err(err) = fs::remove_dir(path) {
+        log::debug!("failed to remove cgroup {:?}: {}", path, err);
+        return Ok(false);
+    }
+
+    removed.push(path.to_path_buf());
+    Ok(true)
+}
+
 fn walk_dir<F>(path: &Path, c: &mut F) -> Result<()>
 where
     F: FnMut(&Path) -> Result<()>,
@@ -432,3 +606,149 @@ pub(crate) fn delete_with_retry<P: AsRef<Path>, L: Into<Option<Duration>>>(
 
     bail!("could not delete {:?}", path)
 }
+
+/// Deletes the cgroup together with the cgroups below it, e.g. the sub-cgroups
+/// of processes exec'd into a container. A cgroup can only be deleted once it
+/// has no children, so the deepest cgroups are deleted first. The processes in
+/// the cgroups have to be killed before.
+pub(crate) fn delete_tree_with_retry<P: AsRef<Path>, L: Into<Option<Duration>> + Copy>(
+    path: P,
+    retries: u32,
+    limit_backoff: L,
+) -> Result<()> {
+    let path = path.as_ref();
+    for entry in fs::read_dir(path).with_context(|| format!("failed to read {:?}", path))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            delete_tree_with_retry(entry.path(), retries, limit_backoff)?;
+        }
+    }
+
+    delete_with_retry(path, retries, limit_backoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+    use oci_spec::runtime::{LinuxMemoryBuilder, LinuxResourcesBuilder};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_effective_memory_max() -> Result<()> {
+        for (limit, max, expected) in [
+            (None, None, None),
+            (Some(-1), None, None),
+            (Some(1073741824), None, Some(1073741824)),
+            (None, Some("536870912"), Some(536870912)),
+            (Some(-1), Some("536870912"), Some(536870912)),
+            (Some(1073741824), Some("536870912"), Some(536870912)),
+            (Some(268435456), Some("536870912"), Some(268435456)),
+            (Some(268435456), Some("max"), Some(268435456)),
+        ] {
+            let mut builder = LinuxResourcesBuilder::default();
+            if let Some(limit) = limit {
+                builder = builder.memory(LinuxMemoryBuilder::default().limit(limit).build()?);
+            }
+            if let Some(max) = max {
+                builder =
+                    builder.unified(HashMap::from([("memory.max".to_owned(), max.to_owned())]));
+            }
+            assert_eq!(effective_memory_max(&builder.build()?)?, expected);
+        }
+
+        let resources = LinuxResourcesBuilder::default()
+            .unified(HashMap::from([(
+                "memory.max".to_owned(),
+                "lots".to_owned(),
+            )]))
+            .build()?;
+        assert!(effective_memory_max(&resources).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cgroup_exists() -> Result<()> {
+        let tmp = create_temp_dir("test_cgroup_exists")?;
+        let cpu = tmp.join("cpu").join("pod");
+        let memory = tmp.join("memory").join("pod");
+        fs::create_dir_all(&cpu)?;
+        set_fixture(&cpu, CGROUP_PROCS, "")?;
+
+        assert!(cgroup_exists(std::slice::from_ref(&cpu))?);
+        assert!(!cgroup_exists(&[cpu.clone(), memory.clone()])?);
+        assert!(!cgroup_exists(&[])?);
+
+        // a plain directory is not a cgroup which could be joined
+        fs::create_dir_all(&memory)?;
+        assert!(cgroup_exists(&[cpu, memory]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_empty_cgroups() -> Result<()> {
+        let tmp = create_temp_dir("test_remove_empty_cgroups")?;
+        // cgroupfs allows removing a cgroup while its interface files are still
+        // present, a plain directory does not. Empty cgroups are therefore modelled
+        // without a cgroup.procs file.
+        let empty = tmp.join("empty");
+        let empty_nested = tmp.join("empty_parent").join("nested");
+        let populated = tmp.join("populated");
+        let mixed_empty = tmp.join("mixed").join("empty");
+        let mixed_populated = tmp.join("mixed").join("populated");
+        for dir in [
+            &empty,
+            &empty_nested,
+            &populated,
+            &mixed_empty,
+            &mixed_populated,
+        ] {
+            fs::create_dir_all(dir)?;
+        }
+        set_fixture(&populated, CGROUP_PROCS, "1234\n")?;
+        set_fixture(&mixed_populated, CGROUP_PROCS, "5678\n")?;
+
+        let mut removed = Vec::new();
+        for entry in fs::read_dir(tmp.path())? {
+            removed.extend(remove_empty_cgroups(&entry?.path())?);
+        }
+        removed.sort();
+
+        let mut expected = vec![
+            empty.clone(),
+            empty_nested.clone(),
+            tmp.join("empty_parent"),
+            mixed_empty.clone(),
+        ];
+        expected.sort();
+        assert_eq!(removed, expected);
+
+        assert!(!empty.exists());
+        assert!(!tmp.join("empty_parent").exists());
+        assert!(!mixed_empty.exists());
+        assert!(populated.exists());
+        assert!(mixed_populated.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_empty_cgroups_not_existing() -> Result<()> {
+        let tmp = create_temp_dir("test_remove_empty_cgroups_not_existing")?;
+        assert!(remove_empty_cgroups(&tmp.join("missing"))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_tree_with_retry() -> Result<()> {
+        let tmp = create_temp_dir("test_delete_tree_with_retry")?;
+        // the cgroup of a container with the sub-cgroups of exec'd processes
+        let container = tmp.join("container");
+        fs::create_dir_all(container.join("debug/nested"))?;
+        fs::create_dir_all(container.join("probe"))?;
+
+        delete_tree_with_retry(&container, 1, None)?;
+        assert!(!container.exists());
+        assert!(tmp.exists());
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@ extern crate mockall;
 mod test;
 
 pub mod common;
+pub mod metrics;
 pub mod stats;
 #[cfg(feature = "systemd")]
 pub mod systemd;
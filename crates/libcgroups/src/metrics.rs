@@ -0,0 +1,317 @@
+//! Renders the statistics of a cgroup in the OpenMetrics text format, which
+//! can be scraped by Prometheus. Metric names are stable, every sample is
+//! labeled with the id of the container.
+use std::fmt::Write;
+
+use super::stats::{BlkioDeviceStat, Stats};
+
+const PREFIX: &str = "youki_container";
+
+#[derive(Debug, Clone, Copy)]
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+        }
+    }
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+struct Metrics {
+    out: String,
+    container_id: String,
+}
+
+impl Metrics {
+    fn new(container_id: &str) -> Self {
+        Self {
+            out: String::new(),
+            container_id: escape_label_value(container_id),
+        }
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(
+            name,
+            MetricType::Counter,
+            help,
+            vec![(Labels::new(), value)],
+        );
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, MetricType::Gauge, help, vec![(Labels::new(), value)]);
+    }
+
+    fn family(&mut self, name: &str, typ: MetricType, help: &str, samples: Vec<(Labels, u64)>) {
+        let name = format!("{}_{}", PREFIX, name);
+        // writing to a string can not fail
+        let _ = writeln!(self.out, "# TYPE {} {}", name, typ.as_str());
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        for (labels, value) in samples {
+            let _ = write!(self.out, "{}", name);
+            if let MetricType::Counter = typ {
+                self.out.push_str("_total");
+            }
+            let _ = write!(self.out, "{{id=\"{}\"", self.container_id);
+            for (label, label_value) in labels {
+                let _ = write!(
+                    self.out,
+                    ",{}=\"{}\"",
+                    label,
+                    escape_label_value(&label_value)
+                );
+            }
+            let _ = writeln!(self.out, "}} {}", value);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("# EOF\n");
+        self.out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn device_samples(stats: &[BlkioDeviceStat]) -> Vec<(Labels, u64)> {
+    stats
+        .iter()
+        .map(|stat| {
+            let mut labels = vec![("device", format!("{}:{}", stat.major, stat.minor))];
+            if let Some(op_type) = &stat.op_type {
+                labels.push(("operation", op_type.to_lowercase()));
+            }
+            (labels, stat.value)
+        })
+        .collect()
+}
+
+/// Renders the memory, cpu, pids and io statistics of a container
+pub fn render_openmetrics(container_id: &str, stats: &Stats) -> String {
+    let mut metrics = Metrics::new(container_id);
+
+    let usage = &stats.cpu.usage;
+    metrics.counter(
+        "cpu_usage",
+        "Cpu time consumed by tasks in total",
+        usage.usage_total,
+    );
+    metrics.counter(
+        "cpu_user",
+        "Cpu time consumed by tasks in user mode",
+        usage.usage_user,
+    );
+    metrics.counter(
+        "cpu_kernel",
+        "Cpu time consumed by tasks in kernel mode",
+        usage.usage_kernel,
+    );
+    let throttling = &stats.cpu.throttling;
+    metrics.counter(
+        "cpu_periods",
+        "Number of elapsed enforcement periods",
+        throttling.periods,
+    );
+    metrics.counter(
+        "cpu_throttled_periods",
+        "Number of periods in which tasks were throttled",
+        throttling.throttled_periods,
+    );
+    metrics.counter(
+        "cpu_throttled_time",
+        "Time for which tasks were throttled",
+        throttling.throttled_time,
+    );
+
+    let memory = &stats.memory;
+    metrics.gauge(
+        "memory_usage_bytes",
+        "Memory usage of the tasks",
+        memory.memory.usage,
+    );
+    metrics.gauge(
+        "memory_max_usage_bytes",
+        "Maximum recorded memory usage of the tasks",
+        memory.memory.max_usage,
+    );
+    metrics.gauge(
+        "memory_limit_bytes",
+        "Memory limit of the tasks",
+        memory.memory.limit,
+    );
+    metrics.counter(
+        "memory_failures",
+        "Number of times the memory limit was hit",
+        memory.memory.fail_count,
+    );
+    metrics.gauge(
+        "memory_swap_usage_bytes",
+        "Memory and swap usage of the tasks",
+        memory.memswap.usage,
+    );
+    metrics.gauge(
+        "memory_swap_limit_bytes",
+        "Memory and swap limit of the tasks",
+        memory.memswap.limit,
+    );
+    metrics.gauge(
+        "memory_cache_bytes",
+        "Page cache used by the tasks",
+        memory.cache,
+    );
+
+    metrics.gauge("pids_current", "Number of tasks", stats.pids.current);
+    metrics.gauge(
+        "pids_limit",
+        "Maximum number of tasks, 0 if unlimited",
+        stats.pids.limit,
+    );
+
+    metrics.family(
+        "io_service_bytes",
+        MetricType::Counter,
+        "Bytes transferred from and to block devices",
+        device_samples(&stats.blkio.service_bytes),
+    );
+    metrics.family(
+        "io_serviced",
+        MetricType::Counter,
+        "Number of io operations on block devices",
+        device_samples(&stats.blkio.serviced),
+    );
+
+    metrics.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::{CpuStats, CpuUsage, MemoryData, MemoryStats, PidStats};
+
+    #[test]
+    fn test_render_openmetrics() {
+        let mut stats = Stats {
+            cpu: CpuStats {
+                usage: CpuUsage {
+                    usage_total: 7730,
+                    usage_user: 4387,
+                    usage_kernel: 3343,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            pids: PidStats {
+                current: 3,
+                limit: 100,
+            },
+            memory: MemoryStats {
+                memory: MemoryData {
+                    usage: 4096,
+                    limit: 8192,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        stats.blkio.service_bytes = vec![
+            BlkioDeviceStat {
+                major: 8,
+                minor: 0,
+                op_type: Some("Read".to_owned()),
+                value: 512,
+            },
+            BlkioDeviceStat {
+                major: 8,
+                minor: 0,
+                op_type: Some("Write".to_owned()),
+                value: 1024,
+            },
+        ];
+        stats.blkio.serviced = vec![BlkioDeviceStat {
+            major: 8,
+            minor: 16,
+            op_type: None,
+            value: 2,
+        }];
+
+        let expected = "# TYPE youki_container_cpu_usage counter\n\
+             # HELP youki_container_cpu_usage Cpu time consumed by tasks in total\n\
+             youki_container_cpu_usage_total{id=\"my-container\"} 7730\n\
+             # TYPE youki_container_cpu_user counter\n\
+             # HELP youki_container_cpu_user Cpu time consumed by tasks in user mode\n\
+             youki_container_cpu_user_total{id=\"my-container\"} 4387\n\
+             # TYPE youki_container_cpu_kernel counter\n\
+             # HELP youki_container_cpu_kernel Cpu time consumed by tasks in kernel mode\n\
+             youki_container_cpu_kernel_total{id=\"my-container\"} 3343\n\
+             # TYPE youki_container_cpu_periods counter\n\
+             # HELP youki_container_cpu_periods Number of elapsed enforcement periods\n\
+             youki_container_cpu_periods_total{id=\"my-container\"} 0\n\
+             # TYPE youki_container_cpu_throttled_periods counter\n\
+             # HELP youki_container_cpu_throttled_periods Number of periods in which tasks were throttled\n\
+             youki_container_cpu_throttled_periods_total{id=\"my-container\"} 0\n\
+             # TYPE youki_container_cpu_throttled_time counter\n\
+             # HELP youki_container_cpu_throttled_time Time for which tasks were throttled\n\
+             youki_container_cpu_throttled_time_total{id=\"my-container\"} 0\n\
+             # TYPE youki_container_memory_usage_bytes gauge\n\
+             # HELP youki_container_memory_usage_bytes Memory usage of the tasks\n\
+             youki_container_memory_usage_bytes{id=\"my-container\"} 4096\n\
+             # TYPE youki_container_memory_max_usage_bytes gauge\n\
+             # HELP youki_container_memory_max_usage_bytes Maximum recorded memory usage of the tasks\n\
+             youki_container_memory_max_usage_bytes{id=\"my-container\"} 0\n\
+             # TYPE youki_container_memory_limit_bytes gauge\n\
+             # HELP youki_container_memory_limit_bytes Memory limit of the tasks\n\
+             youki_container_memory_limit_bytes{id=\"my-container\"} 8192\n\
+             # TYPE youki_container_memory_failures counter\n\
+             # HELP youki_container_memory_failures Number of times the memory limit was hit\n\
+             youki_container_memory_failures_total{id=\"my-container\"} 0\n\
+             # TYPE youki_container_memory_swap_usage_bytes gauge\n\
+             # HELP youki_container_memory_swap_usage_bytes Memory and swap usage of the tasks\n\
+             youki_container_memory_swap_usage_bytes{id=\"my-container\"} 0\n\
+             # TYPE youki_container_memory_swap_limit_bytes gauge\n\
+             # HELP youki_container_memory_swap_limit_bytes Memory and swap limit of the tasks\n\
+             youki_container_memory_swap_limit_bytes{id=\"my-container\"} 0\n\
+             # TYPE youki_container_memory_cache_bytes gauge\n\
+             # HELP youki_container_memory_cache_bytes Page cache used by the tasks\n\
+             youki_container_memory_cache_bytes{id=\"my-container\"} 0\n\
+             # TYPE youki_container_pids_current gauge\n\
+             # HELP youki_container_pids_current Number of tasks\n\
+             youki_container_pids_current{id=\"my-container\"} 3\n\
+             # TYPE youki_container_pids_limit gauge\n\
+             # HELP youki_container_pids_limit Maximum number of tasks, 0 if unlimited\n\
+             youki_container_pids_limit{id=\"my-container\"} 100\n\
+             # TYPE youki_container_io_service_bytes counter\n\
+             # HELP youki_container_io_service_bytes Bytes transferred from and to block devices\n\
+             youki_container_io_service_bytes_total{id=\"my-container\",device=\"8:0\",operation=\"read\"} 512\n\
+             youki_container_io_service_bytes_total{id=\"my-container\",device=\"8:0\",operation=\"write\"} 1024\n\
+             # TYPE youki_container_io_serviced counter\n\
+             # HELP youki_container_io_serviced Number of io operations on block devices\n\
+             youki_container_io_serviced_total{id=\"my-container\",device=\"8:16\"} 2\n\
+             # EOF\n";
+        assert_eq!(render_openmetrics("my-container", &stats), expected);
+    }
+
+    #[test]
+    fn test_render_openmetrics_escapes_id() {
+        let text = render_openmetrics("a\"b\\c\nd", &Stats::default());
+        assert!(text.starts_with(
+            "# TYPE youki_container_cpu_usage counter\n\
+             # HELP youki_container_cpu_usage Cpu time consumed by tasks in total\n\
+             youki_container_cpu_usage_total{id=\"a\\\"b\\\\c\\nd\"} 0\n"
+        ));
+        assert!(text.ends_with("Number of io operations on block devices\n# EOF\n"));
+    }
+}
@@ -4,7 +4,7 @@ use anyhow::{bail, Context, Result};
 use dbus::arg::RefArg;
 use oci_spec::runtime::LinuxMemory;
 
-use crate::common::ControllerOpt;
+use crate::common::{self, ControllerOpt};
 
 use super::controller::Controller;
 
@@ -22,10 +22,25 @@ impl Controller for Memory {
         _: u32,
         properties: &mut HashMap<&str, Box<dyn RefArg>>,
     ) -> Result<()> {
-        if let Some(memory) = options.resources.memory() {
+        // the runtime spec has no fields for guaranteed memory and the
+        // throttling limit, they are passed as memory.min and memory.high in
+        // the unified resources instead
+        let unified = options.resources.unified().as_ref();
+        let min = unified.and_then(|unified| unified.get("memory.min"));
+        let high = unified.and_then(|unified| unified.get("memory.high"));
+        if options.resources.memory().is_some() || min.is_some() || high.is_some() {
             log::debug!("applying memory resource restrictions");
-            return Self::apply(memory, properties)
-                .context("could not apply memory resource restrictions");
+            let memory = options.resources.memory().unwrap_or_default();
+            // memory.max of the unified resources limits the memory as well
+            let max = common::effective_memory_max(options.resources)?;
+            return Self::apply(
+                &memory,
+                max,
+                min.map(String::as_str),
+                high.map(String::as_str),
+                properties,
+            )
+            .context("could not apply memory resource restrictions");
         }
 
         Ok(())
@@ -33,7 +48,23 @@ impl Controller for Memory {
 }
 
 impl Memory {
-    fn apply(memory: &LinuxMemory, properties: &mut HashMap<&str, Box<dyn RefArg>>) -> Result<()> {
+    fn apply(
+        memory: &LinuxMemory,
+        max: Option<u64>,
+        min: Option<&str>,
+        high: Option<&str>,
+        properties: &mut HashMap<&str, Box<dyn RefArg>>,
+    ) -> Result<()> {
+        if let Some(min) = min {
+            let min = common::parse_memory_min(min, max)?;
+            properties.insert(MEMORY_MIN, Box::new(min));
+        }
+
+        if let Some(high) = high {
+            let high = common::parse_memory_high(high, max)?;
+            properties.insert(MEMORY_HIGH, Box::new(high.unwrap_or(u64::MAX)));
+        }
+
         if let Some(reservation) = memory.reservation() {
             match reservation {
                 1..=i64::MAX => {
@@ -101,7 +132,7 @@ impl Memory {
 #[cfg(test)]
 mod tests {
     use dbus::arg::ArgType;
-    use oci_spec::runtime::LinuxMemoryBuilder;
+    use oci_spec::runtime::{LinuxMemoryBuilder, LinuxResourcesBuilder};
 
     use super::*;
 
@@ -118,7 +149,7 @@ mod tests {
             let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
 
             // act
-            Memory::apply(&memory, &mut properties).context("apply memory")?;
+            Memory::apply(&memory, None, None, None, &mut properties).context("apply memory")?;
 
             // assert
             assert_eq!(properties.len(), 1);
@@ -144,7 +175,7 @@ mod tests {
             let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
 
             // act
-            Memory::apply(&memory, &mut properties).context("apply memory")?;
+            Memory::apply(&memory, None, None, None, &mut properties).context("apply memory")?;
 
             // assert
             assert_eq!(properties.len(), prop_count);
@@ -156,4 +187,128 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_memory_min() -> Result<()> {
+        let memory = LinuxMemoryBuilder::default()
+            .limit(1073741824)
+            .reservation(268435456)
+            .build()
+            .context("build memory spec")?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        Memory::apply(
+            &memory,
+            Some(1073741824),
+            Some("536870912"),
+            None,
+            &mut properties,
+        )
+        .context("apply memory")?;
+
+        let memory_min = &properties[MEMORY_MIN];
+        assert_eq!(memory_min.arg_type(), ArgType::UInt64);
+        assert_eq!(memory_min.as_u64().unwrap(), 536870912);
+        assert_eq!(properties[MEMORY_LOW].as_u64().unwrap(), 268435456);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_memory_min_exceeding_limit() -> Result<()> {
+        let memory = LinuxMemoryBuilder::default()
+            .limit(268435456)
+            .build()
+            .context("build memory spec")?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        let result = Memory::apply(
+            &memory,
+            Some(268435456),
+            Some("536870912"),
+            None,
+            &mut properties,
+        );
+        assert!(result.is_err());
+        assert!(!properties.contains_key(MEMORY_MIN));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_memory_high() -> Result<()> {
+        let values = vec![
+            ("536870912", 536870912u64),
+            ("max", u64::MAX),
+            ("-1", u64::MAX),
+        ];
+
+        for (high, expected) in values {
+            let memory = LinuxMemoryBuilder::default()
+                .limit(1073741824)
+                .build()
+                .context("build memory spec")?;
+            let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+            Memory::apply(&memory, Some(1073741824), None, Some(high), &mut properties)
+                .context("apply memory")?;
+
+            let memory_high = &properties[MEMORY_HIGH];
+            assert_eq!(memory_high.arg_type(), ArgType::UInt64);
+            assert_eq!(memory_high.as_u64().unwrap(), expected);
+            assert_eq!(properties[MEMORY_MAX].as_u64().unwrap(), 1073741824);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_memory_high_exceeding_limit() -> Result<()> {
+        let memory = LinuxMemoryBuilder::default()
+            .limit(268435456)
+            .build()
+            .context("build memory spec")?;
+        let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+        let result = Memory::apply(
+            &memory,
+            Some(268435456),
+            None,
+            Some("536870912"),
+            &mut properties,
+        );
+        assert!(result.is_err());
+        assert!(!properties.contains_key(MEMORY_HIGH));
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_protection_exceeding_unified_max() -> Result<()> {
+        // memory.max of the unified resources is lower than memory.limit
+        for (min, high) in [("536870912", "268435456"), ("268435456", "536870912")] {
+            let resources = LinuxResourcesBuilder::default()
+                .memory(LinuxMemoryBuilder::default().limit(1073741824).build()?)
+                .unified(HashMap::from([
+                    ("memory.max".to_owned(), "268435456".to_owned()),
+                    ("memory.min".to_owned(), min.to_owned()),
+                    ("memory.high".to_owned(), high.to_owned()),
+                ]))
+                .build()?;
+            let options = ControllerOpt {
+                resources: &resources,
+                disable_oom_killer: false,
+                oom_score_adj: None,
+                freezer_state: None,
+            };
+            let mut properties: HashMap<&str, Box<dyn RefArg>> = HashMap::new();
+
+            let result = <Memory as Controller>::apply(&options, 245, &mut properties);
+            assert!(
+                result.is_err(),
+                "min {} high {} should be rejected",
+                min,
+                high
+            );
+        }
+
+        Ok(())
+    }
 }
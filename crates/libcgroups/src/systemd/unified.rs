@@ -8,7 +8,7 @@ use super::{
     cpuset::{self, to_bitmask},
     memory, pids,
 };
-use crate::common::ControllerOpt;
+use crate::common::{self, ControllerOpt};
 
 pub struct Unified {}
 
@@ -79,14 +79,17 @@ impl Unified {
 
                     properties.insert(systemd_cpuset, Box::new(bitmask));
                 }
-                memory @ ("memory.min" | "memory.low" | "memory.high" | "memory.max") => {
+                "memory.high" => {
+                    let high = common::parse_memory_high(value, None)?;
+                    properties.insert(memory::MEMORY_HIGH, Box::new(high.unwrap_or(u64::MAX)));
+                }
+                memory @ ("memory.min" | "memory.low" | "memory.max") => {
                     let value = value
                         .parse::<u64>()
                         .with_context(|| format!("failed to parse {}: {}", memory, value))?;
                     let systemd_memory = match memory {
                         "memory.min" => memory::MEMORY_MIN,
                         "memory.low" => memory::MEMORY_LOW,
-                        "memory.high" => memory::MEMORY_HIGH,
                         "memory.max" => memory::MEMORY_MAX,
                         file_name => unreachable!("{} was not matched", file_name),
                     };
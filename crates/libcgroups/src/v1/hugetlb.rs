@@ -71,6 +71,21 @@ impl HugeTlb {
             root_path.join(format!("hugetlb.{}.limit_in_bytes", hugetlb.page_size())),
             hugetlb.limit(),
         )?;
+
+        // reservation limits are only available since kernel 5.7, they also
+        // account for pages which are reserved but not faulted in yet
+        let rsvd_file = root_path.join(format!(
+            "hugetlb.{}.rsvd.limit_in_bytes",
+            hugetlb.page_size()
+        ));
+        if rsvd_file.exists() {
+            common::write_cgroup_file(rsvd_file, hugetlb.limit())?;
+        } else {
+            log::warn!(
+                "hugetlb reservation limit for page size {} is not supported, only the usage is limited",
+                hugetlb.page_size()
+            );
+        }
         Ok(())
     }
 
@@ -119,6 +134,37 @@ mod tests {
         HugeTlb::apply(&tmp, &hugetlb).expect("apply hugetlb");
         let content = read_to_string(tmp.join(page_file_name)).expect("Read hugetlb file content");
         assert_eq!(hugetlb.limit().to_string(), content);
+        assert!(!tmp.join("hugetlb.2MB.rsvd.limit_in_bytes").exists());
+    }
+
+    #[test]
+    fn test_set_hugetlb_rsvd() {
+        let tmp = create_temp_dir("test_set_hugetlb_rsvd").expect("create temp directory for test");
+        for file in [
+            "hugetlb.2MB.limit_in_bytes",
+            "hugetlb.2MB.rsvd.limit_in_bytes",
+            "hugetlb.1GB.limit_in_bytes",
+            "hugetlb.1GB.rsvd.limit_in_bytes",
+        ] {
+            set_fixture(&tmp, file, "0").expect("set hugetlb fixture");
+        }
+
+        let hugetlb = LinuxHugepageLimitBuilder::default()
+            .page_size("1GB")
+            .limit(1073741824)
+            .build()
+            .unwrap();
+
+        HugeTlb::apply(&tmp, &hugetlb).expect("apply hugetlb");
+        for (file, limit) in [
+            ("hugetlb.1GB.limit_in_bytes", "1073741824"),
+            ("hugetlb.1GB.rsvd.limit_in_bytes", "1073741824"),
+            ("hugetlb.2MB.limit_in_bytes", "0"),
+            ("hugetlb.2MB.rsvd.limit_in_bytes", "0"),
+        ] {
+            let content = read_to_string(tmp.join(file)).expect("read hugetlb file content");
+            assert_eq!(content, limit, "{}", file);
+        }
     }
 
     #[test]
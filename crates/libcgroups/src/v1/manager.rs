@@ -1,4 +1,3 @@
-use std::fs;
 use std::path::Path;
 use std::time::Duration;
 use std::{collections::HashMap, path::PathBuf};
@@ -17,7 +16,7 @@ use super::{
     perf_event::PerfEvent, pids::Pids, util, Controller,
 };
 
-use crate::common::{self, CgroupManager, ControllerOpt, FreezerState, PathBufExt, CGROUP_PROCS};
+use crate::common::{self, CgroupManager, ControllerOpt, FreezerState, PathBufExt};
 use crate::stats::{Stats, StatsProvider};
 
 pub struct Manager {
@@ -152,15 +151,11 @@ impl CgroupManager for Manager {
         for cgroup_path in &self.subsystems {
             if cgroup_path.1.exists() {
                 log::debug!("remove cgroup {:?}", cgroup_path.1);
-                let procs_path = cgroup_path.1.join(CGROUP_PROCS);
-                let procs = fs::read_to_string(procs_path)?;
-
-                for line in procs.lines() {
-                    let pid: i32 = line.parse()?;
-                    let _ = nix::sys::signal::kill(Pid::from_raw(pid), nix::sys::signal::SIGKILL);
+                for pid in common::get_all_pids(cgroup_path.1)? {
+                    let _ = nix::sys::signal::kill(pid, nix::sys::signal::SIGKILL);
                 }
 
-                common::delete_with_retry(cgroup_path.1, 4, Duration::from_millis(100))?;
+                common::delete_tree_with_retry(cgroup_path.1, 4, Duration::from_millis(100))?;
             }
         }
 
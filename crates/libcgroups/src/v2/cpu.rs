@@ -66,6 +66,18 @@ impl Cpu {
             bail!("realtime is not supported on cgroup v2 yet");
         }
 
+        // The weight of an idle cgroup is fixed by the kernel. The idle flag is
+        // applied first, so that a cgroup which stops being idle can get a weight.
+        if let Some(idle) = cpu.idle() {
+            if !(0..=1).contains(&idle) {
+                bail!("invalid cpu idle value {}, must be 0 or 1", idle);
+            }
+            if idle == 1 && cpu.shares().is_some() {
+                bail!("cpu idle and cpu shares are mutually exclusive");
+            }
+            common::write_cgroup_file(path.join(CGROUP_CPU_IDLE), idle)?;
+        }
+
         if let Some(mut shares) = cpu.shares() {
             shares = Self::convert_shares_to_cgroup2(shares);
             if shares != 0 {
@@ -100,10 +112,6 @@ impl Cpu {
             common::write_cgroup_file(path.join(CGROUP_CPU_BURST), burst)?;
         }
 
-        if let Some(idle) = cpu.idle() {
-            common::write_cgroup_file(path.join(CGROUP_CPU_IDLE), idle)?;
-        }
-
         Ok(())
     }
 
@@ -191,6 +199,46 @@ mod tests {
         assert_eq!(content, format!("{}", IDLE))
     }
 
+    #[test]
+    fn test_set_cpu_idle_without_weight() {
+        let (tmp, idle) = setup("test_set_cpu_idle_without_weight", CGROUP_CPU_IDLE);
+        let weight = set_fixture(&tmp, CGROUP_CPU_WEIGHT, "100").expect("set cpu weight fixture");
+        let cpu = LinuxCpuBuilder::default().idle(1i64).build().unwrap();
+
+        Cpu::apply(&tmp, &cpu).expect("apply cpu");
+
+        assert_eq!(fs::read_to_string(idle).unwrap(), "1");
+        assert_eq!(fs::read_to_string(weight).unwrap(), "100");
+    }
+
+    #[test]
+    fn test_set_cpu_idle_with_weight() {
+        let (tmp, idle) = setup("test_set_cpu_idle_with_weight", CGROUP_CPU_IDLE);
+        let cpu = LinuxCpuBuilder::default()
+            .idle(1i64)
+            .shares(1024u64)
+            .build()
+            .unwrap();
+
+        let err = Cpu::apply(&tmp, &cpu).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cpu idle and cpu shares are mutually exclusive"
+        );
+        assert_eq!(fs::read_to_string(&idle).unwrap(), "");
+
+        // a cgroup which is not idle can have a weight
+        let cpu = LinuxCpuBuilder::default()
+            .idle(0i64)
+            .shares(1024u64)
+            .build()
+            .unwrap();
+        let weight = set_fixture(&tmp, CGROUP_CPU_WEIGHT, "").expect("set cpu weight fixture");
+        Cpu::apply(&tmp, &cpu).expect("apply cpu");
+        assert_eq!(fs::read_to_string(idle).unwrap(), "0");
+        assert_eq!(fs::read_to_string(weight).unwrap(), "39");
+    }
+
     #[test]
     fn test_set_positive_quota() {
         // arrange
@@ -5,7 +5,7 @@ use std::{
     time::Duration,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
 use nix::unistd::Pid;
 
@@ -53,12 +53,8 @@ impl Manager {
     }
 
     fn create_unified_cgroup(&self, pid: Pid) -> Result<()> {
-        let controllers: Vec<String> = util::get_available_controllers(&self.root_path)?
-            .iter()
-            .map(|c| format!("{}{}", "+", c))
-            .collect();
-
-        Self::write_controllers(&self.root_path, &controllers)?;
+        let controllers = util::get_available_controllers(&self.root_path)?;
+        Self::enable_controllers(&self.root_path, &controllers)?;
 
         let mut current_path = self.root_path.clone();
         let mut components = self
@@ -75,8 +71,14 @@ impl Manager {
 
             // last component cannot have subtree_control enabled due to internal process constraint
             // if this were set, writing to the cgroups.procs file will fail with Erno 16 (device or resource busy)
+            // for the same reason a populated cgroup, e.g. the cgroup of a container whose sub-cgroup is
+            // joined, cannot enable controllers, its children inherit the ones it has enabled already
             if components.peek().is_some() {
-                Self::write_controllers(&current_path, &controllers)?;
+                if Self::has_processes(&current_path)? {
+                    Self::check_inherited_controllers(&current_path)?;
+                } else {
+                    Self::enable_controllers(&current_path, &controllers)?;
+                }
             }
         }
 
@@ -84,13 +86,54 @@ impl Manager {
         Ok(())
     }
 
-    fn write_controllers(path: &Path, controllers: &[String]) -> Result<()> {
-        for controller in controllers {
-            common::write_cgroup_file_str(path.join(CGROUP_SUBTREE_CONTROL), controller)?;
+    fn has_processes(path: &Path) -> Result<bool> {
+        let procs = path.join(CGROUP_PROCS);
+        if !procs.exists() {
+            return Ok(false);
+        }
+
+        Ok(!common::read_cgroup_file(procs)?.trim().is_empty())
+    }
+
+    // The children of a populated cgroup only get the controllers which it has
+    // enabled before it was populated. A child without any controller would be
+    // a cgroup in name only, so it is rejected.
+    fn check_inherited_controllers(path: &Path) -> Result<()> {
+        let enabled = common::read_cgroup_file(path.join(CGROUP_SUBTREE_CONTROL))?;
+        if enabled.trim().is_empty() {
+            bail!(
+                "cannot create a cgroup with controllers below {:?}, which contains processes, but has no controllers enabled for its children",
+                path
+            );
         }
 
         Ok(())
     }
+
+    // Enables the controllers for the children of the cgroup, as far as they
+    // are available in the cgroup. Controllers are only ever added, the ones
+    // which are enabled already, possibly for other children, are kept.
+    fn enable_controllers(path: &Path, controllers: &[ControllerType]) -> Result<()> {
+        let available = util::get_available_controllers(path)?;
+        let subtree_control = path.join(CGROUP_SUBTREE_CONTROL);
+        let enabled = common::read_cgroup_file(&subtree_control)?;
+        let enabled: Vec<_> = enabled.split_whitespace().collect();
+
+        let mut enable = Vec::new();
+        for controller in controllers {
+            if !available.contains(controller) {
+                log::debug!("controller {} is not available in {:?}", controller, path);
+            } else if !enabled.contains(&controller.to_string().as_str()) {
+                enable.push(format!("+{}", controller));
+            }
+        }
+        if enable.is_empty() {
+            return Ok(());
+        }
+
+        common::write_cgroup_file_str(&subtree_control, &enable.join(" "))
+            .with_context(|| format!("failed to enable controllers in {:?}", path))
+    }
 }
 
 impl CgroupManager for Manager {
@@ -119,7 +162,7 @@ impl CgroupManager for Manager {
                 Unified::apply(
                     controller_opt,
                     &self.full_path,
-                    util::get_available_controllers(&self.root_path)?,
+                    util::get_available_controllers(&self.full_path)?,
                 )?;
             }
         }
@@ -134,16 +177,12 @@ impl CgroupManager for Manager {
             if kill_file.exists() {
                 fs::write(kill_file, "1").context("failed to kill cgroup")?;
             } else {
-                let procs_path = self.full_path.join(CGROUP_PROCS);
-                let procs = fs::read_to_string(procs_path)?;
-
-                for line in procs.lines() {
-                    let pid: i32 = line.parse()?;
-                    let _ = nix::sys::signal::kill(Pid::from_raw(pid), nix::sys::signal::SIGKILL);
+                for pid in common::get_all_pids(&self.full_path)? {
+                    let _ = nix::sys::signal::kill(pid, nix::sys::signal::SIGKILL);
                 }
             }
 
-            common::delete_with_retry(&self.full_path, 4, Duration::from_millis(100))?;
+            common::delete_tree_with_retry(&self.full_path, 4, Duration::from_millis(100))?;
         }
 
         Ok(())
@@ -180,3 +219,97 @@ impl CgroupManager for Manager {
         common::get_all_pids(&self.full_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{create_temp_dir, set_fixture};
+    use crate::v2::util::CGROUP_CONTROLLERS;
+
+    #[test]
+    fn test_add_task_to_sub_cgroup_of_populated_cgroup() -> Result<()> {
+        let tmp = create_temp_dir("test_add_task_to_sub_cgroup_of_populated_cgroup")?;
+        set_fixture(&tmp, "cgroup.controllers", "cpu memory")?;
+        let root_subtree_control = set_fixture(&tmp, CGROUP_SUBTREE_CONTROL, "")?;
+        let container = tmp.join("container");
+        fs::create_dir(&container)?;
+        set_fixture(&container, CGROUP_PROCS, "1")?;
+        // the controllers were enabled before the cgroup was populated
+        let container_subtree_control = set_fixture(&container, CGROUP_SUBTREE_CONTROL, "cpu")?;
+        let sub_cgroup = container.join("debug");
+        fs::create_dir(&sub_cgroup)?;
+        let sub_cgroup_procs = set_fixture(&sub_cgroup, CGROUP_PROCS, "")?;
+
+        let manager = Manager::new(tmp.to_path_buf(), PathBuf::from("container/debug"))?;
+        manager.add_task(Pid::from_raw(1000))?;
+
+        assert_eq!(fs::read_to_string(root_subtree_control)?, "+cpu +memory");
+        // the sub-cgroup inherits the controllers of the container cgroup,
+        // which can not be changed anymore
+        assert_eq!(fs::read_to_string(container_subtree_control)?, "cpu");
+        assert_eq!(fs::read_to_string(sub_cgroup_procs)?, "1000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_task_to_sub_cgroup_without_controllers() -> Result<()> {
+        let tmp = create_temp_dir("test_add_task_to_sub_cgroup_without_controllers")?;
+        set_fixture(&tmp, "cgroup.controllers", "cpu memory")?;
+        set_fixture(&tmp, CGROUP_SUBTREE_CONTROL, "")?;
+        let container = tmp.join("container");
+        fs::create_dir(&container)?;
+        set_fixture(&container, CGROUP_PROCS, "1")?;
+        let container_subtree_control = set_fixture(&container, CGROUP_SUBTREE_CONTROL, "")?;
+
+        let manager = Manager::new(tmp.to_path_buf(), PathBuf::from("container/debug"))?;
+        let err = manager.add_task(Pid::from_raw(1000)).unwrap_err();
+        assert!(
+            err.to_string().contains("no controllers enabled"),
+            "{}",
+            err
+        );
+        assert_eq!(fs::read_to_string(container_subtree_control)?, "");
+        assert!(!container.join("debug").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_task_enables_controllers_down_the_hierarchy() -> Result<()> {
+        let tmp = create_temp_dir("test_add_task_enables_controllers_down_the_hierarchy")?;
+        set_fixture(&tmp, CGROUP_CONTROLLERS, "cpu io memory pids")?;
+        let root_subtree_control = set_fixture(&tmp, CGROUP_SUBTREE_CONTROL, "")?;
+        // another cgroup below the parent relies on memory already
+        let parent = tmp.join("parent");
+        fs::create_dir(&parent)?;
+        set_fixture(&parent, CGROUP_CONTROLLERS, "cpu io memory pids")?;
+        let parent_subtree_control = set_fixture(&parent, CGROUP_SUBTREE_CONTROL, "memory")?;
+        // io is not delegated further than the parent
+        let child = parent.join("child");
+        fs::create_dir(&child)?;
+        set_fixture(&child, CGROUP_CONTROLLERS, "cpu memory pids")?;
+        let child_subtree_control = set_fixture(&child, CGROUP_SUBTREE_CONTROL, "")?;
+        let container = child.join("container");
+        fs::create_dir(&container)?;
+        let container_subtree_control = set_fixture(&container, CGROUP_SUBTREE_CONTROL, "")?;
+        let container_procs = set_fixture(&container, CGROUP_PROCS, "")?;
+
+        let manager = Manager::new(tmp.to_path_buf(), PathBuf::from("parent/child/container"))?;
+        manager.add_task(Pid::from_raw(1000))?;
+
+        assert_eq!(
+            fs::read_to_string(root_subtree_control)?,
+            "+cpu +io +memory +pids"
+        );
+        assert_eq!(
+            fs::read_to_string(parent_subtree_control)?,
+            "+cpu +io +pids"
+        );
+        assert_eq!(
+            fs::read_to_string(child_subtree_control)?,
+            "+cpu +memory +pids"
+        );
+        assert_eq!(fs::read_to_string(container_subtree_control)?, "");
+        assert_eq!(fs::read_to_string(container_procs)?, "1000");
+        Ok(())
+    }
+}
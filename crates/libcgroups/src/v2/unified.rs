@@ -1,10 +1,15 @@
 use std::{collections::HashMap, path::Path};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
 use super::controller_type::ControllerType;
 use crate::common::{self, ControllerOpt};
 
+const CGROUP_CPU_UCLAMP_MIN: &str = "cpu.uclamp.min";
+const CGROUP_CPU_UCLAMP_MAX: &str = "cpu.uclamp.max";
+const CGROUP_MEMORY_MIN: &str = "memory.min";
+const CGROUP_MEMORY_HIGH: &str = "memory.high";
+
 pub struct Unified {}
 
 impl Unified {
@@ -14,7 +19,9 @@ impl Unified {
         controllers: Vec<ControllerType>,
     ) -> Result<()> {
         if let Some(unified) = &controller_opt.resources.unified() {
-            Self::apply_impl(unified, cgroup_path, &controllers)
+            // memory.max of the unified resources limits the memory as well
+            let memory_max = common::effective_memory_max(controller_opt.resources)?;
+            Self::apply_impl(unified, memory_max, cgroup_path, &controllers)
                 .context("failed to apply unified resource restrictions")?;
         }
 
@@ -23,37 +30,77 @@ impl Unified {
 
     fn apply_impl(
         unified: &HashMap<String, String>,
+        memory_max: Option<u64>,
         cgroup_path: &Path,
         controllers: &[ControllerType],
     ) -> Result<()> {
-        {
-            log::debug!("Apply unified cgroup config");
-            for (cgroup_file, value) in unified {
-                common::write_cgroup_file_str(cgroup_path.join(cgroup_file), value).map_err(
-                    |e| {
-                        let (subsystem, _) = cgroup_file
-                            .split_once('.')
-                            .with_context(|| {
-                                format!("failed to split {} with {}", cgroup_file, ".")
-                            })
-                            .unwrap();
-                        let context = if !controllers.iter().any(|c| c.to_string() == subsystem) {
-                            format!(
-                                "failed to set {} to {}: subsystem {} is not available",
-                                cgroup_file, value, subsystem
-                            )
-                        } else {
-                            format!("failed to set {} to {}: {}", cgroup_file, value, e)
-                        };
-
-                        e.context(context)
-                    },
-                )?;
-            }
+        log::debug!("Apply unified cgroup config");
+        // validate all entries first, so that nothing is written if any of them is invalid
+        let mut values = Vec::with_capacity(unified.len());
+        for (cgroup_file, value) in unified {
+            Self::validate_key(cgroup_file, controllers)?;
+            let value = Self::validate_value(cgroup_file, value, memory_max)?;
+            values.push((cgroup_file, value));
+        }
+
+        for (cgroup_file, value) in values {
+            common::write_cgroup_file_str(cgroup_path.join(cgroup_file), &value)
+                .with_context(|| format!("failed to set {} to {}", cgroup_file, value))?;
+        }
+
+        Ok(())
+    }
+
+    // Keys have the form <controller>.<file>, the core interface files prefixed with
+    // "cgroup." are always present and do not require a controller to be enabled.
+    fn validate_key(cgroup_file: &str, controllers: &[ControllerType]) -> Result<()> {
+        if cgroup_file.contains('/') {
+            bail!(
+                "invalid unified resource {}: must not contain '/'",
+                cgroup_file
+            );
+        }
+
+        let (subsystem, _) = cgroup_file.split_once('.').with_context(|| {
+            format!(
+                "invalid unified resource {}: expected <controller>.<file>",
+                cgroup_file
+            )
+        })?;
+
+        if subsystem != "cgroup" && !controllers.iter().any(|c| c.to_string() == subsystem) {
+            bail!(
+                "failed to set unified resource {}: subsystem {} is not enabled",
+                cgroup_file,
+                subsystem
+            );
         }
 
         Ok(())
     }
+
+    // Returns the value which has to be written to the cgroup file.
+    fn validate_value(cgroup_file: &str, value: &str, memory_max: Option<u64>) -> Result<String> {
+        if cgroup_file == CGROUP_CPU_UCLAMP_MIN || cgroup_file == CGROUP_CPU_UCLAMP_MAX {
+            common::validate_uclamp(value)
+                .with_context(|| format!("invalid value for {}", cgroup_file))?;
+        }
+
+        if cgroup_file == CGROUP_MEMORY_MIN {
+            return Ok(common::parse_memory_min(value, memory_max)?.to_string());
+        }
+
+        if cgroup_file == CGROUP_MEMORY_HIGH {
+            // the kernel only accepts max for unlimited, -1 is accepted for
+            // consistency with the memory limits of the runtime spec
+            return match common::parse_memory_high(value, memory_max)? {
+                Some(high) => Ok(high.to_string()),
+                None => Ok("max".to_owned()),
+            };
+        }
+
+        Ok(value.to_owned())
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +108,7 @@ mod tests {
     use std::collections::HashMap;
     use std::fs;
 
-    use oci_spec::runtime::LinuxResourcesBuilder;
+    use oci_spec::runtime::{LinuxMemoryBuilder, LinuxResources, LinuxResourcesBuilder};
 
     use crate::test::{create_temp_dir, set_fixture};
     use crate::v2::controller_type::ControllerType;
@@ -98,7 +145,12 @@ mod tests {
         };
 
         // act
-        Unified::apply(&controller_opt, &tmp, vec![]).expect("apply unified");
+        Unified::apply(
+            &controller_opt,
+            &tmp,
+            vec![ControllerType::HugeTlb, ControllerType::Cpu],
+        )
+        .expect("apply unified");
 
         // assert
         let hugetlb_limit = fs::read_to_string(hugetlb_limit_path).expect("read hugetlb limit");
@@ -179,4 +231,275 @@ mod tests {
         // assert
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_set_unified_multiple_entries() {
+        // arrange
+        let tmp = create_temp_dir("test_set_unified_multiple_entries").unwrap();
+        let memory_high_path = set_fixture(&tmp, "memory.high", "").unwrap();
+        let pids_max_path = set_fixture(&tmp, "pids.max", "").unwrap();
+        let io_weight_path = set_fixture(&tmp, "io.weight", "").unwrap();
+        let max_depth_path = set_fixture(&tmp, "cgroup.max.depth", "").unwrap();
+
+        let unified = HashMap::from([
+            ("memory.high".to_owned(), "1073741824".to_owned()),
+            ("pids.max".to_owned(), "128".to_owned()),
+            ("io.weight".to_owned(), "default 200".to_owned()),
+            ("cgroup.max.depth".to_owned(), "5".to_owned()),
+        ]);
+        let resources = LinuxResourcesBuilder::default()
+            .unified(unified)
+            .build()
+            .unwrap();
+        let controller_opt = ControllerOpt {
+            resources: &resources,
+            freezer_state: None,
+            oom_score_adj: None,
+            disable_oom_killer: false,
+        };
+
+        // act
+        Unified::apply(
+            &controller_opt,
+            &tmp,
+            vec![
+                ControllerType::Memory,
+                ControllerType::Pids,
+                ControllerType::Io,
+            ],
+        )
+        .expect("apply unified");
+
+        // assert
+        assert_eq!(fs::read_to_string(memory_high_path).unwrap(), "1073741824");
+        assert_eq!(fs::read_to_string(pids_max_path).unwrap(), "128");
+        assert_eq!(fs::read_to_string(io_weight_path).unwrap(), "default 200");
+        assert_eq!(fs::read_to_string(max_depth_path).unwrap(), "5");
+    }
+
+    #[test]
+    fn test_set_unified_controller_not_enabled() {
+        // arrange
+        let tmp = create_temp_dir("test_set_unified_controller_not_enabled").unwrap();
+        let memory_high_path = set_fixture(&tmp, "memory.high", "").unwrap();
+        let cpu_weight_path = set_fixture(&tmp, "cpu.weight", "").unwrap();
+
+        let unified = HashMap::from([
+            ("memory.high".to_owned(), "1073741824".to_owned()),
+            ("cpu.weight".to_owned(), "5000".to_owned()),
+        ]);
+        let resources = LinuxResourcesBuilder::default()
+            .unified(unified)
+            .build()
+            .unwrap();
+        let controller_opt = ControllerOpt {
+            resources: &resources,
+            freezer_state: None,
+            oom_score_adj: None,
+            disable_oom_killer: false,
+        };
+
+        // act
+        let result = Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory]);
+
+        // assert
+        let err = format!("{:?}", result.expect_err("cpu controller is not enabled"));
+        assert!(err.contains("cpu.weight"), "{}", err);
+        // nothing is written if any of the entries is invalid
+        assert_eq!(fs::read_to_string(memory_high_path).unwrap(), "");
+        assert_eq!(fs::read_to_string(cpu_weight_path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_set_unified_invalid_key() {
+        let tmp = create_temp_dir("test_set_unified_invalid_key").unwrap();
+        for key in ["memory", "../memory.high"] {
+            let unified = HashMap::from([(key.to_owned(), "1".to_owned())]);
+            let resources = LinuxResourcesBuilder::default()
+                .unified(unified)
+                .build()
+                .unwrap();
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            let result = Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory]);
+            assert!(result.is_err(), "{} should be rejected", key);
+        }
+    }
+
+    #[test]
+    fn test_set_unified_uclamp() {
+        for (min, max) in [("0", "max"), ("10.5", "80"), ("20.25", "100")] {
+            // cgroup files are not truncated when written, so use new fixtures each time
+            let tmp = create_temp_dir("test_set_unified_uclamp").unwrap();
+            let uclamp_min_path = set_fixture(&tmp, CGROUP_CPU_UCLAMP_MIN, "").unwrap();
+            let uclamp_max_path = set_fixture(&tmp, CGROUP_CPU_UCLAMP_MAX, "").unwrap();
+            let unified = HashMap::from([
+                (CGROUP_CPU_UCLAMP_MIN.to_owned(), min.to_owned()),
+                (CGROUP_CPU_UCLAMP_MAX.to_owned(), max.to_owned()),
+            ]);
+            let resources = LinuxResourcesBuilder::default()
+                .unified(unified)
+                .build()
+                .unwrap();
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            Unified::apply(&controller_opt, &tmp, vec![ControllerType::Cpu]).expect("apply uclamp");
+
+            assert_eq!(fs::read_to_string(&uclamp_min_path).unwrap(), min);
+            assert_eq!(fs::read_to_string(&uclamp_max_path).unwrap(), max);
+        }
+    }
+
+    #[test]
+    fn test_set_unified_uclamp_out_of_range() {
+        let tmp = create_temp_dir("test_set_unified_uclamp_out_of_range").unwrap();
+        let uclamp_min_path = set_fixture(&tmp, CGROUP_CPU_UCLAMP_MIN, "").unwrap();
+
+        for value in ["-1", "100.01", "101", "10.125", "min", ""] {
+            let unified = HashMap::from([(CGROUP_CPU_UCLAMP_MIN.to_owned(), value.to_owned())]);
+            let resources = LinuxResourcesBuilder::default()
+                .unified(unified)
+                .build()
+                .unwrap();
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            let result = Unified::apply(&controller_opt, &tmp, vec![ControllerType::Cpu]);
+            assert!(result.is_err(), "{:?} should be rejected", value);
+            assert_eq!(fs::read_to_string(&uclamp_min_path).unwrap(), "");
+        }
+    }
+
+    fn memory_high_opt(high: &str, limit: Option<i64>) -> LinuxResources {
+        let mut builder = LinuxResourcesBuilder::default().unified(HashMap::from([(
+            CGROUP_MEMORY_HIGH.to_owned(),
+            high.to_owned(),
+        )]));
+        if let Some(limit) = limit {
+            builder = builder.memory(LinuxMemoryBuilder::default().limit(limit).build().unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_set_memory_high() {
+        let tmp = create_temp_dir("test_set_memory_high").unwrap();
+
+        for (high, limit, expected) in [
+            ("536870912", Some(1073741824), "536870912"),
+            ("1073741824", Some(1073741824), "1073741824"),
+            ("536870912", Some(-1), "536870912"),
+            ("536870912", None, "536870912"),
+        ] {
+            let memory_high_path = set_fixture(&tmp, CGROUP_MEMORY_HIGH, "").unwrap();
+            let resources = memory_high_opt(high, limit);
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory])
+                .expect("apply memory.high");
+            assert_eq!(fs::read_to_string(&memory_high_path).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_set_memory_high_unlimited() {
+        let tmp = create_temp_dir("test_set_memory_high_unlimited").unwrap();
+        let memory_high_path = set_fixture(&tmp, CGROUP_MEMORY_HIGH, "").unwrap();
+
+        for high in ["max", "-1"] {
+            let resources = memory_high_opt(high, Some(1073741824));
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory])
+                .expect("apply memory.high");
+            assert_eq!(fs::read_to_string(&memory_high_path).unwrap(), "max");
+        }
+    }
+
+    #[test]
+    fn test_set_memory_high_invalid() {
+        let tmp = create_temp_dir("test_set_memory_high_invalid").unwrap();
+        let memory_high_path = set_fixture(&tmp, CGROUP_MEMORY_HIGH, "").unwrap();
+
+        for (high, limit) in [("1073741825", Some(1073741824)), ("-2", None), ("1G", None)] {
+            let resources = memory_high_opt(high, limit);
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            let result = Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory]);
+            assert!(result.is_err(), "{} should be rejected", high);
+            assert_eq!(fs::read_to_string(&memory_high_path).unwrap(), "");
+        }
+    }
+
+    #[test]
+    fn test_memory_protection_exceeding_unified_max() {
+        let tmp = create_temp_dir("test_memory_protection_exceeding_unified_max").unwrap();
+        let memory_max_path = set_fixture(&tmp, "memory.max", "").unwrap();
+        let memory_min_path = set_fixture(&tmp, CGROUP_MEMORY_MIN, "").unwrap();
+        let memory_high_path = set_fixture(&tmp, CGROUP_MEMORY_HIGH, "").unwrap();
+
+        // memory.max of the unified resources is lower than memory.limit
+        for (min, high) in [("536870912", "268435456"), ("268435456", "536870912")] {
+            let resources = LinuxResourcesBuilder::default()
+                .memory(
+                    LinuxMemoryBuilder::default()
+                        .limit(1073741824)
+                        .build()
+                        .unwrap(),
+                )
+                .unified(HashMap::from([
+                    ("memory.max".to_owned(), "268435456".to_owned()),
+                    (CGROUP_MEMORY_MIN.to_owned(), min.to_owned()),
+                    (CGROUP_MEMORY_HIGH.to_owned(), high.to_owned()),
+                ]))
+                .build()
+                .unwrap();
+            let controller_opt = ControllerOpt {
+                resources: &resources,
+                freezer_state: None,
+                oom_score_adj: None,
+                disable_oom_killer: false,
+            };
+
+            let result = Unified::apply(&controller_opt, &tmp, vec![ControllerType::Memory]);
+            assert!(
+                result.is_err(),
+                "min {} high {} should be rejected",
+                min,
+                high
+            );
+            assert_eq!(fs::read_to_string(&memory_max_path).unwrap(), "");
+            assert_eq!(fs::read_to_string(&memory_min_path).unwrap(), "");
+            assert_eq!(fs::read_to_string(&memory_high_path).unwrap(), "");
+        }
+    }
 }
@@ -0,0 +1,503 @@
+//! Fallback for settings passed via annotations.
+//!
+//! Some orchestrators pass the selinux label and apparmor profile of the
+//! container process as annotations instead of setting the dedicated fields of
+//! the runtime spec. These are applied when the dedicated fields are empty.
+//!
+//! The runtime spec has no fields for the utilization clamps of the cpu
+//! controller, these are passed as annotations and applied as unified
+//! resources on cgroup v2, they are rejected with the systemd cgroup manager
+//! and on cgroup v1. The same goes for the hard memory guarantee, which
+//! becomes `memory.min`, whereas the reservation is only a best-effort
+//! `memory.low`, and for the throttling limit below the memory limit, which
+//! becomes `memory.high`.
+//!
+//! An existing absolute cgroups path of the spec is only joined instead of
+//! created if the annotation asks for it.
+use anyhow::{bail, Context, Result};
+use libcgroups::common::{self, CgroupSetup};
+use oci_spec::runtime::{LinuxResources, Spec};
+use std::collections::HashMap;
+
+/// Annotation carrying the selinux label of the container process.
+pub const SELINUX_LABEL_ANNOTATION: &str = "org.opencontainers.selinux";
+/// Annotation carrying the apparmor profile of the container process.
+pub const APPARMOR_PROFILE_ANNOTATION: &str = "org.opencontainers.apparmor";
+
+/// Annotation carrying the minimum utilization clamp of the container in percent.
+pub const CPU_UCLAMP_MIN_ANNOTATION: &str = "org.youki.cpu.uclamp.min";
+/// Annotation carrying the maximum utilization clamp of the container in percent.
+pub const CPU_UCLAMP_MAX_ANNOTATION: &str = "org.youki.cpu.uclamp.max";
+/// Annotation carrying the memory in bytes which is guaranteed to the container.
+pub const MEMORY_MIN_ANNOTATION: &str = "org.youki.memory.min";
+/// Annotation carrying the memory in bytes above which the container is throttled.
+pub const MEMORY_HIGH_ANNOTATION: &str = "org.youki.memory.high";
+/// Annotation asking to join the cgroup of the cgroups path if it exists already.
+pub const JOIN_CGROUP_ANNOTATION: &str = "org.youki.cgroup.join";
+
+/// Fills `process.selinuxLabel` and `process.apparmorProfile` from the
+/// annotations of the spec if they are not set. A value which is set in the
+/// field as well as in the annotation must be the same.
+pub fn apply_security_labels(spec: &mut Spec) -> Result<()> {
+    let annotations = match spec.annotations() {
+        Some(annotations) => annotations.clone(),
+        None => return Ok(()),
+    };
+    let mut process = match spec.process() {
+        Some(process) => process.clone(),
+        None => return Ok(()),
+    };
+
+    if let Some(label) = resolve_label(
+        process.selinux_label().as_deref(),
+        &annotations,
+        SELINUX_LABEL_ANNOTATION,
+    )? {
+        process.set_selinux_label(Some(label));
+    }
+
+    if let Some(profile) = resolve_label(
+        process.apparmor_profile().as_deref(),
+        &annotations,
+        APPARMOR_PROFILE_ANNOTATION,
+    )? {
+        process.set_apparmor_profile(Some(profile));
+    }
+
+    spec.set_process(Some(process));
+    Ok(())
+}
+
+/// Adds the utilization clamps from the annotations of the spec to
+/// `linux.resources.unified`. A clamp which is set in the unified resources as
+/// well as in the annotation must be the same. The clamps are validated here,
+/// as they are only applied by the cgroup v2 manager, so the annotations are
+/// rejected with the systemd cgroup manager and on cgroup v1.
+pub fn apply_cpu_uclamp(spec: &mut Spec, use_systemd: bool) -> Result<()> {
+    if !has_any(
+        spec,
+        &[CPU_UCLAMP_MIN_ANNOTATION, CPU_UCLAMP_MAX_ANNOTATION],
+    ) {
+        return Ok(());
+    }
+
+    let setup = common::get_cgroup_setup().context("failed to determine the cgroup setup")?;
+    apply_cpu_uclamp_with(spec, use_systemd, &setup)
+}
+
+fn apply_cpu_uclamp_with(spec: &mut Spec, use_systemd: bool, setup: &CgroupSetup) -> Result<()> {
+    let annotations = spec.annotations().clone().unwrap_or_default();
+    for annotation in [CPU_UCLAMP_MIN_ANNOTATION, CPU_UCLAMP_MAX_ANNOTATION] {
+        if let Some(value) = annotations.get(annotation) {
+            common::validate_uclamp(value)
+                .with_context(|| format!("invalid value for annotation {}", annotation))?;
+        }
+    }
+    if use_systemd {
+        bail!("cpu utilization clamps are not supported with the systemd cgroup manager");
+    }
+    if !matches!(setup, CgroupSetup::Unified) {
+        bail!("cpu utilization clamps are not supported on cgroup v1");
+    }
+
+    apply_unified(
+        spec,
+        &[
+            (CPU_UCLAMP_MIN_ANNOTATION, "cpu.uclamp.min"),
+            (CPU_UCLAMP_MAX_ANNOTATION, "cpu.uclamp.max"),
+        ],
+    )
+}
+
+/// Adds the guaranteed memory from the annotations of the spec to
+/// `linux.resources.unified` as `memory.min`. A value which is set in the
+/// unified resources as well as in the annotation must be the same. The value
+/// must not exceed the memory limit, from either `memory.limit` or
+/// `memory.max` of the unified resources.
+pub fn apply_memory_min(spec: &mut Spec) -> Result<()> {
+    apply_unified(spec, &[(MEMORY_MIN_ANNOTATION, "memory.min")])?;
+    if let Some((min, resources)) = annotated_unified(spec, MEMORY_MIN_ANNOTATION, "memory.min") {
+        common::parse_memory_min(min, common::effective_memory_max(resources)?)?;
+    }
+    Ok(())
+}
+
+/// Adds the throttling limit from the annotations of the spec to
+/// `linux.resources.unified` as `memory.high`. A value which is set in the
+/// unified resources as well as in the annotation must be the same. The value
+/// must not exceed the memory limit, from either `memory.limit` or
+/// `memory.max` of the unified resources.
+pub fn apply_memory_high(spec: &mut Spec) -> Result<()> {
+    apply_unified(spec, &[(MEMORY_HIGH_ANNOTATION, "memory.high")])?;
+    if let Some((high, resources)) = annotated_unified(spec, MEMORY_HIGH_ANNOTATION, "memory.high")
+    {
+        common::parse_memory_high(high, common::effective_memory_max(resources)?)?;
+    }
+    Ok(())
+}
+
+/// Returns if the annotations of the spec ask to join an existing cgroup
+/// instead of creating it. The annotation is either "true" or "false".
+pub fn join_cgroup(spec: &Spec) -> Result<bool> {
+    let value = spec
+        .annotations()
+        .as_ref()
+        .and_then(|annotations| annotations.get(JOIN_CGROUP_ANNOTATION));
+    match value.map(String::as_str) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(value) => bail!(
+            "annotation {} must be either true or false, found {:?}",
+            JOIN_CGROUP_ANNOTATION,
+            value
+        ),
+    }
+}
+
+fn has_any(spec: &Spec, annotations: &[&str]) -> bool {
+    spec.annotations().as_ref().map_or(false, |a| {
+        annotations.iter().any(|&key| a.contains_key(key))
+    })
+}
+
+// Returns the unified resource which was filled from the annotation, together
+// with the resources it is part of.
+fn annotated_unified<'a>(
+    spec: &'a Spec,
+    annotation: &str,
+    cgroup_file: &str,
+) -> Option<(&'a str, &'a LinuxResources)> {
+    if !has_any(spec, &[annotation]) {
+        return None;
+    }
+    let resources = spec.linux().as_ref()?.resources().as_ref()?;
+    let value = resources.unified().as_ref()?.get(cgroup_file)?;
+    Some((value, resources))
+}
+
+fn apply_unified(spec: &mut Spec, cgroup_files: &[(&str, &str)]) -> Result<()> {
+    let annotations = match spec.annotations() {
+        Some(annotations) => annotations.clone(),
+        None => return Ok(()),
+    };
+    let mut linux = match spec.linux() {
+        Some(linux) => linux.clone(),
+        None => return Ok(()),
+    };
+
+    let mut resources = linux.resources().clone().unwrap_or_default();
+    let mut unified = resources.unified().clone().unwrap_or_default();
+    let mut changed = false;
+    for &(annotation, cgroup_file) in cgroup_files {
+        if let Some(value) = resolve_label(
+            unified.get(cgroup_file).map(String::as_str),
+            &annotations,
+            annotation,
+        )? {
+            unified.insert(cgroup_file.to_owned(), value);
+            changed = true;
+        }
+    }
+
+    if changed {
+        resources.set_unified(Some(unified));
+        linux.set_resources(Some(resources));
+        spec.set_linux(Some(linux));
+    }
+    Ok(())
+}
+
+// Returns the value of the annotation if the field has to be filled from it.
+fn resolve_label(
+    field: Option<&str>,
+    annotations: &HashMap<String, String>,
+    annotation: &str,
+) -> Result<Option<String>> {
+    let field = field.filter(|f| !f.is_empty());
+    let value = annotations
+        .get(annotation)
+        .map(String::as_str)
+        .filter(|v| !v.is_empty());
+
+    match (field, value) {
+        (Some(field), Some(value)) if field != value => bail!(
+            "annotation {} with value {:?} conflicts with {:?} set in the runtime spec",
+            annotation,
+            value,
+            field
+        ),
+        (None, Some(value)) => {
+            log::info!("using {:?} from annotation {}", value, annotation);
+            Ok(Some(value.to_owned()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::{LinuxBuilder, LinuxResourcesBuilder, ProcessBuilder, SpecBuilder};
+
+    fn spec_with(
+        selinux_label: Option<&str>,
+        apparmor_profile: Option<&str>,
+        annotations: &[(&str, &str)],
+    ) -> Result<Spec> {
+        let mut process = ProcessBuilder::default().build()?;
+        process.set_selinux_label(selinux_label.map(String::from));
+        process.set_apparmor_profile(apparmor_profile.map(String::from));
+        let annotations = annotations
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        Ok(SpecBuilder::default()
+            .process(process)
+            .annotations(annotations)
+            .build()?)
+    }
+
+    #[test]
+    fn test_annotation_only() -> Result<()> {
+        let mut spec = spec_with(
+            None,
+            None,
+            &[
+                (SELINUX_LABEL_ANNOTATION, "system_u:system_r:container_t:s0"),
+                (APPARMOR_PROFILE_ANNOTATION, "youki-default"),
+            ],
+        )?;
+        apply_security_labels(&mut spec)?;
+
+        let process = spec.process().as_ref().unwrap();
+        assert_eq!(
+            process.selinux_label().as_deref(),
+            Some("system_u:system_r:container_t:s0")
+        );
+        assert_eq!(process.apparmor_profile().as_deref(), Some("youki-default"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_only() -> Result<()> {
+        let mut spec = spec_with(
+            Some("system_u:system_r:container_t:s0"),
+            Some("youki-default"),
+            &[],
+        )?;
+        apply_security_labels(&mut spec)?;
+
+        let process = spec.process().as_ref().unwrap();
+        assert_eq!(
+            process.selinux_label().as_deref(),
+            Some("system_u:system_r:container_t:s0")
+        );
+        assert_eq!(process.apparmor_profile().as_deref(), Some("youki-default"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_value_in_field_and_annotation() -> Result<()> {
+        let mut spec = spec_with(
+            None,
+            Some("youki-default"),
+            &[(APPARMOR_PROFILE_ANNOTATION, "youki-default")],
+        )?;
+        apply_security_labels(&mut spec)?;
+
+        let process = spec.process().as_ref().unwrap();
+        assert_eq!(process.apparmor_profile().as_deref(), Some("youki-default"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicting_values() -> Result<()> {
+        let mut spec = spec_with(
+            None,
+            Some("youki-default"),
+            &[(APPARMOR_PROFILE_ANNOTATION, "unconfined")],
+        )?;
+        assert!(apply_security_labels(&mut spec).is_err());
+
+        let mut spec = spec_with(
+            Some("system_u:system_r:container_t:s0"),
+            None,
+            &[(SELINUX_LABEL_ANNOTATION, "system_u:system_r:spc_t:s0")],
+        )?;
+        assert!(apply_security_labels(&mut spec).is_err());
+        Ok(())
+    }
+
+    fn spec_with_unified(unified: &[(&str, &str)], annotations: &[(&str, &str)]) -> Result<Spec> {
+        let to_map = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let resources = LinuxResourcesBuilder::default()
+            .unified(to_map(unified))
+            .build()?;
+
+        Ok(SpecBuilder::default()
+            .linux(LinuxBuilder::default().resources(resources).build()?)
+            .annotations(to_map(annotations))
+            .build()?)
+    }
+
+    #[test]
+    fn test_cpu_uclamp_annotations() -> Result<()> {
+        let mut spec = spec_with_unified(
+            &[("cpu.weight", "100")],
+            &[
+                (CPU_UCLAMP_MIN_ANNOTATION, "10"),
+                (CPU_UCLAMP_MAX_ANNOTATION, "max"),
+            ],
+        )?;
+        apply_cpu_uclamp_with(&mut spec, false, &CgroupSetup::Unified)?;
+
+        let unified = spec
+            .linux()
+            .as_ref()
+            .and_then(|l| l.resources().as_ref())
+            .and_then(|r| r.unified().as_ref())
+            .unwrap();
+        assert_eq!(unified.len(), 3);
+        assert_eq!(unified["cpu.uclamp.min"], "10");
+        assert_eq!(unified["cpu.uclamp.max"], "max");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu_uclamp_conflicting_values() -> Result<()> {
+        let mut spec = spec_with_unified(
+            &[("cpu.uclamp.max", "50")],
+            &[(CPU_UCLAMP_MAX_ANNOTATION, "80")],
+        )?;
+        assert!(apply_cpu_uclamp_with(&mut spec, false, &CgroupSetup::Unified).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu_uclamp_unsupported() -> Result<()> {
+        let spec = spec_with_unified(&[], &[(CPU_UCLAMP_MIN_ANNOTATION, "10")])?;
+        for (use_systemd, setup, expected) in [
+            (true, CgroupSetup::Unified, "systemd"),
+            (false, CgroupSetup::Legacy, "cgroup v1"),
+            (false, CgroupSetup::Hybrid, "cgroup v1"),
+        ] {
+            let err = apply_cpu_uclamp_with(&mut spec.clone(), use_systemd, &setup).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+
+        // without the annotations, the cgroup setup does not matter
+        let mut spec = spec_with_unified(&[("cpu.weight", "100")], &[])?;
+        apply_cpu_uclamp(&mut spec, true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu_uclamp_invalid_annotation() -> Result<()> {
+        // the value is rejected before it reaches any cgroup manager
+        let spec = spec_with_unified(&[], &[(CPU_UCLAMP_MAX_ANNOTATION, "101")])?;
+        for use_systemd in [false, true] {
+            let err = apply_cpu_uclamp_with(&mut spec.clone(), use_systemd, &CgroupSetup::Legacy)
+                .unwrap_err();
+            assert!(
+                format!("{:?}", err).contains("not a percentage"),
+                "{:?}",
+                err
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_min_annotation() -> Result<()> {
+        let mut spec = spec_with_unified(&[], &[(MEMORY_MIN_ANNOTATION, "536870912")])?;
+        apply_memory_min(&mut spec)?;
+
+        let unified = spec
+            .linux()
+            .as_ref()
+            .and_then(|l| l.resources().as_ref())
+            .and_then(|r| r.unified().as_ref())
+            .unwrap();
+        assert_eq!(unified["memory.min"], "536870912");
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_high_annotation() -> Result<()> {
+        let mut spec = spec_with_unified(
+            &[("memory.min", "268435456")],
+            &[(MEMORY_HIGH_ANNOTATION, "max")],
+        )?;
+        apply_memory_high(&mut spec)?;
+
+        let unified = spec
+            .linux()
+            .as_ref()
+            .and_then(|l| l.resources().as_ref())
+            .and_then(|r| r.unified().as_ref())
+            .unwrap();
+        assert_eq!(unified["memory.min"], "268435456");
+        assert_eq!(unified["memory.high"], "max");
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_cgroup_annotation() -> Result<()> {
+        assert!(!join_cgroup(&spec_with(None, None, &[])?)?);
+        assert!(join_cgroup(&spec_with(
+            None,
+            None,
+            &[(JOIN_CGROUP_ANNOTATION, "true")]
+        )?)?);
+        assert!(!join_cgroup(&spec_with(
+            None,
+            None,
+            &[(JOIN_CGROUP_ANNOTATION, "false")]
+        )?)?);
+        assert!(join_cgroup(&spec_with(None, None, &[(JOIN_CGROUP_ANNOTATION, "yes")])?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_annotations_exceeding_limit() -> Result<()> {
+        // memory.max of the unified resources is a limit as well
+        let mut spec = spec_with_unified(
+            &[("memory.max", "268435456")],
+            &[(MEMORY_MIN_ANNOTATION, "536870912")],
+        )?;
+        let err = apply_memory_min(&mut spec).unwrap_err();
+        assert!(
+            err.to_string().contains("exceeds the memory limit"),
+            "{}",
+            err
+        );
+
+        let mut spec = spec_with_unified(
+            &[("memory.max", "268435456")],
+            &[(MEMORY_HIGH_ANNOTATION, "536870912")],
+        )?;
+        let err = apply_memory_high(&mut spec).unwrap_err();
+        assert!(
+            err.to_string().contains("exceeds the memory limit"),
+            "{}",
+            err
+        );
+
+        let mut spec = spec_with_unified(
+            &[("memory.max", "1073741824")],
+            &[
+                (MEMORY_MIN_ANNOTATION, "268435456"),
+                (MEMORY_HIGH_ANNOTATION, "536870912"),
+            ],
+        )?;
+        apply_memory_min(&mut spec)?;
+        apply_memory_high(&mut spec)?;
+        Ok(())
+    }
+}
@@ -3,8 +3,10 @@ use crate::syscall::Syscall;
 use caps::Capability as CapsCapability;
 use caps::*;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use oci_spec::runtime::{Capabilities, Capability as SpecCapability, LinuxCapabilities};
+use std::collections::BTreeSet;
+use std::fs;
 
 /// Converts a list of capability types to capabilities has set
 fn to_set(caps: &Capabilities) -> CapsHashSet {
@@ -121,6 +123,74 @@ impl CapabilityExt for SpecCapability {
     }
 }
 
+/// Every capability a spec or an exec can name, by its CAP_* name
+const CAPABILITIES: &[(&str, SpecCapability)] = &[
+    ("CAP_AUDIT_CONTROL", SpecCapability::AuditControl),
+    ("CAP_AUDIT_READ", SpecCapability::AuditRead),
+    ("CAP_AUDIT_WRITE", SpecCapability::AuditWrite),
+    ("CAP_BLOCK_SUSPEND", SpecCapability::BlockSuspend),
+    ("CAP_BPF", SpecCapability::Bpf),
+    ("CAP_CHECKPOINT_RESTORE", SpecCapability::CheckpointRestore),
+    ("CAP_CHOWN", SpecCapability::Chown),
+    ("CAP_DAC_OVERRIDE", SpecCapability::DacOverride),
+    ("CAP_DAC_READ_SEARCH", SpecCapability::DacReadSearch),
+    ("CAP_FOWNER", SpecCapability::Fowner),
+    ("CAP_FSETID", SpecCapability::Fsetid),
+    ("CAP_IPC_LOCK", SpecCapability::IpcLock),
+    ("CAP_IPC_OWNER", SpecCapability::IpcOwner),
+    ("CAP_KILL", SpecCapability::Kill),
+    ("CAP_LEASE", SpecCapability::Lease),
+    ("CAP_LINUX_IMMUTABLE", SpecCapability::LinuxImmutable),
+    ("CAP_MAC_ADMIN", SpecCapability::MacAdmin),
+    ("CAP_MAC_OVERRIDE", SpecCapability::MacOverride),
+    ("CAP_MKNOD", SpecCapability::Mknod),
+    ("CAP_NET_ADMIN", SpecCapability::NetAdmin),
+    ("CAP_NET_BIND_SERVICE", SpecCapability::NetBindService),
+    ("CAP_NET_BROADCAST", SpecCapability::NetBroadcast),
+    ("CAP_NET_RAW", SpecCapability::NetRaw),
+    ("CAP_PERFMON", SpecCapability::Perfmon),
+    ("CAP_SETFCAP", SpecCapability::Setfcap),
+    ("CAP_SETGID", SpecCapability::Setgid),
+    ("CAP_SETPCAP", SpecCapability::Setpcap),
+    ("CAP_SETUID", SpecCapability::Setuid),
+    ("CAP_SYSLOG", SpecCapability::Syslog),
+    ("CAP_SYS_ADMIN", SpecCapability::SysAdmin),
+    ("CAP_SYS_BOOT", SpecCapability::SysBoot),
+    ("CAP_SYS_CHROOT", SpecCapability::SysChroot),
+    ("CAP_SYS_MODULE", SpecCapability::SysModule),
+    ("CAP_SYS_NICE", SpecCapability::SysNice),
+    ("CAP_SYS_PACCT", SpecCapability::SysPacct),
+    ("CAP_SYS_PTRACE", SpecCapability::SysPtrace),
+    ("CAP_SYS_RAWIO", SpecCapability::SysRawio),
+    ("CAP_SYS_RESOURCE", SpecCapability::SysResource),
+    ("CAP_SYS_TIME", SpecCapability::SysTime),
+    ("CAP_SYS_TTY_CONFIG", SpecCapability::SysTtyConfig),
+    ("CAP_WAKE_ALARM", SpecCapability::WakeAlarm),
+];
+
+/// Parses the name of a capability, e.g. CAP_SYS_ADMIN. The CAP_ prefix and
+/// the case are optional, as they are for capabilities in the spec. Unknown
+/// names are rejected rather than ignored, so that a typo cannot silently
+/// grant less than intended.
+pub fn parse_capability(name: &str) -> Result<SpecCapability> {
+    let upper = name.to_uppercase();
+    let stripped = upper.strip_prefix("CAP_").unwrap_or(&upper);
+    match CAPABILITIES
+        .iter()
+        .find(|(known, _)| known.strip_prefix("CAP_") == Some(stripped))
+    {
+        Some((_, cap)) => Ok(*cap),
+        None => {
+            let valid: Vec<_> = CAPABILITIES.iter().map(|(known, _)| *known).collect();
+            bail!(
+                "unknown capability {}, valid capabilities are: {}",
+                name,
+                valid.join(", ")
+            )
+        }
+    }
+}
+
 /// reset capabilities of process calling this to effective capabilities
 /// effective capability set is set of capabilities used by kernel to perform checks
 /// see <https://man7.org/linux/man-pages/man7/capabilities.7.html> for more information
@@ -159,6 +229,54 @@ pub fn drop_privileges<S: Syscall + ?Sized>(cs: &LinuxCapabilities, syscall: &S)
     Ok(())
 }
 
+/// Capability sets of a process, decoded to capability names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityAudit {
+    pub effective: BTreeSet<String>,
+    pub permitted: BTreeSet<String>,
+    pub bounding: BTreeSet<String>,
+}
+
+/// Reads back the capability sets the calling process ended up with, as
+/// reported by the kernel in /proc/self/status
+pub fn audit() -> Result<CapabilityAudit> {
+    let status = fs::read_to_string("/proc/self/status").context("failed to read status")?;
+    parse_status(&status)
+}
+
+fn parse_status(status: &str) -> Result<CapabilityAudit> {
+    let set = |field: &str| -> Result<BTreeSet<String>> {
+        let mask = status
+            .lines()
+            .find_map(|line| line.strip_prefix(field))
+            .with_context(|| format!("no {} in status", field))?;
+        decode_mask(mask.trim()).with_context(|| format!("failed to decode {}", field))
+    };
+
+    Ok(CapabilityAudit {
+        effective: set("CapEff:")?,
+        permitted: set("CapPrm:")?,
+        bounding: set("CapBnd:")?,
+    })
+}
+
+/// Decodes a hex capability mask, as found in /proc/<pid>/status. Bits of
+/// capabilities which are unknown to the caps crate are named by their number.
+fn decode_mask(mask: &str) -> Result<BTreeSet<String>> {
+    let mask = u64::from_str_radix(mask, 16)?;
+    let known = caps::all();
+    Ok((0..64u8)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .map(|bit| {
+            known
+                .iter()
+                .find(|c| c.index() == bit)
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| format!("CAP_{}", bit))
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use oci_spec::runtime::LinuxCapabilitiesBuilder;
@@ -166,6 +284,7 @@ mod tests {
 
     use super::*;
     use crate::syscall::test::TestHelperSyscall;
+    use std::str::FromStr;
 
     #[test]
     fn test_reset_effective() {
@@ -662,4 +781,90 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_decode_mask() -> Result<()> {
+        // CAP_CHOWN, CAP_DAC_OVERRIDE, CAP_KILL, CAP_NET_BIND_SERVICE and CAP_NET_RAW
+        let names = decode_mask("0000000000002423")?;
+        let want: BTreeSet<String> = [
+            "CAP_CHOWN",
+            "CAP_DAC_OVERRIDE",
+            "CAP_KILL",
+            "CAP_NET_BIND_SERVICE",
+            "CAP_NET_RAW",
+        ]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+        assert_eq!(names, want);
+
+        assert!(decode_mask("0000000000000000")?.is_empty());
+        assert!(decode_mask("not a mask").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_mask_unknown_bit() -> Result<()> {
+        let names = decode_mask("8000000000000001")?;
+        let want: BTreeSet<String> = ["CAP_CHOWN", "CAP_63"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(names, want);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_status() -> Result<()> {
+        let status = "Name:\tsh\nCapInh:\t0000000000000000\nCapPrm:\t0000000000000001\n\
+                      CapEff:\t0000000000000001\nCapBnd:\t0000000000000021\n\
+                      CapAmb:\t0000000000000000\n";
+        let audit = parse_status(status)?;
+        assert_eq!(audit.effective, BTreeSet::from(["CAP_CHOWN".to_string()]));
+        assert_eq!(audit.permitted, BTreeSet::from(["CAP_CHOWN".to_string()]));
+        assert_eq!(
+            audit.bounding,
+            BTreeSet::from(["CAP_CHOWN".to_string(), "CAP_KILL".to_string()])
+        );
+
+        assert!(parse_status("Name:\tsh\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_capability() -> Result<()> {
+        assert_eq!(parse_capability("CAP_SYS_ADMIN")?, SpecCapability::SysAdmin);
+        assert_eq!(parse_capability("sys_admin")?, SpecCapability::SysAdmin);
+        assert_eq!(parse_capability("Cap_Net_Raw")?, SpecCapability::NetRaw);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_capability_rejects_unknown_names() {
+        for name in ["CAP_SYS_ADMN", "CAP_", "", "CAP_CAP_KILL", "NET_RAW "] {
+            let err = parse_capability(name).unwrap_err().to_string();
+            assert!(
+                err.starts_with(&format!("unknown capability {},", name)),
+                "{}",
+                err
+            );
+            assert!(err.contains("CAP_SYS_ADMIN"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_all_capabilities_map_to_their_bit() -> Result<()> {
+        let names: HashSet<_> = CAPABILITIES.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names.len(), CAPABILITIES.len());
+        assert_eq!(CAPABILITIES.len(), caps::all().len());
+
+        for (name, _) in CAPABILITIES {
+            let want = CapsCapability::from_str(name)?;
+            let got = parse_capability(name)?.to_cap();
+            assert_eq!(got, want, "{}", name);
+            assert_eq!(got.index(), want.index(), "{}", name);
+            assert_eq!(got.bitmask(), 1 << want.index(), "{}", name);
+        }
+        Ok(())
+    }
 }
@@ -19,23 +19,71 @@ const YOUKI_CONFIG_NAME: &str = "youki_config.json";
 pub struct YoukiConfig {
     pub hooks: Option<Hooks>,
     pub cgroup_path: PathBuf,
+    /// The cgroup was created by someone else and is only joined by the
+    /// container, so its controllers are neither set nor is it removed
+    #[serde(default)]
+    pub join_cgroup: bool,
+    /// Notify socket of the host to which the readiness of the container is
+    /// relayed on start
+    #[serde(default)]
+    pub notify_socket: Option<PathBuf>,
 }
 
 impl<'a> YoukiConfig {
     pub fn from_spec(spec: &'a Spec, container_id: &str, rootless: bool) -> Result<Self> {
+        let cgroup_path = utils::get_cgroup_path(
+            spec.linux()
+                .as_ref()
+                .context("no linux in spec")?
+                .cgroups_path(),
+            container_id,
+            rootless,
+        );
+
         Ok(YoukiConfig {
             hooks: spec.hooks().clone(),
-            cgroup_path: utils::get_cgroup_path(
-                spec.linux()
-                    .as_ref()
-                    .context("no linux in spec")?
-                    .cgroups_path(),
-                container_id,
-                rootless,
-            ),
+            cgroup_path,
+            join_cgroup: false,
+            notify_socket: None,
         })
     }
 
+    /// Joins the cgroup instead of creating it, if it is an absolute path
+    /// which exists already. This is only done on request, as the resources
+    /// of the spec are not applied to a joined cgroup.
+    pub fn join_existing_cgroup(&mut self, spec: &Spec) -> Result<()> {
+        // ref https://github.com/opencontainers/runtime-spec/blob/main/config-linux.md#cgroups-path
+        self.join_cgroup = self.cgroup_path.is_absolute()
+            && libcgroups::common::cgroup_exists(&libcgroups::common::get_cgroup_hierarchy_paths(
+                &self.cgroup_path,
+            )?)
+            .context("failed to check for an existing cgroup")?;
+        if !self.join_cgroup {
+            log::debug!("no existing cgroup {:?} to join", self.cgroup_path);
+            return Ok(());
+        }
+
+        // the device rules are part of almost every spec, so only the other
+        // resources are worth a warning
+        if let Some(resources) = spec.linux().as_ref().and_then(|l| l.resources().as_ref()) {
+            if resources.memory().is_some()
+                || resources.cpu().is_some()
+                || resources.pids().is_some()
+                || resources.block_io().is_some()
+                || resources.hugepage_limits().is_some()
+                || resources.network().is_some()
+                || resources.rdma().is_some()
+                || resources.unified().is_some()
+            {
+                log::warn!(
+                    "resources of the spec are not applied to the joined cgroup {:?}",
+                    self.cgroup_path
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let file = fs::File::create(path.as_ref().join(YOUKI_CONFIG_NAME))?;
         serde_json::to_writer(&file, self)?;
@@ -69,6 +117,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_join_existing_cgroup() -> Result<()> {
+        let spec = Spec::default();
+        // a relative path is always created
+        let mut config = YoukiConfig::from_spec(&spec, "sample", false)?;
+        config.join_existing_cgroup(&spec)?;
+        assert!(!config.join_cgroup);
+
+        config.cgroup_path = PathBuf::from("/youki/test_join_existing_cgroup/missing");
+        config.join_existing_cgroup(&spec)?;
+        assert!(!config.join_cgroup);
+        Ok(())
+    }
+
     #[test]
     fn test_config_save_and_load() -> Result<()> {
         let container_id = "sample";
@@ -1,6 +1,7 @@
 use crate::{syscall::Syscall, utils::PathBufExt};
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use oci_spec::runtime::Spec;
+use std::{ffi::OsString, path::PathBuf, time::Duration};
 
 use super::{init_builder::InitContainerBuilder, tenant_builder::TenantContainerBuilder};
 
@@ -18,8 +19,18 @@ pub struct ContainerBuilder<'a> {
     pub(super) console_socket: Option<PathBuf>,
     /// File descriptors to be passed into the container process
     pub(super) preserve_fds: i32,
+    pub(super) audit_capabilities: bool,
+    /// Socket to communicate the stdio file descriptors without a ptty
+    pub(super) stdio_socket: Option<PathBuf>,
+    /// Time after which the creation of the container is aborted
+    pub(super) timeout: Option<Duration>,
+    /// Arguments of the runtime to re-execute it for the intermediate process
+    pub(super) reexec: Option<Vec<OsString>>,
 }
 
+/// Default time after which the creation of a container is aborted
+pub const DEFAULT_CREATE_TIMEOUT: Duration = Duration::from_secs(120);
+
 /// Builder that can be used to configure the common properties of
 /// either a init or a tenant container
 ///
@@ -58,6 +69,10 @@ impl<'a> ContainerBuilder<'a> {
             pid_file: None,
             console_socket: None,
             preserve_fds: 0,
+            audit_capabilities: false,
+            stdio_socket: None,
+            timeout: Some(DEFAULT_CREATE_TIMEOUT),
+            reexec: None,
         }
     }
 
@@ -95,6 +110,25 @@ impl<'a> ContainerBuilder<'a> {
         InitContainerBuilder::new(self, bundle.into())
     }
 
+    /// Transforms this builder into an init builder for a spec given in
+    /// memory, so that no bundle directory with a config.json is needed
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    /// # use oci_spec::runtime::Spec;
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init_from_spec(Spec::default())
+    /// .with_rootfs("/var/lib/containers/74f1a4cb3801/rootfs")
+    /// .build();
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    pub fn as_init_from_spec(self, spec: Spec) -> InitContainerBuilder<'a> {
+        InitContainerBuilder::from_spec(self, spec)
+    }
+
     /// Sets the root path which will be used to store the container state
     /// # Example
     ///
@@ -171,6 +205,76 @@ impl<'a> ContainerBuilder<'a> {
         self.preserve_fds = preserved_fds;
         self
     }
+
+    /// Sets the time after which the creation of the container is aborted
+    /// and any partially created state is removed. No timeout is applied if
+    /// it is set to None. Processes executed in an existing container are not
+    /// subject to the timeout
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .with_timeout(Some(Duration::from_secs(30)));
+    /// ```
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the socket over which the stdin, stdout and stderr of the
+    /// container process are sent when no terminal is used
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .with_stdio_socket(Some("/var/run/supervisor/stdio.sock"));
+    /// ```
+    pub fn with_stdio_socket<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
+        self.stdio_socket = path.map(|p| p.into());
+        self
+    }
+
+    /// Sets if the capability sets the container process ends up with should
+    /// be read back and logged before the process is executed
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .with_capability_audit(true);
+    /// ```
+    pub fn with_capability_audit(mut self, audit: bool) -> Self {
+        self.audit_capabilities = audit;
+        self
+    }
+
+    /// Sets the arguments with which the runtime is re-executed to set up
+    /// the container in a process with a single thread, instead of forking
+    /// the intermediate process. The runtime has to call
+    /// [`reexec::run`](crate::process::reexec::run) when it is started with
+    /// them.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use libcontainer::container::builder::ContainerBuilder;
+    /// # use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .with_reexec(Some(vec!["init".into()]));
+    /// ```
+    pub fn with_reexec(mut self, args: Option<Vec<OsString>>) -> Self {
+        self.reexec = args;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -1,19 +1,24 @@
 use super::{Container, ContainerStatus};
 use crate::{
+    error::ErrorKind,
     hooks,
+    io_priority::IoPriority,
     notify_socket::NotifyListener,
+    pidfd,
     process::{
         self,
         args::{ContainerArgs, ContainerType},
+        reexec::Reexec,
     },
     rootless::Rootless,
+    scheduler::Scheduler,
     syscall::Syscall,
     utils,
 };
 use anyhow::{bail, Context, Result};
 use nix::unistd::Pid;
 use oci_spec::runtime::Spec;
-use std::{fs, io::Write, os::unix::prelude::RawFd, path::PathBuf};
+use std::{ffi::OsString, fs, io::Write, os::unix::prelude::RawFd, path::PathBuf, time::Duration};
 
 pub(super) struct ContainerBuilderImpl<'a> {
     /// Flag indicating if an init or a tenant container should be created
@@ -33,6 +38,8 @@ pub(super) struct ContainerBuilderImpl<'a> {
     pub pid_file: Option<PathBuf>,
     /// Socket to communicate the file descriptor of the ptty
     pub console_socket: Option<RawFd>,
+    /// Socket to communicate the stdio file descriptors without a ptty
+    pub stdio_socket: Option<RawFd>,
     /// Options for rootless containers
     pub rootless: Option<Rootless<'a>>,
     /// Path to the Unix Domain Socket to communicate container start
@@ -43,6 +50,23 @@ pub(super) struct ContainerBuilderImpl<'a> {
     pub preserve_fds: i32,
     /// If the container is to be run in detached mode
     pub detached: bool,
+    /// Run a minimal init as PID 1 of the container which reaps zombies
+    /// and forwards signals to the container process
+    pub init: bool,
+    /// Scheduling policy of the container process
+    pub scheduler: Option<Scheduler>,
+    /// IO priority of the container process
+    pub io_priority: Option<IoPriority>,
+    /// Log the capability sets of the container process before it is executed
+    pub audit_capabilities: bool,
+    /// Create a new session keyring for the container process
+    pub new_keyring: bool,
+    /// Join the existing cgroup without setting its controllers
+    pub join_cgroup: bool,
+    /// Time after which the creation of the container is aborted
+    pub timeout: Option<Duration>,
+    /// Arguments of the runtime to re-execute it for the intermediate process
+    pub reexec_args: Option<Vec<OsString>>,
 }
 
 impl<'a> ContainerBuilderImpl<'a> {
@@ -69,7 +93,9 @@ impl<'a> ContainerBuilderImpl<'a> {
             &cgroups_path,
             self.use_systemd || self.rootless.is_some(),
             &self.container_id,
-        )?;
+        )
+        .context(ErrorKind::Cgroup)
+        .context("failed to create cgroup manager")?;
         let process = self.spec.process().as_ref().context("No process in spec")?;
 
         if matches!(self.container_type, ContainerType::InitContainer) {
@@ -120,12 +146,26 @@ impl<'a> ContainerBuilderImpl<'a> {
             spec: self.spec,
             rootfs: &self.rootfs,
             console_socket: self.console_socket,
+            stdio_socket: self.stdio_socket,
             notify_socket,
             preserve_fds: self.preserve_fds,
             container: &self.container,
             rootless: &self.rootless,
             cgroup_manager: cmanager,
             detached: self.detached,
+            init: self.init,
+            scheduler: self.scheduler.clone(),
+            io_priority: self.io_priority.clone(),
+            audit_capabilities: self.audit_capabilities,
+            new_keyring: self.new_keyring,
+            join_cgroup: self.join_cgroup,
+            timeout: self.timeout,
+            reexec: self.reexec_args.clone().map(|args| Reexec {
+                args,
+                cgroups_path,
+                use_systemd: self.use_systemd || self.rootless.is_some(),
+                container_id: self.container_id.clone(),
+            }),
         };
 
         let (intermediate, init_pid) =
@@ -137,11 +177,15 @@ impl<'a> ContainerBuilderImpl<'a> {
         }
 
         if let Some(container) = &mut self.container {
+            // the container process waits to be started, so it is known to
+            // be alive and its start time identifies it from now on
+            let start_time = pidfd::start_time(init_pid)?;
             // update status and pid of the container process
             container
                 .set_status(ContainerStatus::Created)
                 .set_creator(nix::unistd::geteuid().as_raw())
                 .set_pid(init_pid.as_raw())
+                .set_start_time(start_time)
                 .save()
                 .context("Failed to save container state")?;
         }
@@ -163,7 +207,9 @@ impl<'a> ContainerBuilderImpl<'a> {
         )?;
 
         let mut errors = Vec::new();
-        if let Err(e) = cmanager.remove().context("failed to remove cgroup") {
+        if self.join_cgroup {
+            log::debug!("keep joined cgroup {:?}", cgroups_path);
+        } else if let Err(e) = cmanager.remove().context("failed to remove cgroup") {
             errors.push(e.to_string());
         }
 
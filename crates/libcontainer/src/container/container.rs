@@ -9,6 +9,7 @@ use nix::unistd::Pid;
 
 use chrono::Utc;
 use procfs::process::Process;
+use serde::{Deserialize, Serialize};
 
 use crate::config::YoukiConfig;
 use crate::syscall::syscall::create_syscall;
@@ -16,7 +17,7 @@ use crate::syscall::syscall::create_syscall;
 use crate::container::{ContainerStatus, State};
 
 /// Structure representing the container data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Container {
     // State of the container
     pub state: State,
@@ -82,6 +83,13 @@ impl Container {
         &self.state.bundle
     }
 
+    /// Returns the path of the config.json of the container, which is kept in
+    /// the container directory as the container was created from it, whether
+    /// it was read from the bundle, another file, stdin or given in memory.
+    pub fn spec_path(&self) -> PathBuf {
+        self.root.join("config.json")
+    }
+
     pub fn set_annotations(&mut self, annotations: Option<HashMap<String, String>>) -> &mut Self {
         self.state.annotations = annotations;
         self
@@ -96,6 +104,16 @@ impl Container {
         self
     }
 
+    /// Start time of the container process, recorded along with its pid
+    pub fn start_time(&self) -> Option<u64> {
+        self.state.start_time
+    }
+
+    pub fn set_start_time(&mut self, start_time: u64) -> &mut Self {
+        self.state.start_time = Some(start_time);
+        self
+    }
+
     pub fn created(&self) -> Option<DateTime<Utc>> {
         self.state.created
     }
@@ -126,6 +144,26 @@ impl Container {
         self
     }
 
+    pub fn exit_code(&self) -> Option<i32> {
+        self.state.exit_code
+    }
+
+    pub fn set_exit_code(&mut self, exit_code: i32) -> &mut Self {
+        self.state.exit_code = Some(exit_code);
+        self
+    }
+
+    /// Returns true if the parent of the container process records its exit
+    /// code in the state
+    pub fn records_exit_code(&self) -> bool {
+        self.state.records_exit_code
+    }
+
+    pub fn set_records_exit_code(&mut self, records_exit_code: bool) -> &mut Self {
+        self.state.records_exit_code = records_exit_code;
+        self
+    }
+
     pub fn status(&self) -> ContainerStatus {
         self.state.status
     }
@@ -151,7 +189,13 @@ impl Container {
                 if let Ok(proc) = Process::new(pid.as_raw()) {
                     use procfs::process::ProcState;
 
-                    match proc.stat()?.state()? {
+                    let stat = proc.stat()?;
+                    match stat.state()? {
+                        // the container process has exited and its pid was
+                        // reused by another process
+                        _ if self.start_time().map_or(false, |t| t != stat.starttime) => {
+                            ContainerStatus::Stopped
+                        }
                         ProcState::Zombie | ProcState::Dead => ContainerStatus::Stopped,
                         _ => match self.status() {
                             ContainerStatus::Creating
@@ -213,6 +257,7 @@ pub struct CheckpointOptions {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::LibcontainerError;
     use crate::utils::create_temp_dir;
     use anyhow::Context;
     use serial_test::serial;
@@ -352,8 +397,15 @@ mod tests {
         container.refresh_status()?;
         assert_eq!(container.status(), ContainerStatus::Stopped);
 
-        // with PID case
+        // with PID case but PID reused by another process
         container.set_pid(1);
+        container.set_start_time(crate::pidfd::start_time(Pid::from_raw(1))? + 1);
+        container.set_status(ContainerStatus::Running);
+        container.refresh_status()?;
+        assert_eq!(container.status(), ContainerStatus::Stopped);
+
+        // with PID case
+        container.set_start_time(crate::pidfd::start_time(Pid::from_raw(1))?);
         container.set_status(ContainerStatus::Paused);
         container.refresh_status()?;
         assert_eq!(container.status(), ContainerStatus::Paused);
@@ -363,4 +415,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_state_error_on_stopped_container() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_state_error_on_stopped_container")?;
+        let mut container = Container::new(
+            "stopped",
+            ContainerStatus::Stopped,
+            None,
+            &tmp_dir,
+            &tmp_dir,
+        )?;
+        container.save()?;
+
+        let err = container.start().unwrap_err();
+        assert!(matches!(err, LibcontainerError::State(_)));
+        assert_eq!(
+            err.to_string(),
+            "stopped could not be started because it was Stopped"
+        );
+        assert!(matches!(
+            container.pause().unwrap_err(),
+            LibcontainerError::State(_)
+        ));
+        assert!(matches!(
+            container.resume().unwrap_err(),
+            LibcontainerError::State(_)
+        ));
+        Ok(())
+    }
 }
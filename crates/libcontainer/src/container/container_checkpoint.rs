@@ -1,6 +1,7 @@
 use super::{Container, ContainerStatus};
 use crate::container::container::CheckpointOptions;
-use anyhow::{bail, Context, Result};
+use crate::error::LibcontainerError;
+use anyhow::{anyhow, Context, Result};
 
 use libcgroups::common::CgroupSetup::{Hybrid, Legacy};
 #[cfg(feature = "v1")]
@@ -14,7 +15,7 @@ const CRIU_CHECKPOINT_LOG_FILE: &str = "dump.log";
 const DESCRIPTORS_JSON: &str = "descriptors.json";
 
 impl Container {
-    pub fn checkpoint(&mut self, opts: &CheckpointOptions) -> Result<()> {
+    pub fn checkpoint(&mut self, opts: &CheckpointOptions) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
@@ -22,11 +23,11 @@ impl Container {
         // checkpoitning. is_running() would make more sense here, but let's
         // just reuse existing functions.
         if !self.can_pause() {
-            bail!(
+            return Err(LibcontainerError::State(anyhow!(
                 "{} could not be checkpointed because it was {:?}",
                 self.id(),
                 self.status()
-            );
+            )));
         }
 
         let mut criu = rust_criu::Criu::new().unwrap();
@@ -36,8 +37,9 @@ impl Container {
         // This information is needed during restore again. The external location of the bind
         // mounts can change and CRIU will just mount whatever we tell it to mount based on
         // information found in 'config.json'.
-        let source_spec_path = self.bundle().join("config.json");
-        let spec = Spec::load(source_spec_path)?;
+        let source_spec_path = self.spec_path();
+        let spec = Spec::load(&source_spec_path)
+            .with_context(|| format!("failed to load spec from {:?}", source_spec_path))?;
         let mounts = spec.mounts().clone();
         for m in mounts.unwrap() {
             match m.typ().as_deref() {
@@ -87,7 +89,8 @@ impl Container {
         // keep the FD open until CRIU uses it.
         let work_dir: std::fs::File;
         if let Some(wp) = &opts.work_path {
-            work_dir = std::fs::File::open(wp)?;
+            work_dir = std::fs::File::open(wp)
+                .with_context(|| format!("failed to open work path {:?}", wp))?;
             criu.set_work_dir_fd(work_dir.as_raw_fd());
         }
 
@@ -103,8 +106,12 @@ impl Container {
             descriptors.push(link_path);
         }
         let descriptors_json_path = opts.image_path.join(DESCRIPTORS_JSON);
-        let mut descriptors_json = File::create(descriptors_json_path)?;
-        write!(descriptors_json, "{}", serde_json::to_string(&descriptors)?)?;
+        let mut descriptors_json = File::create(&descriptors_json_path)
+            .with_context(|| format!("failed to create {:?}", descriptors_json_path))?;
+        let descriptors =
+            serde_json::to_string(&descriptors).context("failed to serialize descriptors")?;
+        write!(descriptors_json, "{}", descriptors)
+            .with_context(|| format!("failed to write {:?}", descriptors_json_path))?;
 
         criu.set_log_file(CRIU_CHECKPOINT_LOG_FILE.to_string());
         criu.set_log_level(4);
@@ -124,7 +131,7 @@ impl Container {
                 .unwrap(),
         );
         if let Err(e) = criu.dump() {
-            bail!(
+            return Err(anyhow!(
                 "checkpointing container {} failed with {:?}. Please check CRIU logfile {:}/{}",
                 self.id(),
                 e,
@@ -133,7 +140,8 @@ impl Container {
                     .unwrap_or(&opts.image_path)
                     .display(),
                 CRIU_CHECKPOINT_LOG_FILE
-            );
+            )
+            .into());
         }
 
         if !opts.leave_running {
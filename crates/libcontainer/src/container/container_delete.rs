@@ -1,7 +1,8 @@
 use super::{Container, ContainerStatus};
 use crate::config::YoukiConfig;
+use crate::error::LibcontainerError;
 use crate::hooks;
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use libcgroups;
 use nix::sys::signal;
 use std::fs;
@@ -24,7 +25,7 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn delete(&mut self, force: bool) -> Result<()> {
+    pub fn delete(&mut self, force: bool) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         if self.can_kill() && force {
@@ -48,18 +49,26 @@ impl Container {
                 // remove the cgroup created for the container
                 // check https://man7.org/linux/man-pages/man7/cgroups.7.html
                 // creating and removing cgroups section for more information on cgroups
-                let use_systemd = self
-                    .systemd()
-                    .context("container state does not contain cgroup manager")?;
-                let cmanager = libcgroups::common::create_cgroup_manager(
-                    &config.cgroup_path,
-                    use_systemd,
-                    self.id(),
-                )
-                .context("failed to create cgroup manager")?;
-                cmanager.remove().with_context(|| {
-                    format!("failed to remove cgroup {}", config.cgroup_path.display())
-                })?;
+                if config.join_cgroup {
+                    log::debug!("keep joined cgroup {:?}", config.cgroup_path);
+                } else {
+                    let use_systemd = self
+                        .systemd()
+                        .context("container state does not contain cgroup manager")?;
+                    let cmanager = libcgroups::common::create_cgroup_manager(
+                        &config.cgroup_path,
+                        use_systemd,
+                        self.id(),
+                    )
+                    .context("failed to create cgroup manager")
+                    .map_err(LibcontainerError::Cgroup)?;
+                    cmanager
+                        .remove()
+                        .with_context(|| {
+                            format!("failed to remove cgroup {}", config.cgroup_path.display())
+                        })
+                        .map_err(LibcontainerError::Cgroup)?;
+                }
 
                 if let Some(hooks) = config.hooks.as_ref() {
                     hooks::run_hooks(hooks.poststop().as_ref(), Some(self))
@@ -68,11 +77,11 @@ impl Container {
             }
             Ok(())
         } else {
-            bail!(
+            Err(LibcontainerError::State(anyhow!(
                 "{} could not be deleted because it was {:?}",
                 self.id(),
                 self.status()
-            )
+            )))
         }
     }
 }
@@ -1,7 +1,9 @@
 use std::{thread, time::Duration};
 
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use crate::error::LibcontainerError;
+use anyhow::{anyhow, Context, Result};
+use libcgroups::{common::CgroupManager, stats::Stats};
 
 impl Container {
     /// Displays container events
@@ -21,32 +23,68 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn events(&mut self, interval: u32, stats: bool) -> Result<()> {
-        self.refresh_status()
-            .context("failed to refresh container status")?;
-        if !self.state.status.eq(&ContainerStatus::Running) {
-            bail!("{} is not in running state", self.id());
-        }
-
-        let cgroups_path = self.spec()?.cgroup_path;
-        let use_systemd = self
-            .systemd()
-            .context("could not determine cgroup manager")?;
-
-        let cgroup_manager =
-            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())?;
+    pub fn events(&mut self, interval: u32, stats: bool) -> Result<(), LibcontainerError> {
+        let cgroup_manager = self.cgroup_manager()?;
         match stats {
             true => {
                 let stats = cgroup_manager.stats()?;
-                println!("{}", serde_json::to_string_pretty(&stats)?);
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&stats).context("failed to serialize stats")?
+                );
             }
             false => loop {
                 let stats = cgroup_manager.stats()?;
-                println!("{}", serde_json::to_string_pretty(&stats)?);
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&stats).context("failed to serialize stats")?
+                );
                 thread::sleep(Duration::from_secs(interval as u64));
             },
         }
 
         Ok(())
     }
+
+    /// Returns the cgroup statistics of a running container
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let stats = container.stats()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&mut self) -> Result<Stats, LibcontainerError> {
+        self.cgroup_manager()?
+            .stats()
+            .map_err(LibcontainerError::Cgroup)
+    }
+
+    fn cgroup_manager(&mut self) -> Result<Box<dyn CgroupManager>, LibcontainerError> {
+        self.refresh_status()
+            .context("failed to refresh container status")?;
+        if !self.state.status.eq(&ContainerStatus::Running) {
+            return Err(LibcontainerError::State(anyhow!(
+                "{} is not in running state",
+                self.id()
+            )));
+        }
+
+        let cgroups_path = self.spec()?.cgroup_path;
+        let use_systemd = self
+            .systemd()
+            .context("could not determine cgroup manager")?;
+
+        libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())
+            .map_err(LibcontainerError::Cgroup)
+    }
 }
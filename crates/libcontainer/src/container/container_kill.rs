@@ -1,6 +1,6 @@
 use super::{Container, ContainerStatus};
-use crate::signal::Signal;
-use anyhow::{bail, Context, Result};
+use crate::{error::LibcontainerError, pidfd, signal::Signal};
+use anyhow::{anyhow, Context, Result};
 use libcgroups::common::{create_cgroup_manager, get_cgroup_setup};
 use nix::sys::signal::{self};
 
@@ -23,7 +23,7 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn kill<S: Into<Signal>>(&mut self, signal: S, all: bool) -> Result<()> {
+    pub fn kill<S: Into<Signal>>(&mut self, signal: S, all: bool) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         if self.can_kill() {
@@ -33,11 +33,11 @@ impl Container {
             if all && self.status() == ContainerStatus::Stopped {
                 self.do_kill(signal, all)?;
             } else {
-                bail!(
+                return Err(LibcontainerError::State(anyhow!(
                     "{} could not be killed because it was {:?}",
                     self.id(),
                     self.status()
-                )
+                )));
             }
         }
         self.set_status(ContainerStatus::Stopped).save()?;
@@ -57,7 +57,7 @@ impl Container {
         let pid = self.pid().unwrap();
 
         log::debug!("kill signal {} to {}", signal, pid);
-        let res = signal::kill(pid, signal);
+        let res = pidfd::kill(pid, self.start_time(), signal);
 
         match res {
             Err(nix::errno::Errno::ESRCH) => {
@@ -103,7 +103,8 @@ impl Container {
         let pids = cmanger.get_all_pids()?;
         pids.iter().try_for_each(|&pid| {
             log::debug!("kill signal {} to {}", signal, pid);
-            let res = signal::kill(pid, signal);
+            // the processes are frozen, so their pids can not be reused
+            let res = pidfd::kill(pid, None, signal);
             match res {
                 Err(nix::errno::Errno::ESRCH) => {
                     /* the process does not exist, which is what we want */
@@ -1,5 +1,6 @@
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use crate::error::LibcontainerError;
+use anyhow::{anyhow, Context, Result};
 use libcgroups::common::FreezerState;
 
 impl Container {
@@ -20,16 +21,16 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn pause(&mut self) -> Result<()> {
+    pub fn pause(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
         if !self.can_pause() {
-            bail!(
+            return Err(LibcontainerError::State(anyhow!(
                 "{} could not be paused because it was {:?}",
                 self.id(),
                 self.status()
-            );
+            )));
         }
 
         let cgroups_path = self.spec()?.cgroup_path;
@@ -37,8 +38,11 @@ impl Container {
             .systemd()
             .context("container state does not contain cgroup manager")?;
         let cmanager =
-            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())?;
-        cmanager.freeze(FreezerState::Frozen)?;
+            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())
+                .map_err(LibcontainerError::Cgroup)?;
+        cmanager
+            .freeze(FreezerState::Frozen)
+            .map_err(LibcontainerError::Cgroup)?;
 
         log::debug!("saving paused status");
         self.set_status(ContainerStatus::Paused).save()?;
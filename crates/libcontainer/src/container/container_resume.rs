@@ -1,6 +1,7 @@
 use super::{Container, ContainerStatus};
+use crate::error::LibcontainerError;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use libcgroups::common::FreezerState;
 
 impl Container {
@@ -21,17 +22,17 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn resume(&mut self) -> Result<()> {
+    pub fn resume(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
         // check if container can be resumed :
         // for example, a running process cannot be resumed
         if !self.can_resume() {
-            bail!(
+            return Err(LibcontainerError::State(anyhow!(
                 "{} could not be resumed because it was {:?}",
                 self.id(),
                 self.status()
-            );
+            )));
         }
 
         let cgroups_path = self.spec()?.cgroup_path;
@@ -39,9 +40,12 @@ impl Container {
             .systemd()
             .context("container state does not contain cgroup manager")?;
         let cmanager =
-            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())?;
+            libcgroups::common::create_cgroup_manager(cgroups_path, use_systemd, self.id())
+                .map_err(LibcontainerError::Cgroup)?;
         // resume the frozen container
-        cmanager.freeze(FreezerState::Thawed)?;
+        cmanager
+            .freeze(FreezerState::Thawed)
+            .map_err(LibcontainerError::Cgroup)?;
 
         log::debug!("saving running status");
         self.set_status(ContainerStatus::Running).save()?;
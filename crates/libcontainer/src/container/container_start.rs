@@ -1,12 +1,15 @@
 use crate::{
     config::YoukiConfig,
+    error::LibcontainerError,
     hooks,
+    notify_relay::{NotifyRelay, READY_TIMEOUT, RELAY_DIR},
     notify_socket::{NotifySocket, NOTIFY_FILE},
 };
 
 use super::{Container, ContainerStatus};
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use nix::unistd;
+use std::path::Path;
 
 impl Container {
     /// Starts a previously created container
@@ -26,7 +29,7 @@ impl Container {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn start(&mut self) -> Result<()> {
+    pub fn start(&mut self) -> Result<(), LibcontainerError> {
         self.refresh_status()
             .context("failed to refresh container status")?;
 
@@ -37,7 +40,7 @@ impl Container {
                 self.status()
             );
             log::error!("{}", err_msg);
-            bail!(err_msg);
+            return Err(LibcontainerError::State(anyhow!(err_msg)));
         }
 
         let config = YoukiConfig::load(&self.root)
@@ -50,7 +53,16 @@ impl Container {
                 .with_context(|| "failed to run pre start hooks")?;
         }
 
-        unistd::chdir(self.root.as_os_str())?;
+        unistd::chdir(self.root.as_os_str())
+            .with_context(|| format!("failed to chdir into {:?}", self.root))?;
+
+        // the relay has to listen before the container process can notify
+        let relay = match &config.notify_socket {
+            Some(_) => Some(
+                NotifyRelay::bind(Path::new(RELAY_DIR)).context("failed to bind notify relay")?,
+            ),
+            None => None,
+        };
 
         let mut notify_socket = NotifySocket::new(self.root.join(NOTIFY_FILE));
         notify_socket.notify_container_start()?;
@@ -65,6 +77,15 @@ impl Container {
                 .with_context(|| "failed to run post start hooks")?;
         }
 
+        if let (Some(relay), Some(host_socket)) = (relay, &config.notify_socket) {
+            let pid = self
+                .pid()
+                .with_context(|| format!("container {} has no pid", self.id()))?;
+            relay
+                .spawn(host_socket, pid, READY_TIMEOUT)
+                .with_context(|| format!("failed to relay readiness of container {}", self.id()))?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,310 @@
+use super::{Container, ContainerStatus};
+use crate::{error::LibcontainerError, pidfd::PidFd, signal::Signal, subreaper};
+use anyhow::{anyhow, bail, Context, Result};
+use nix::{
+    errno::Errno,
+    sys::wait::{waitpid, WaitPidFlag, WaitStatus},
+    unistd::{self, Pid},
+};
+use procfs::process::{ProcState, Process};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Time the parent of the container process has to record the exit code in
+// the state once the container process has exited
+const RECORD_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Container {
+    /// Waits until the container process has exited and returns its exit
+    /// code. If the container process was terminated by a signal, the exit
+    /// code is 128 plus the number of the signal. A container which has
+    /// already exited returns the exit code recorded in its state.
+    ///
+    /// Only the parent of the container process can reap it, so unless the
+    /// container process is a child of the calling process, the exit code is
+    /// read from the state once the parent has recorded it there. This is
+    /// only the case for the runtime staying in the foreground with
+    /// `run --subreaper`, for any other container the exit code is unknown
+    /// and waiting fails right away.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use libcontainer::container::builder::ContainerBuilder;
+    /// use libcontainer::syscall::syscall::create_syscall;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut container = ContainerBuilder::new("74f1a4cb3801".to_owned(), create_syscall().as_ref())
+    /// .as_init("/var/run/docker/bundle")
+    /// .build()?;
+    ///
+    /// let exit_code = container.wait()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait(&mut self) -> Result<i32, LibcontainerError> {
+        self.refresh_status()
+            .context("failed to refresh container status")?;
+        if let (ContainerStatus::Stopped, Some(exit_code)) = (self.status(), self.exit_code()) {
+            return Ok(exit_code);
+        }
+
+        let pid = self
+            .pid()
+            .with_context(|| format!("container {} has no pid", self.id()))
+            .map_err(LibcontainerError::State)?;
+        if !self.records_exit_code() && !is_child(pid) {
+            return Err(LibcontainerError::State(anyhow!(
+                "the exit code of container {} is not recorded, as it is not run with --subreaper",
+                self.id()
+            )));
+        }
+        let exit_code = match wait_for_exit(pid)? {
+            Some(exit_code) => exit_code,
+            None => self.wait_for_recorded_exit_code()?,
+        };
+
+        self.set_status(ContainerStatus::Stopped)
+            .set_exit_code(exit_code)
+            .save()?;
+        Ok(exit_code)
+    }
+
+    // The container process was not a child of this process, so only its
+    // parent knows the exit code, which it may record only after the
+    // container process has already exited.
+    fn wait_for_recorded_exit_code(&mut self) -> Result<i32> {
+        let deadline = Instant::now() + RECORD_TIMEOUT;
+        loop {
+            self.refresh_state()?;
+            if let Some(exit_code) = self.exit_code() {
+                return Ok(exit_code);
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "container {} has exited, but its exit code was not recorded",
+                    self.id()
+                );
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Waits like [`Container::wait`] until the container process has exited,
+    /// but also reaps every descendant of the container which is re-parented
+    /// to the calling process in the meantime. The calling process has to
+    /// become a child subreaper with [`subreaper::set_subreaper`] before the
+    /// container is created. The signals to forward are relayed to the
+    /// container process while waiting.
+    pub fn wait_as_subreaper(&mut self, forward: &[Signal]) -> Result<i32, LibcontainerError> {
+        let pid = self
+            .pid()
+            .with_context(|| format!("container {} has no pid", self.id()))
+            .map_err(LibcontainerError::State)?;
+        let forward: Vec<_> = forward.iter().map(|signal| signal.into_raw()).collect();
+        let exit_code = subreaper::reap_until(pid, &forward)?;
+
+        self.set_status(ContainerStatus::Stopped)
+            .set_exit_code(exit_code)
+            .save()?;
+        Ok(exit_code)
+    }
+}
+
+// Blocks until the process has exited. The exit code is returned if the
+// process was a child of this process and could be reaped.
+fn wait_for_exit(pid: Pid) -> Result<Option<i32>> {
+    match PidFd::open(pid) {
+        Ok(pidfd) => pidfd.wait_for_exit()?,
+        // the process does not exist anymore
+        Err(Errno::ESRCH) => {}
+        Err(err) => {
+            log::debug!("pidfd is not available ({}), fall back to polling", err);
+            return poll_for_exit(pid);
+        }
+    }
+
+    match waitpid(pid, None) {
+        Ok(status) => Ok(exit_code(status)),
+        Err(Errno::ECHILD) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("failed to wait for {}", pid)),
+    }
+}
+
+fn poll_for_exit(pid: Pid) -> Result<Option<i32>> {
+    loop {
+        match waitpid(pid, Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::StillAlive) => {}
+            Ok(status) => return Ok(exit_code(status)),
+            Err(Errno::ECHILD) => {
+                if has_exited(pid) {
+                    return Ok(None);
+                }
+            }
+            Err(err) => return Err(err).with_context(|| format!("failed to wait for {}", pid)),
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn is_child(pid: Pid) -> bool {
+    match Process::new(pid.as_raw()).and_then(|process| process.stat()) {
+        Ok(stat) => stat.ppid == unistd::getpid().as_raw(),
+        Err(_) => false,
+    }
+}
+
+// A process which is not a child of this process is gone once its parent has
+// reaped it, but it has exited already as soon as it becomes a zombie.
+fn has_exited(pid: Pid) -> bool {
+    match Process::new(pid.as_raw()).and_then(|process| process.stat()) {
+        Ok(stat) => matches!(stat.state(), Ok(ProcState::Zombie | ProcState::Dead)),
+        Err(_) => true,
+    }
+}
+
+fn exit_code(status: WaitStatus) -> Option<i32> {
+    match status {
+        WaitStatus::Exited(_, exit_code) => Some(exit_code),
+        WaitStatus::Signaled(_, signal, _) => Some(128 + signal as i32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::State;
+    use crate::process::fork;
+    use crate::utils::create_temp_dir;
+    use nix::{sys::signal, unistd};
+    use serial_test::serial;
+    use std::{fs::File, io::Read, io::Write, os::unix::io::FromRawFd};
+
+    #[test]
+    #[serial]
+    fn test_wait_short_lived_container() -> Result<()> {
+        let tmp = create_temp_dir("test_wait_short_lived_container")?;
+        let pid = fork::container_fork(|| Ok(3))?;
+        let mut container = Container::new(
+            "wait",
+            ContainerStatus::Running,
+            Some(pid.as_raw()),
+            &tmp,
+            &tmp,
+        )?;
+        container.save()?;
+
+        assert_eq!(container.wait()?, 3);
+        let state = State::load(&tmp)?;
+        assert_eq!(state.status, ContainerStatus::Stopped);
+        assert_eq!(state.exit_code, Some(3));
+
+        // the exit code is taken from the state once the container has exited
+        let mut container = Container::load(tmp.to_path_buf())?;
+        assert_eq!(container.wait()?, 3);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_wait_signaled_container() -> Result<()> {
+        let tmp = create_temp_dir("test_wait_signaled_container")?;
+        let pid = fork::container_fork(|| loop {
+            unistd::pause();
+        })?;
+        let mut container = Container::new(
+            "wait",
+            ContainerStatus::Running,
+            Some(pid.as_raw()),
+            &tmp,
+            &tmp,
+        )?;
+        container.save()?;
+
+        signal::kill(pid, signal::Signal::SIGKILL)?;
+        assert_eq!(container.wait()?, 137);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_wait_container_of_another_parent() -> Result<()> {
+        let tmp = create_temp_dir("test_wait_container_of_another_parent")?;
+        let (pid_reader, pid_writer) = unistd::pipe()?;
+        let (go_reader, go_writer) = unistd::pipe()?;
+
+        // the parent of the container process records its exit code, like
+        // the runtime does with run --subreaper
+        let root = tmp.to_path_buf();
+        let parent = fork::container_fork(|| {
+            let pid = fork::container_fork(|| {
+                let mut go = [0];
+                unsafe { File::from_raw_fd(go_reader) }.read_exact(&mut go)?;
+                Ok(7)
+            })?;
+            unsafe { File::from_raw_fd(pid_writer) }.write_all(&pid.as_raw().to_le_bytes())?;
+            let exit_code = match waitpid(pid, None)? {
+                WaitStatus::Exited(_, exit_code) => exit_code,
+                status => bail!("unexpected wait status {:?}", status),
+            };
+            // the exit code is recorded a while after the process has exited
+            thread::sleep(Duration::from_millis(300));
+            let mut container = Container::load(root)?;
+            container
+                .set_status(ContainerStatus::Stopped)
+                .set_exit_code(exit_code)
+                .save()?;
+            Ok(0)
+        })?;
+        unistd::close(pid_writer)?;
+        unistd::close(go_reader)?;
+
+        let mut pid = [0; 4];
+        unsafe { File::from_raw_fd(pid_reader) }.read_exact(&mut pid)?;
+        let pid = Pid::from_raw(i32::from_le_bytes(pid));
+        let mut container = Container::new(
+            "wait",
+            ContainerStatus::Running,
+            Some(pid.as_raw()),
+            &tmp,
+            &tmp,
+        )?;
+        container.set_records_exit_code(true).save()?;
+        unsafe { File::from_raw_fd(go_writer) }.write_all(&[1])?;
+
+        assert_eq!(container.wait()?, 7);
+        assert_eq!(State::load(&tmp)?.exit_code, Some(7));
+        waitpid(parent, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_wait_without_recorded_exit_code() -> Result<()> {
+        let tmp = create_temp_dir("test_wait_without_recorded_exit_code")?;
+        // the container process is neither a child nor is its exit code
+        // recorded by its parent, as for a container which was started
+        // without --subreaper
+        let mut container = Container::new("wait", ContainerStatus::Running, Some(1), &tmp, &tmp)?;
+        container.save()?;
+
+        let started = Instant::now();
+        let err = container.wait().unwrap_err();
+        assert!(matches!(err, LibcontainerError::State(_)), "{:?}", err);
+        assert!(err.to_string().contains("not recorded"), "{}", err);
+        assert!(started.elapsed() < RECORD_TIMEOUT);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_poll_for_exit() -> Result<()> {
+        let pid = fork::container_fork(|| Ok(5))?;
+        assert_eq!(poll_for_exit(pid)?, Some(5));
+        Ok(())
+    }
+}
@@ -1,26 +1,47 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use nix::unistd;
 use oci_spec::runtime::Spec;
 use rootless::Rootless;
 use std::{
     fs,
+    io::{self, Read},
     path::{Path, PathBuf},
 };
 
 use crate::{
-    apparmor, config::YoukiConfig, notify_socket::NOTIFY_FILE, process::args::ContainerType,
-    rootless, tty, utils,
+    annotations, apparmor,
+    config::YoukiConfig,
+    error::{ErrorKind, LibcontainerError},
+    io_priority::IoPriority,
+    notify_relay,
+    notify_socket::NOTIFY_FILE,
+    process::args::ContainerType,
+    rootfs::mount::resolve_overlay_options,
+    rootless,
+    scheduler::Scheduler,
+    tty, utils,
 };
 
 use super::{
     builder::ContainerBuilder, builder_impl::ContainerBuilderImpl, Container, ContainerStatus,
 };
 
+/// Config path which makes the builder read the spec from stdin
+pub const STDIN_CONFIG: &str = "-";
+
 // Builder that can be used to configure the properties of a new container
 pub struct InitContainerBuilder<'a> {
     base: ContainerBuilder<'a>,
     bundle: PathBuf,
+    config: Option<PathBuf>,
+    spec: Option<Spec>,
+    rootfs: Option<PathBuf>,
+    cgroup_path: Option<PathBuf>,
+    notify_socket: Option<PathBuf>,
     use_systemd: bool,
+    init: bool,
+    strict_pids_limit: bool,
+    new_keyring: bool,
 }
 
 impl<'a> InitContainerBuilder<'a> {
@@ -30,19 +51,96 @@ impl<'a> InitContainerBuilder<'a> {
         Self {
             base: builder,
             bundle,
+            config: None,
+            spec: None,
+            rootfs: None,
+            cgroup_path: None,
+            notify_socket: None,
             use_systemd: true,
+            init: false,
+            strict_pids_limit: false,
+            new_keyring: false,
+        }
+    }
+
+    /// Generates the base configuration for a new container whose spec is
+    /// given in memory instead of being loaded from a bundle
+    pub(super) fn from_spec(builder: ContainerBuilder<'a>, spec: Spec) -> Self {
+        Self {
+            spec: Some(spec),
+            ..Self::new(builder, PathBuf::new())
         }
     }
 
+    /// Sets the path of the config.json of the container, instead of the one
+    /// in the bundle. If the path is [`STDIN_CONFIG`], the spec is read from
+    /// stdin, a relative root filesystem is still resolved against the bundle.
+    pub fn with_config<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
+        self.config = path.map(|p| p.into());
+        self
+    }
+
+    /// Sets the path of the root filesystem of the container, instead of the
+    /// root path in the spec. For a spec given in memory, relative paths in
+    /// the spec, e.g. of overlay mounts, are resolved against this directory.
+    pub fn with_rootfs<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.rootfs = Some(path.into());
+        self
+    }
+
+    /// Sets the cgroup of the container, instead of the cgroups path in the
+    /// spec. An absolute path of a cgroup which exists already, e.g. one
+    /// created by a parent manager, is joined without setting its controllers.
+    /// Without it, an existing cgroups path of the spec is only joined if the
+    /// `org.youki.cgroup.join` annotation is "true".
+    pub fn with_cgroup_path<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
+        self.cgroup_path = path.map(|p| p.into());
+        self
+    }
+
+    /// Sets the notify socket of the host, e.g. the one systemd passes in
+    /// `NOTIFY_SOCKET` to a service of Type=notify, and enables the relay of
+    /// notifications to it. The relay is started in the background once the
+    /// container is started and mounts its socket at /run/notify, so no
+    /// socket should be set for a container which does not speak sd_notify.
+    pub fn with_notify_socket<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
+        self.notify_socket = path.map(|p| p.into());
+        self
+    }
+
     /// Sets if systemd should be used for managing cgroups
     pub fn with_systemd(mut self, should_use: bool) -> Self {
         self.use_systemd = should_use;
         self
     }
 
+    /// Sets if a minimal init should run as PID 1 of the container, which
+    /// reaps zombie processes and forwards signals to the container process
+    pub fn with_init(mut self, init: bool) -> Self {
+        self.init = init;
+        self
+    }
+
+    /// Sets if creation should fail, instead of only warning, when the pids
+    /// limit of the container exceeds the pids still available on the host
+    pub fn with_strict_pids_limit(mut self, strict: bool) -> Self {
+        self.strict_pids_limit = strict;
+        self
+    }
+
+    /// Sets if the container process should get a new session keyring
+    /// instead of inheriting the one of the session starting the container
+    pub fn with_new_keyring(mut self, new_keyring: bool) -> Self {
+        self.new_keyring = new_keyring;
+        self
+    }
+
     /// Creates a new container
-    pub fn build(self) -> Result<Container> {
-        let spec = self.load_spec().context("failed to load spec")?;
+    pub fn build(mut self) -> Result<Container, LibcontainerError> {
+        let raw_config = self.raw_config()?;
+        let mut spec = self.load_spec(&raw_config).context("failed to load spec")?;
+        self.check_pids_limit(&spec)
+            .context("failed to check pids limit")?;
         let container_dir = self
             .create_container_dir()
             .context("failed to create container dir")?;
@@ -54,10 +152,18 @@ impl<'a> InitContainerBuilder<'a> {
             .set_systemd(self.use_systemd)
             .set_annotations(spec.annotations().clone());
 
-        unistd::chdir(&container_dir)?;
+        unistd::chdir(&container_dir)
+            .with_context(|| format!("failed to chdir into {:?}", container_dir))?;
         let notify_path = container_dir.join(NOTIFY_FILE);
+        if self.notify_socket.is_some() {
+            let relay_dir = container_dir.join(notify_relay::RELAY_DIR);
+            utils::create_dir_all(&relay_dir).context("failed to create notify relay dir")?;
+            notify_relay::setup_spec(&mut spec, &relay_dir)
+                .context("failed to set up notify relay")?;
+        }
         // convert path of root file system of the container to absolute path
-        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())?;
+        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())
+            .context("failed to canonicalize rootfs")?;
 
         // if socket file path is given in commandline options,
         // get file descriptors of console socket
@@ -70,19 +176,42 @@ impl<'a> InitContainerBuilder<'a> {
         } else {
             None
         };
+        let stdio_socketfd = if let Some(stdio_socket) = &self.base.stdio_socket {
+            Some(tty::setup_console_socket(
+                &container_dir,
+                stdio_socket,
+                "stdio-socket",
+            )?)
+        } else {
+            None
+        };
 
         let rootless = Rootless::new(&spec)?;
-        let config = YoukiConfig::from_spec(&spec, container.id(), rootless.is_some())?;
+        let mut config = YoukiConfig::from_spec(&spec, container.id(), rootless.is_some())?;
+        if self.cgroup_path.is_some() || annotations::join_cgroup(&spec)? {
+            config
+                .join_existing_cgroup(&spec)
+                .context("failed to join existing cgroup")?;
+        }
+        config.notify_socket = self.notify_socket.clone();
         config
             .save(&container_dir)
             .context("failed to save config")?;
 
+        let config_path = self
+            .save_config(&container_dir, &raw_config, &spec)
+            .context("failed to save config")?;
+        let scheduler = Scheduler::from_config(&config_path).context("failed to load scheduler")?;
+        let io_priority =
+            IoPriority::from_config(&config_path).context("failed to load io priority")?;
+
         let mut builder_impl = ContainerBuilderImpl {
             container_type: ContainerType::InitContainer,
             syscall: self.base.syscall,
             container_id: self.base.container_id,
             pid_file: self.base.pid_file,
             console_socket: csocketfd,
+            stdio_socket: stdio_socketfd,
             use_systemd: self.use_systemd,
             spec: &spec,
             rootfs,
@@ -91,6 +220,14 @@ impl<'a> InitContainerBuilder<'a> {
             container: Some(container.clone()),
             preserve_fds: self.base.preserve_fds,
             detached: false, // TODO this should be set properly based on how the command is given
+            init: self.init,
+            scheduler,
+            io_priority,
+            audit_capabilities: self.base.audit_capabilities,
+            new_keyring: self.new_keyring,
+            join_cgroup: config.join_cgroup,
+            timeout: self.base.timeout,
+            reexec_args: self.base.reexec,
         };
 
         builder_impl.create()?;
@@ -99,12 +236,65 @@ impl<'a> InitContainerBuilder<'a> {
         Ok(container)
     }
 
+    // Returns the spec as json, which is read from the config file, from stdin
+    // or given in memory. Without a bundle, the root filesystem of a spec given
+    // in memory takes the place of the bundle.
+    fn raw_config(&mut self) -> Result<String> {
+        if let Some(spec) = &self.spec {
+            let rootfs = match &self.rootfs {
+                Some(rootfs) => rootfs.clone(),
+                None => spec
+                    .root()
+                    .as_ref()
+                    .context("no root in spec")?
+                    .path()
+                    .clone(),
+            };
+            self.bundle = fs::canonicalize(&rootfs)
+                .with_context(|| format!("failed to canonicalize rootfs {:?}", rootfs))?;
+            self.rootfs = Some(self.bundle.clone());
+            return serde_json::to_string(spec).context("failed to serialize spec");
+        }
+
+        if self.config.as_deref() == Some(Path::new(STDIN_CONFIG)) {
+            let mut config = String::new();
+            io::stdin()
+                .read_to_string(&mut config)
+                .context("failed to read config from stdin")?;
+            return Ok(config);
+        }
+
+        let path = self.config_path();
+        fs::read_to_string(&path).with_context(|| format!("failed to read config {:?}", path))
+    }
+
+    // Keeps the config in the container directory, from where later commands,
+    // e.g. exec, load it, as the config the container was created from may
+    // not be a file or may change in the meantime. The config is kept with the
+    // root path of the spec the container was created with, which is absolute,
+    // so that it does not depend on the bundle anymore.
+    fn save_config(&self, container_dir: &Path, raw_config: &str, spec: &Spec) -> Result<PathBuf> {
+        // the raw config is patched instead of serializing the spec, as it may
+        // contain fields the spec does not know about, e.g. the scheduler
+        let mut config: serde_json::Value =
+            serde_json::from_str(raw_config).context("failed to parse config")?;
+        let root = spec.root().as_ref().context("no root in spec")?;
+        config["root"]["path"] = serde_json::to_value(root.path())?;
+        let config_path = container_dir.join("config.json");
+        fs::write(&config_path, serde_json::to_string(&config)?)
+            .with_context(|| format!("failed to write config to {:?}", config_path))?;
+        Ok(config_path)
+    }
+
     fn create_container_dir(&self) -> Result<PathBuf> {
         let container_dir = self.base.root_path.join(&self.base.container_id);
         log::debug!("container directory will be {:?}", container_dir);
 
         if container_dir.exists() {
-            bail!("container {} already exists", self.base.container_id);
+            return Err(anyhow!(ErrorKind::State).context(format!(
+                "container {} already exists",
+                self.base.container_id
+            )));
         }
 
         utils::create_dir_all(&container_dir).context("failed to create container dir")?;
@@ -112,13 +302,48 @@ impl<'a> InitContainerBuilder<'a> {
         Ok(container_dir)
     }
 
-    fn load_spec(&self) -> Result<Spec> {
-        let source_spec_path = self.bundle.join("config.json");
-        let mut spec = Spec::load(source_spec_path)?;
+    fn config_path(&self) -> PathBuf {
+        match &self.config {
+            Some(config) => config.clone(),
+            None => self.bundle.join("config.json"),
+        }
+    }
+
+    fn load_spec(&self, raw_config: &str) -> Result<Spec> {
+        let mut spec = match &self.spec {
+            Some(spec) => {
+                validate_platform(&serde_json::to_value(spec)?)?;
+                spec.clone()
+            }
+            None => parse_spec(raw_config)
+                .with_context(|| format!("failed to parse config {:?}", self.config_path()))?,
+        };
+        if let Some(rootfs) = &self.rootfs {
+            let mut root = spec.root().clone().unwrap_or_default();
+            root.set_path(rootfs.clone());
+            spec.set_root(Some(root));
+        }
+        if let Some(cgroup_path) = &self.cgroup_path {
+            let mut linux = spec.linux().clone().context("no linux in spec")?;
+            linux.set_cgroups_path(Some(cgroup_path.clone()));
+            spec.set_linux(Some(linux));
+        }
+        annotations::apply_security_labels(&mut spec)
+            .context("failed to apply security labels from annotations")?;
+        // rootless containers are managed by systemd as well
+        let use_systemd = self.use_systemd || Rootless::new(&spec)?.is_some();
+        annotations::apply_cpu_uclamp(&mut spec, use_systemd)
+            .context("failed to apply cpu utilization clamps from annotations")?;
+        annotations::apply_memory_min(&mut spec)
+            .context("failed to apply guaranteed memory from annotations")?;
+        annotations::apply_memory_high(&mut spec)
+            .context("failed to apply memory throttling limit from annotations")?;
         Self::validate_spec(&spec).context("failed to validate runtime spec")?;
 
         spec.canonicalize_rootfs(&self.bundle)
             .context("failed to canonicalize rootfs")?;
+        resolve_overlay_mounts(&mut spec, &self.bundle)
+            .context("failed to resolve overlay mounts")?;
         Ok(spec)
     }
 
@@ -145,6 +370,31 @@ impl<'a> InitContainerBuilder<'a> {
         Ok(())
     }
 
+    // Cheap preflight that the host can satisfy the pids limit of the container,
+    // otherwise the container may fail later on in a way that is hard to relate
+    // to the exhausted pids of the host.
+    fn check_pids_limit(&self, spec: &Spec) -> Result<()> {
+        let limit = spec
+            .linux()
+            .as_ref()
+            .and_then(|l| l.resources().as_ref())
+            .and_then(|r| r.pids().as_ref())
+            .map(|p| p.limit());
+        // no limit, or -1/0 for unlimited
+        let limit = match limit {
+            Some(limit) if limit > 0 => limit as u64,
+            _ => return Ok(()),
+        };
+
+        match available_pids() {
+            Ok(available) => check_available_pids(limit, available, self.strict_pids_limit),
+            Err(err) => {
+                log::debug!("skip pids limit preflight: {:?}", err);
+                Ok(())
+            }
+        }
+    }
+
     fn create_container_state(&self, container_dir: &Path) -> Result<Container> {
         let container = Container::new(
             &self.base.container_id,
@@ -157,3 +407,457 @@ impl<'a> InitContainerBuilder<'a> {
         Ok(container)
     }
 }
+
+// Relative directories of overlay mounts are relative to the bundle, they are
+// resolved here as the container process does not run in the bundle.
+fn resolve_overlay_mounts(spec: &mut Spec, bundle: &Path) -> Result<()> {
+    let mounts = match spec.mounts() {
+        Some(mounts) => mounts.clone(),
+        None => return Ok(()),
+    };
+
+    let mounts = mounts
+        .into_iter()
+        .map(|mut mount| {
+            if mount.typ().as_deref() == Some("overlay") {
+                if let Some(options) = mount.options() {
+                    let options = resolve_overlay_options(options, bundle)?;
+                    mount.set_options(Some(options));
+                }
+            }
+            Ok(mount)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    spec.set_mounts(Some(mounts));
+
+    Ok(())
+}
+
+// Number of pids which can still be allocated on the host, i.e. pid_max minus
+// the number of tasks which currently exist.
+fn available_pids() -> Result<u64> {
+    let pid_max: u64 = fs::read_to_string("/proc/sys/kernel/pid_max")?
+        .trim()
+        .parse()?;
+    // the fourth field of loadavg is <runnable>/<existing> scheduling entities
+    let loadavg = fs::read_to_string("/proc/loadavg")?;
+    let tasks: u64 = loadavg
+        .split_whitespace()
+        .nth(3)
+        .and_then(|f| f.split_once('/'))
+        .with_context(|| format!("unexpected format of /proc/loadavg: {}", loadavg))?
+        .1
+        .parse()?;
+
+    Ok(pid_max.saturating_sub(tasks))
+}
+
+// A spec for another platform either fails to deserialize, e.g. because the
+// user of a windows process has no uid, or fails later on in obscure ways
+// because everything a Linux container needs is missing. It is therefore
+// checked before it is deserialized.
+fn validate_platform(config: &serde_json::Value) -> Result<()> {
+    let sections = [
+        ("windows", config.get("windows")),
+        ("solaris", config.get("solaris")),
+        (
+            "process.commandLine",
+            config.get("process").and_then(|p| p.get("commandLine")),
+        ),
+    ];
+
+    match sections
+        .iter()
+        .find(|(_, value)| value.map_or(false, |v| !v.is_null()))
+    {
+        Some((field, _)) => bail!(
+            "unsupported platform section {} in runtime spec, only linux containers are supported",
+            field
+        ),
+        None => Ok(()),
+    }
+}
+
+fn check_available_pids(limit: u64, available: u64, strict: bool) -> Result<()> {
+    if limit <= available {
+        return Ok(());
+    }
+
+    let msg = format!(
+        "pids limit {} of the container exceeds the {} pids available on the host",
+        limit, available
+    );
+    if strict {
+        bail!(msg);
+    }
+
+    log::warn!("{}", msg);
+    Ok(())
+}
+
+// The error of serde_json points to the line and column of invalid json
+fn parse_spec(config: &str) -> Result<Spec> {
+    validate_platform(&serde_json::from_str(config)?)?;
+    Ok(serde_json::from_str(config)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscall::test::TestHelperSyscall;
+    use crate::utils::create_temp_dir;
+    use oci_spec::runtime::{
+        HookBuilder, HooksBuilder, LinuxBuilder, MountBuilder, ProcessBuilder, SpecBuilder,
+    };
+    use serial_test::serial;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_resolve_overlay_mounts() -> Result<()> {
+        let overlay = MountBuilder::default()
+            .destination("/merged")
+            .typ("overlay")
+            .source("overlay")
+            .options(vec![
+                "lowerdir=lower".to_string(),
+                "upperdir=upper".to_string(),
+                "workdir=work".to_string(),
+            ])
+            .build()?;
+        let tmpfs = MountBuilder::default()
+            .destination("/tmp")
+            .typ("tmpfs")
+            .source("tmpfs")
+            .options(vec!["size=workdir".to_string()])
+            .build()?;
+        let mut spec = SpecBuilder::default()
+            .mounts(vec![overlay, tmpfs.clone()])
+            .build()?;
+
+        resolve_overlay_mounts(&mut spec, Path::new("/bundle"))?;
+        let mounts = spec.mounts().as_ref().unwrap();
+        assert_eq!(
+            mounts[0].options().as_ref().unwrap(),
+            &vec![
+                "lowerdir=/bundle/lower".to_string(),
+                "upperdir=/bundle/upper".to_string(),
+                "workdir=/bundle/work".to_string(),
+            ]
+        );
+        assert_eq!(mounts[1], tmpfs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_available_pids() {
+        assert!(check_available_pids(100, 1000, true).is_ok());
+        assert!(check_available_pids(1000, 1000, true).is_ok());
+    }
+
+    #[test]
+    fn test_check_available_pids_low() {
+        // only a warning is logged if the check is not strict
+        assert!(check_available_pids(1000, 10, false).is_ok());
+
+        let err = check_available_pids(1000, 10, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pids limit 1000 of the container exceeds the 10 pids available on the host"
+        );
+    }
+
+    #[test]
+    fn test_available_pids() -> Result<()> {
+        let pid_max: u64 = fs::read_to_string("/proc/sys/kernel/pid_max")?
+            .trim()
+            .parse()?;
+        let available = available_pids()?;
+        assert!(available > 0);
+        assert!(available < pid_max);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_spec() -> Result<()> {
+        let spec = serde_json::to_string(&Spec::default())?;
+        assert_eq!(parse_spec(&spec)?, Spec::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_spec_invalid() {
+        let err = parse_spec("{\n  \"ociVersion\": \"1.0.2\",\n  \"root\": \n}").unwrap_err();
+        assert!(
+            err.to_string().contains("line 4 column 1"),
+            "no position in error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_load_spec_from_memory() -> Result<()> {
+        let tmp = create_temp_dir("test_load_spec_from_memory")?;
+        let rootfs = tmp.join("rootfs");
+        fs::create_dir(&rootfs)?;
+        let overlay = MountBuilder::default()
+            .destination("/merged")
+            .typ("overlay")
+            .source("overlay")
+            .options(vec!["lowerdir=lower".to_string()])
+            .build()?;
+        let spec = SpecBuilder::default()
+            .version("1.0.2")
+            .mounts(vec![overlay])
+            .build()?;
+
+        let syscall = TestHelperSyscall::default();
+        let mut builder = ContainerBuilder::new("spec".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .as_init_from_spec(spec.clone())
+            .with_rootfs(&rootfs);
+        let raw_config = builder.raw_config()?;
+        assert_eq!(parse_spec(&raw_config)?, spec);
+
+        // relative paths are resolved against the root filesystem
+        let loaded = builder.load_spec(&raw_config)?;
+        let rootfs = fs::canonicalize(&rootfs)?;
+        assert_eq!(loaded.root().as_ref().unwrap().path(), &rootfs);
+        assert_eq!(
+            loaded.mounts().as_ref().unwrap()[0]
+                .options()
+                .as_ref()
+                .unwrap(),
+            &vec![format!("lowerdir={}", rootfs.join("lower").display())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exec_spec_of_container_from_other_config() -> Result<()> {
+        let tmp = create_temp_dir("test_exec_spec_of_container_from_other_config")?;
+        fs::create_dir(tmp.join("rootfs"))?;
+        let bundle_config =
+            r#"{"ociVersion":"1.0.2","root":{"path":"rootfs"},"hostname":"bundle"}"#;
+        fs::write(tmp.join("config.json"), bundle_config)?;
+        let other_config = r#"{"ociVersion":"1.0.2","root":{"path":"rootfs"},"hostname":"other"}"#;
+        let other_config_path = tmp.join("other.json");
+        fs::write(&other_config_path, other_config)?;
+
+        let syscall = TestHelperSyscall::default();
+        let mut builder = ContainerBuilder::new("other".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .as_init(tmp.to_path_buf())
+            .with_config(Some(&other_config_path));
+        let raw_config = builder.raw_config()?;
+        let spec = builder.load_spec(&raw_config)?;
+        let container_dir = builder.create_container_dir()?;
+        builder.create_container_state(&container_dir)?;
+        builder.save_config(&container_dir, &raw_config, &spec)?;
+        // the config may change once the container has been created
+        fs::remove_file(&other_config_path)?;
+
+        let container = Container::load(container_dir)?;
+        let exec_spec = ContainerBuilder::new("other".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .as_tenant()
+            .load_init_spec(&container)?;
+        assert_eq!(exec_spec.hostname().as_deref(), Some("other"));
+        assert_eq!(
+            exec_spec.root().as_ref().unwrap().path(),
+            &fs::canonicalize(tmp.join("rootfs"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exec_spec_of_container_from_memory() -> Result<()> {
+        let tmp = create_temp_dir("test_exec_spec_of_container_from_memory")?;
+        let rootfs = tmp.join("rootfs");
+        fs::create_dir(&rootfs)?;
+        let spec = SpecBuilder::default().version("1.0.2").build()?;
+
+        let syscall = TestHelperSyscall::default();
+        let mut builder = ContainerBuilder::new("spec".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .as_init_from_spec(spec)
+            .with_rootfs(&rootfs);
+        let raw_config = builder.raw_config()?;
+        let spec = builder.load_spec(&raw_config)?;
+        let container_dir = builder.create_container_dir()?;
+        builder.create_container_state(&container_dir)?;
+        builder.save_config(&container_dir, &raw_config, &spec)?;
+
+        // exec loads the spec of the container from its directory
+        let container = Container::load(container_dir)?;
+        let exec_spec = ContainerBuilder::new("spec".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .as_tenant()
+            .load_init_spec(&container)?;
+        assert_eq!(
+            exec_spec.root().as_ref().unwrap().path(),
+            &fs::canonicalize(&rootfs)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_build_timeout_leaves_nothing_behind() -> Result<()> {
+        let tmp = create_temp_dir("test_build_timeout_leaves_nothing_behind")?;
+        let rootfs = tmp.join("rootfs");
+        fs::create_dir(&rootfs)?;
+        // the hook blocks the container process within the creation
+        let hooks = HooksBuilder::default()
+            .create_container(vec![HookBuilder::default()
+                .path("/bin/sleep")
+                .args(vec!["sleep".to_owned(), "10".to_owned()])
+                .build()?])
+            .build()?;
+        let spec = SpecBuilder::default()
+            .hooks(hooks)
+            .process(ProcessBuilder::default().rlimits(vec![]).build()?)
+            .linux(
+                LinuxBuilder::default()
+                    .namespaces(vec![])
+                    .cgroups_path("/youki/test_build_timeout")
+                    .build()?,
+            )
+            .build()?;
+
+        let syscall = TestHelperSyscall::default();
+        let started = Instant::now();
+        let err = ContainerBuilder::new("timeout".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .with_timeout(Some(Duration::from_millis(500)))
+            .as_init_from_spec(spec)
+            .with_rootfs(&rootfs)
+            .with_systemd(false)
+            .build()
+            .unwrap_err();
+        assert!(format!("{:?}", err).contains("timed out"), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        assert!(!tmp.join("state").join("timeout").exists());
+        let cgroup_paths =
+            libcgroups::common::get_cgroup_hierarchy_paths(Path::new("/youki/test_build_timeout"))?;
+        for path in cgroup_paths {
+            assert!(!path.exists(), "{:?} was not removed", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_build_timeout_kills_init_in_joined_cgroup() -> Result<()> {
+        let tmp = create_temp_dir("test_build_timeout_kills_init_in_joined_cgroup")?;
+        let rootfs = tmp.join("rootfs");
+        fs::create_dir(&rootfs)?;
+        // the cgroup is created by someone else before the container joins it
+        let cgroup_path = Path::new("/youki/test_build_timeout_joined");
+        let cgroup_paths = libcgroups::common::get_cgroup_hierarchy_paths(cgroup_path)?;
+        for path in &cgroup_paths {
+            fs::create_dir_all(path)?;
+            let procs = path.join("cgroup.procs");
+            if !procs.exists() {
+                fs::write(procs, "")?;
+            }
+        }
+
+        // the hook records the pid of the container process, which runs it,
+        // and blocks it within the creation
+        let init_pid_file = tmp.join("init_pid");
+        let hooks = HooksBuilder::default()
+            .create_container(vec![HookBuilder::default()
+                .path("/bin/sh")
+                .args(vec![
+                    "sh".to_owned(),
+                    "-c".to_owned(),
+                    format!("echo $PPID > {}; exec sleep 10", init_pid_file.display()),
+                ])
+                .build()?])
+            .build()?;
+        let spec = SpecBuilder::default()
+            .hooks(hooks)
+            .annotations(HashMap::from([(
+                annotations::JOIN_CGROUP_ANNOTATION.to_owned(),
+                "true".to_owned(),
+            )]))
+            .process(ProcessBuilder::default().rlimits(vec![]).build()?)
+            .linux(
+                LinuxBuilder::default()
+                    .namespaces(vec![])
+                    .cgroups_path(cgroup_path)
+                    .build()?,
+            )
+            .build()?;
+
+        let syscall = TestHelperSyscall::default();
+        let result = ContainerBuilder::new("timeout_joined".to_owned(), &syscall)
+            .with_root_path(tmp.join("state"))?
+            .with_timeout(Some(Duration::from_millis(500)))
+            .as_init_from_spec(spec)
+            .with_rootfs(&rootfs)
+            .with_systemd(false)
+            .build();
+        let kept = cgroup_paths.iter().all(|path| path.exists());
+        for path in &cgroup_paths {
+            let _ = fs::remove_dir_all(path).or_else(|_| fs::remove_dir(path));
+        }
+
+        let err = result.unwrap_err();
+        assert!(format!("{:?}", err).contains("timed out"), "{:?}", err);
+        assert!(kept, "joined cgroup was removed");
+        let init_pid: i32 = fs::read_to_string(&init_pid_file)?.trim().parse()?;
+        let started = Instant::now();
+        while let Ok(stat) = procfs::process::Process::new(init_pid).and_then(|p| p.stat()) {
+            if stat.state == 'Z' {
+                break;
+            }
+            assert!(
+                started.elapsed() < Duration::from_secs(1),
+                "init process {} is still running",
+                init_pid
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_spec_for_other_platform() -> Result<()> {
+        let specs = [
+            (
+                "windows",
+                r#"{"ociVersion":"1.0.2","root":{"path":"rootfs"},"windows":{"layerFolders":["C:\\layers"]}}"#,
+            ),
+            (
+                "process.commandLine",
+                r#"{"ociVersion":"1.0.2","root":{"path":"rootfs"},"process":{"user":{"username":"ContainerUser"},"cwd":"C:\\","commandLine":"cmd.exe"}}"#,
+            ),
+        ];
+
+        for (field, config) in specs {
+            let tmp = create_temp_dir("test_load_spec_for_other_platform")?;
+            fs::create_dir(tmp.join("rootfs"))?;
+            fs::write(tmp.join("config.json"), config)?;
+
+            let syscall = TestHelperSyscall::default();
+            let mut builder = ContainerBuilder::new("other_platform".to_owned(), &syscall)
+                .with_root_path(tmp.join("state"))?
+                .as_init(tmp.to_path_buf());
+            let raw_config = builder.raw_config()?;
+            let err = builder
+                .load_spec(&raw_config)
+                .expect_err("spec for another platform");
+            assert_eq!(
+                err.root_cause().to_string(),
+                format!(
+                    "unsupported platform section {} in runtime spec, only linux containers are supported",
+                    field
+                )
+            );
+        }
+        Ok(())
+    }
+}
@@ -85,6 +85,10 @@ pub struct State {
     // Pid is the process ID for the container process.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pid: Option<i32>,
+    // Start time of the container process in clock ticks after boot, which
+    // tells it apart from a process which reused its pid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
     // Bundle is the path to the container's bundle directory.
     pub bundle: PathBuf,
     // Annotations are key values associated with the container.
@@ -98,6 +102,13 @@ pub struct State {
     pub creator: Option<u32>,
     // Specifies if systemd should be used to manage cgroups
     pub use_systemd: Option<bool>,
+    // Exit code of the container process, once it has exited and was reaped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    // The parent of the container process reaps it and records its exit
+    // code, e.g. the runtime running the container with --subreaper
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub records_exit_code: bool,
 }
 
 impl State {
@@ -114,11 +125,14 @@ impl State {
             id: container_id.to_string(),
             status,
             pid,
+            start_time: None,
             bundle,
             annotations: Some(HashMap::default()),
             created: None,
             creator: None,
             use_systemd: None,
+            exit_code: None,
+            records_exit_code: false,
         }
     }
 
@@ -1,11 +1,9 @@
-use anyhow::{bail, Context, Result};
-use caps::Capability;
+use anyhow::{anyhow, bail, Context, Result};
 use nix::fcntl::OFlag;
 use nix::unistd::{self, close, pipe2, read, Pid};
 use oci_spec::runtime::{
-    Capabilities as SpecCapabilities, Capability as SpecCapability, LinuxBuilder,
-    LinuxCapabilities, LinuxCapabilitiesBuilder, LinuxNamespace, LinuxNamespaceBuilder,
-    LinuxNamespaceType, Process, ProcessBuilder, Spec,
+    Capabilities as SpecCapabilities, LinuxBuilder, LinuxCapabilities, LinuxCapabilitiesBuilder,
+    LinuxNamespace, LinuxNamespaceBuilder, LinuxNamespaceType, Process, ProcessBuilder, Spec,
 };
 use procfs::process::Namespace;
 
@@ -15,12 +13,14 @@ use std::{
     ffi::{OsStr, OsString},
     fs,
     os::unix::prelude::RawFd,
-    path::{Path, PathBuf},
-    str::FromStr,
+    path::{Component, Path, PathBuf},
 };
 
+use crate::error::{ErrorKind, LibcontainerError};
+use crate::io_priority::IoPriority;
 use crate::process::args::ContainerType;
-use crate::{capabilities::CapabilityExt, container::builder_impl::ContainerBuilderImpl};
+use crate::scheduler::Scheduler;
+use crate::{capabilities, container::builder_impl::ContainerBuilderImpl};
 use crate::{notify_socket::NotifySocket, rootless::Rootless, tty, utils};
 
 use super::{builder::ContainerBuilder, Container};
@@ -28,18 +28,21 @@ use super::{builder::ContainerBuilder, Container};
 const NAMESPACE_TYPES: &[&str] = &["ipc", "uts", "net", "pid", "mnt", "cgroup"];
 const TENANT_NOTIFY: &str = "tenant-notify-";
 const TENANT_TTY: &str = "tenant-tty-";
+const TENANT_STDIO: &str = "tenant-stdio-";
 
 /// Builder that can be used to configure the properties of a process
 /// that will join an existing container sandbox
 pub struct TenantContainerBuilder<'a> {
     base: ContainerBuilder<'a>,
     env: HashMap<String, String>,
+    keep_env: Vec<String>,
     cwd: Option<PathBuf>,
     args: Vec<String>,
     no_new_privs: Option<bool>,
     capabilities: Vec<String>,
     process: Option<PathBuf>,
     detached: bool,
+    cgroup: Option<PathBuf>,
 }
 
 impl<'a> TenantContainerBuilder<'a> {
@@ -50,12 +53,14 @@ impl<'a> TenantContainerBuilder<'a> {
         Self {
             base: builder,
             env: HashMap::new(),
+            keep_env: Vec::new(),
             cwd: None,
             args: Vec::new(),
             no_new_privs: None,
             capabilities: Vec::new(),
             process: None,
             detached: false,
+            cgroup: None,
         }
     }
 
@@ -65,6 +70,15 @@ impl<'a> TenantContainerBuilder<'a> {
         self
     }
 
+    /// Passes the given environment variables of the host to the process in
+    /// addition to the environment of the container. Variables which are set
+    /// for the process or in the spec of the container take precedence and
+    /// variables which are not set on the host are skipped.
+    pub fn with_keep_env(mut self, keys: Vec<String>) -> Self {
+        self.keep_env = keys;
+        self
+    }
+
     /// Sets the working directory of the container
     pub fn with_cwd<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
         self.cwd = path.map(|p| p.into());
@@ -97,8 +111,20 @@ impl<'a> TenantContainerBuilder<'a> {
         self
     }
 
+    /// Places the process in a sub-cgroup of the cgroup of the container. The
+    /// path is relative to the cgroup of the container, the sub-cgroup is
+    /// created if it does not exist and inherits the controllers enabled for
+    /// the children of the container cgroup. On cgroup v2, these can not be
+    /// enabled anymore once the container cgroup contains processes, so the
+    /// exec fails if the container cgroup has no controllers enabled for its
+    /// children.
+    pub fn with_cgroup<P: Into<PathBuf>>(mut self, path: Option<P>) -> Self {
+        self.cgroup = path.map(|p| p.into());
+        self
+    }
+
     /// Joins an existing container
-    pub fn build(self) -> Result<Pid> {
+    pub fn build(self) -> Result<Pid, LibcontainerError> {
         let container_dir = self
             .lookup_container_dir()
             .context("failed to look up container dir")?;
@@ -113,19 +139,26 @@ impl<'a> TenantContainerBuilder<'a> {
 
         log::debug!("{:#?}", spec);
 
-        unistd::chdir(&container_dir)?;
+        unistd::chdir(&container_dir)
+            .with_context(|| format!("failed to chdir into {:?}", container_dir))?;
         let notify_path = Self::setup_notify_listener(&container_dir)?;
         // convert path of root file system of the container to absolute path
-        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())?;
+        let rootfs = fs::canonicalize(spec.root().as_ref().context("no root in spec")?.path())
+            .context("failed to canonicalize rootfs")?;
 
         // if socket file path is given in commandline options,
         // get file descriptors of console socket
         let csocketfd = self.setup_tty_socket(&container_dir)?;
+        let stdio_socketfd = self.setup_stdio_socket(&container_dir)?;
 
         let use_systemd = self.should_use_systemd(&container);
         let rootless = Rootless::new(&spec)?;
 
-        let (read_end, write_end) = pipe2(OFlag::O_CLOEXEC)?;
+        let scheduler = self.get_scheduler()?;
+        let io_priority = self.get_io_priority()?;
+
+        let (read_end, write_end) =
+            pipe2(OFlag::O_CLOEXEC).context("failed to create exec notify pipe")?;
 
         let mut builder_impl = ContainerBuilderImpl {
             container_type: ContainerType::TenantContainer {
@@ -135,6 +168,7 @@ impl<'a> TenantContainerBuilder<'a> {
             container_id: self.base.container_id,
             pid_file: self.base.pid_file,
             console_socket: csocketfd,
+            stdio_socket: stdio_socketfd,
             use_systemd,
             spec: &spec,
             rootfs,
@@ -143,6 +177,17 @@ impl<'a> TenantContainerBuilder<'a> {
             container: None,
             preserve_fds: self.base.preserve_fds,
             detached: self.detached,
+            init: false,
+            scheduler,
+            io_priority,
+            audit_capabilities: self.base.audit_capabilities,
+            // the process joins the session keyring of the container
+            new_keyring: false,
+            join_cgroup: false,
+            // the timeout is only for the creation of the container, a process
+            // which is executed in it takes as long as it takes
+            timeout: None,
+            reexec_args: self.base.reexec,
         };
 
         let pid = builder_impl.create()?;
@@ -150,18 +195,20 @@ impl<'a> TenantContainerBuilder<'a> {
         let mut notify_socket = NotifySocket::new(notify_path);
         notify_socket.notify_container_start()?;
 
-        close(write_end)?;
+        close(write_end).context("failed to close write end of exec notify pipe")?;
 
         let mut err_str_buf = Vec::new();
 
         loop {
             let mut buf = [0; 3];
-            match read(read_end, &mut buf)? {
+            match read(read_end, &mut buf).context("failed to read from exec notify pipe")? {
                 0 => {
                     if err_str_buf.is_empty() {
                         return Ok(pid);
                     } else {
-                        bail!(String::from_utf8_lossy(&err_str_buf).to_string());
+                        return Err(
+                            anyhow!(String::from_utf8_lossy(&err_str_buf).to_string()).into()
+                        );
                     }
                 }
                 _ => {
@@ -174,14 +221,17 @@ impl<'a> TenantContainerBuilder<'a> {
     fn lookup_container_dir(&self) -> Result<PathBuf> {
         let container_dir = self.base.root_path.join(&self.base.container_id);
         if !container_dir.exists() {
-            bail!("container {} does not exist", self.base.container_id);
+            return Err(anyhow!(ErrorKind::State).context(format!(
+                "container {} does not exist",
+                self.base.container_id
+            )));
         }
 
         Ok(container_dir)
     }
 
-    fn load_init_spec(&self, container: &Container) -> Result<Spec> {
-        let spec_path = container.bundle().join("config.json");
+    pub(super) fn load_init_spec(&self, container: &Container) -> Result<Spec> {
+        let spec_path = container.spec_path();
 
         let mut spec = Spec::load(&spec_path)
             .with_context(|| format!("failed to load spec from {:?}", spec_path))?;
@@ -194,17 +244,17 @@ impl<'a> TenantContainerBuilder<'a> {
     fn load_container_state(&self, container_dir: PathBuf) -> Result<Container> {
         let container = Container::load(container_dir)?;
         if !container.can_exec() {
-            bail!(
+            return Err(anyhow!(ErrorKind::State).context(format!(
                 "Cannot exec as container is in state {}",
                 container.status()
-            );
+            )));
         }
 
         Ok(container)
     }
 
     fn adapt_spec_for_tenant(&self, spec: &mut Spec, container: &Container) -> Result<()> {
-        let process = if let Some(process) = &self.process {
+        let mut process = if let Some(process) = &self.process {
             self.get_process(process)?
         } else {
             let mut process_builder = ProcessBuilder::default()
@@ -224,6 +274,20 @@ impl<'a> TenantContainerBuilder<'a> {
 
             process_builder.build()?
         };
+        if !self.keep_env.is_empty() {
+            let spec_env = spec
+                .process()
+                .as_ref()
+                .and_then(|p| p.env().clone())
+                .unwrap_or_default();
+            let env = keep_host_env(
+                process.env().clone().unwrap_or_default(),
+                spec_env,
+                &self.keep_env,
+                |key| std::env::var(key).ok(),
+            );
+            process.set_env(Some(env));
+        }
 
         if container.pid().is_none() {
             bail!("could not retrieve container init pid");
@@ -231,7 +295,15 @@ impl<'a> TenantContainerBuilder<'a> {
 
         let init_process = procfs::process::Process::new(container.pid().unwrap().as_raw())?;
         let ns = self.get_namespaces(init_process.namespaces()?)?;
-        let linux = LinuxBuilder::default().namespaces(ns).build()?;
+        let mut linux_builder = LinuxBuilder::default().namespaces(ns);
+        if let Some(cgroup) = &self.cgroup {
+            if self.should_use_systemd(container) {
+                bail!("sub-cgroups are not supported for containers managed by systemd");
+            }
+            let container_cgroup = container.spec()?.cgroup_path;
+            linux_builder = linux_builder.cgroups_path(sub_cgroup_path(&container_cgroup, cgroup)?);
+        }
+        let linux = linux_builder.build()?;
 
         spec.set_process(Some(process)).set_linux(Some(linux));
         Ok(())
@@ -250,6 +322,22 @@ impl<'a> TenantContainerBuilder<'a> {
         Ok(process_spec)
     }
 
+    fn get_scheduler(&self) -> Result<Option<Scheduler>> {
+        match &self.process {
+            Some(process) => Scheduler::from_process(process).context("failed to load scheduler"),
+            None => Ok(None),
+        }
+    }
+
+    fn get_io_priority(&self) -> Result<Option<IoPriority>> {
+        match &self.process {
+            Some(process) => {
+                IoPriority::from_process(process).context("failed to load io priority")
+            }
+            None => Ok(None),
+        }
+    }
+
     fn get_working_dir(&self) -> Result<Option<PathBuf>> {
         if let Some(cwd) = &self.cwd {
             if cwd.is_relative() {
@@ -285,13 +373,11 @@ impl<'a> TenantContainerBuilder<'a> {
 
     fn get_capabilities(&self, spec: &Spec) -> Result<Option<LinuxCapabilities>> {
         if !self.capabilities.is_empty() {
-            let mut caps: Vec<Capability> = Vec::with_capacity(self.capabilities.len());
-            for cap in &self.capabilities {
-                caps.push(Capability::from_str(cap)?);
-            }
-
-            let caps: SpecCapabilities =
-                caps.iter().map(|c| SpecCapability::from_cap(*c)).collect();
+            let caps = self
+                .capabilities
+                .iter()
+                .map(|cap| capabilities::parse_capability(cap))
+                .collect::<Result<SpecCapabilities>>()?;
 
             if let Some(spec_caps) = spec
                 .process()
@@ -406,6 +492,21 @@ impl<'a> TenantContainerBuilder<'a> {
         Ok(csocketfd)
     }
 
+    fn setup_stdio_socket(&self, container_dir: &Path) -> Result<Option<RawFd>> {
+        let stdio_name = Self::generate_name(container_dir, TENANT_STDIO);
+        let stdio_socketfd = if let Some(stdio_socket) = &self.base.stdio_socket {
+            Some(tty::setup_console_socket(
+                container_dir,
+                stdio_socket,
+                &stdio_name,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(stdio_socketfd)
+    }
+
     fn generate_name(dir: &Path, prefix: &str) -> String {
         loop {
             let rand = fastrand::i32(..);
@@ -416,3 +517,110 @@ impl<'a> TenantContainerBuilder<'a> {
         }
     }
 }
+
+// The sub-cgroup has to stay within the cgroup tree of the container, so only
+// plain relative paths are accepted.
+fn sub_cgroup_path(container_cgroup: &Path, sub_cgroup: &Path) -> Result<PathBuf> {
+    let mut components = sub_cgroup.components().peekable();
+    if components.peek().is_none() || !components.all(|c| matches!(c, Component::Normal(_))) {
+        bail!(
+            "sub-cgroup {:?} is not a relative path within the cgroup of the container",
+            sub_cgroup
+        );
+    }
+
+    Ok(container_cgroup.join(sub_cgroup))
+}
+
+// Adds the variables of the spec of the container which the environment of
+// the process does not set, and then the values the host has for the keys,
+// unless either of them sets the variable already
+fn keep_host_env<F>(
+    mut env: Vec<String>,
+    spec_env: Vec<String>,
+    keys: &[String],
+    host_var: F,
+) -> Vec<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    fn is_set(env: &[String], key: &str) -> bool {
+        env.iter()
+            .any(|var| var.split_once('=').map_or(var.as_str(), |(k, _)| k) == key)
+    }
+
+    for var in spec_env {
+        let key = var.split_once('=').map_or(var.as_str(), |(k, _)| k);
+        if !is_set(&env, key) {
+            env.push(var);
+        }
+    }
+    for key in keys {
+        if is_set(&env, key) {
+            continue;
+        }
+        if let Some(value) = host_var(key) {
+            env.push(format!("{}={}", key, value));
+        }
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_cgroup_path() -> Result<()> {
+        assert_eq!(
+            sub_cgroup_path(Path::new("/youki/container"), Path::new("debug"))?,
+            PathBuf::from("/youki/container/debug")
+        );
+        assert_eq!(
+            sub_cgroup_path(Path::new("container"), Path::new("debug/nested"))?,
+            PathBuf::from("container/debug/nested")
+        );
+
+        for invalid in ["", "/debug", "../debug", "debug/../../other", "./debug"] {
+            assert!(
+                sub_cgroup_path(Path::new("/youki/container"), Path::new(invalid)).is_err(),
+                "{:?} should be rejected",
+                invalid
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_host_env() {
+        let host_var = |key: &str| match key {
+            "KEPT" | "PROCESS_CONFLICT" | "SPEC_CONFLICT" => Some("host".to_owned()),
+            _ => None,
+        };
+
+        let env = keep_host_env(
+            vec!["PROCESS_CONFLICT=process".to_owned()],
+            vec![
+                "PATH=/bin".to_owned(),
+                "SPEC_CONFLICT=spec".to_owned(),
+                "PROCESS_CONFLICT=spec".to_owned(),
+            ],
+            &[
+                "KEPT".to_owned(),
+                "PROCESS_CONFLICT".to_owned(),
+                "SPEC_CONFLICT".to_owned(),
+                "MISSING".to_owned(),
+            ],
+            host_var,
+        );
+        assert_eq!(
+            env,
+            vec![
+                "PROCESS_CONFLICT=process",
+                "PATH=/bin",
+                "SPEC_CONFLICT=spec",
+                "KEPT=host",
+            ]
+        );
+    }
+}
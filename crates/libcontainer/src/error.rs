@@ -0,0 +1,139 @@
+//! Errors returned by the public API of libcontainer
+//!
+//! Internally libcontainer uses anyhow. Errors which cross the API boundary
+//! are classified into a [`LibcontainerError`], so that consumers can match
+//! on the kind of failure. Deep inside, e.g. in the container processes, the
+//! kind is attached to an error as context with an [`ErrorKind`], below a
+//! descriptive context, so that the top of the error still tells what failed.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Kind of a failure, attached as context to an anyhow error
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorKind {
+    Cgroup,
+    Namespace,
+    Mount,
+    Seccomp,
+    State,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let print = match self {
+            Self::Cgroup => "cgroup error",
+            Self::Namespace => "namespace error",
+            Self::Mount => "mount error",
+            Self::Seccomp => "seccomp error",
+            Self::State => "state error",
+        };
+
+        write!(f, "{}", print)
+    }
+}
+
+/// Error returned by the public API of libcontainer
+#[derive(Debug)]
+pub enum LibcontainerError {
+    /// Setting up or changing the cgroups of the container failed
+    Cgroup(anyhow::Error),
+    /// Creating or joining the namespaces of the container failed
+    Namespace(anyhow::Error),
+    /// Setting up the mounts or the root filesystem of the container failed
+    Mount(anyhow::Error),
+    /// Setting up the seccomp filter of the container failed
+    Seccomp(anyhow::Error),
+    /// The container does not exist or its state does not allow the operation
+    State(anyhow::Error),
+    /// Any other failure
+    Other(anyhow::Error),
+}
+
+impl LibcontainerError {
+    /// Returns the kind of the failure, none for other failures
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            Self::Cgroup(_) => Some(ErrorKind::Cgroup),
+            Self::Namespace(_) => Some(ErrorKind::Namespace),
+            Self::Mount(_) => Some(ErrorKind::Mount),
+            Self::Seccomp(_) => Some(ErrorKind::Seccomp),
+            Self::State(_) => Some(ErrorKind::State),
+            Self::Other(_) => None,
+        }
+    }
+
+    fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::Cgroup(err)
+            | Self::Namespace(err)
+            | Self::Mount(err)
+            | Self::Seccomp(err)
+            | Self::State(err)
+            | Self::Other(err) => err,
+        }
+    }
+}
+
+impl fmt::Display for LibcontainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.inner(), f)
+    }
+}
+
+impl std::error::Error for LibcontainerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
+/// Classifies an error by the first kind attached to it as context
+impl From<anyhow::Error> for LibcontainerError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::Cgroup) => Self::Cgroup(err),
+            Some(ErrorKind::Namespace) => Self::Namespace(err),
+            Some(ErrorKind::Mount) => Self::Mount(err),
+            Some(ErrorKind::Seccomp) => Self::Seccomp(err),
+            Some(ErrorKind::State) => Self::State(err),
+            None => Self::Other(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_classify_by_context() {
+        let err: anyhow::Error = Err::<(), _>(anyhow!("mkdir failed"))
+            .context(ErrorKind::Mount)
+            .context("failed to prepare rootfs")
+            .unwrap_err();
+        let err = LibcontainerError::from(err);
+        assert!(matches!(err, LibcontainerError::Mount(_)));
+        assert_eq!(err.kind(), Some(ErrorKind::Mount));
+        assert_eq!(err.to_string(), "failed to prepare rootfs");
+    }
+
+    #[test]
+    fn test_classify_untagged() {
+        let err = LibcontainerError::from(anyhow!("something went wrong"));
+        assert!(matches!(err, LibcontainerError::Other(_)));
+        assert_eq!(err.kind(), None);
+    }
+
+    #[test]
+    fn test_source_chain() {
+        let err: anyhow::Error = Err::<(), _>(anyhow!("no space left"))
+            .context(ErrorKind::Cgroup)
+            .unwrap_err();
+        let err = LibcontainerError::from(err);
+        let chain: Vec<String> = anyhow::Error::from(err)
+            .chain()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(chain, vec!["cgroup error", "no space left"]);
+    }
+}
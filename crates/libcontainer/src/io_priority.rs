@@ -0,0 +1,117 @@
+//! IO scheduling class and priority of the container process (`process.ioPriority`).
+//!
+//! Like the scheduler, this is not covered by oci-spec-rs yet and is read directly
+//! from the raw json of the configuration.
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::utils;
+
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+/// IO scheduling classes as defined by ioprio_set(2).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Class {
+    #[serde(rename = "IOPRIO_CLASS_RT")]
+    RealTime,
+    #[serde(rename = "IOPRIO_CLASS_BE")]
+    BestEffort,
+    #[serde(rename = "IOPRIO_CLASS_IDLE")]
+    Idle,
+}
+
+impl Class {
+    fn as_raw(self) -> i32 {
+        match self {
+            Class::RealTime => 1,
+            Class::BestEffort => 2,
+            Class::Idle => 3,
+        }
+    }
+}
+
+/// IO priority configuration of the container process.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: Class,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl IoPriority {
+    /// Reads the IO priority of the process from the config.json of a bundle.
+    pub fn from_config<P: AsRef<Path>>(config: P) -> Result<Option<Self>> {
+        utils::read_json_pointer(config.as_ref(), "/process/ioPriority")
+    }
+
+    /// Reads the IO priority from a process.json, as passed to exec.
+    pub fn from_process<P: AsRef<Path>>(process: P) -> Result<Option<Self>> {
+        utils::read_json_pointer(process.as_ref(), "/ioPriority")
+    }
+
+    /// Encodes class and level into the value expected by ioprio_set(2).
+    fn as_ioprio(&self) -> Result<i32> {
+        if !(0..=7).contains(&self.priority) {
+            bail!(
+                "invalid io priority level {}, must be within 0..=7",
+                self.priority
+            );
+        }
+
+        Ok(self.class.as_raw() << IOPRIO_CLASS_SHIFT | self.priority)
+    }
+}
+
+/// Applies the IO priority to the calling process.
+pub fn set_io_priority(io_priority: &IoPriority) -> Result<()> {
+    let ioprio = io_priority.as_ioprio()?;
+    let res = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+    if res != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("failed to set io priority {:?}", io_priority));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_effort_encoding() -> Result<()> {
+        let io_priority = IoPriority {
+            class: Class::BestEffort,
+            priority: 4,
+        };
+        assert_eq!(io_priority.as_ioprio()?, (2 << 13) | 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_level_out_of_range() {
+        let io_priority = IoPriority {
+            class: Class::RealTime,
+            priority: 8,
+        };
+        assert!(io_priority.as_ioprio().is_err());
+    }
+
+    #[test]
+    fn test_deserialize() -> Result<()> {
+        let io_priority: IoPriority =
+            serde_json::from_str(r#"{"class": "IOPRIO_CLASS_IDLE", "priority": 0}"#)?;
+        assert_eq!(
+            io_priority,
+            IoPriority {
+                class: Class::Idle,
+                priority: 0,
+            }
+        );
+
+        Ok(())
+    }
+}
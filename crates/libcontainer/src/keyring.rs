@@ -0,0 +1,62 @@
+//! Session keyring of the container process.
+//!
+//! The session keyring is inherited on fork, so without a new one the container
+//! process can access the keys of the session which started the runtime.
+use anyhow::Result;
+use std::ffi::CString;
+
+const KEYCTL_GET_KEYRING_ID: libc::c_int = 0;
+const KEYCTL_JOIN_SESSION_KEYRING: libc::c_int = 1;
+const KEY_SPEC_SESSION_KEYRING: libc::c_int = -3;
+
+/// Creates a new session keyring with the given name and makes it the session
+/// keyring of the calling process. Returns the serial number of the keyring.
+pub fn join_session_keyring(name: Option<&str>) -> Result<i32> {
+    let name = name.map(CString::new).transpose()?;
+    let name_ptr = name.as_ref().map_or(std::ptr::null(), |n| n.as_ptr());
+    let res = unsafe { libc::syscall(libc::SYS_keyctl, KEYCTL_JOIN_SESSION_KEYRING, name_ptr) };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(res as i32)
+}
+
+/// Returns the serial number of the session keyring of the calling process.
+pub fn session_keyring() -> Result<i32> {
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_keyctl,
+            KEYCTL_GET_KEYRING_ID,
+            KEY_SPEC_SESSION_KEYRING,
+            0,
+        )
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(res as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils;
+    use anyhow::bail;
+
+    #[test]
+    fn test_join_session_keyring() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            let parent = session_keyring()?;
+            let keyring = join_session_keyring(Some("_ses.youki_test"))?;
+            if keyring == parent {
+                bail!("expected a new session keyring, got the one of the parent");
+            }
+            if session_keyring()? != keyring {
+                bail!("expected the new keyring to be the session keyring");
+            }
+            Ok(())
+        })
+    }
+}
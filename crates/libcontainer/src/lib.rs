@@ -1,16 +1,25 @@
+pub mod annotations;
 pub mod apparmor;
 pub mod capabilities;
 pub mod config;
 pub mod container;
+pub mod error;
 pub mod hooks;
+pub mod io_priority;
+pub mod keyring;
 pub mod namespaces;
+pub mod notify_relay;
 pub mod notify_socket;
+pub mod pidfd;
 pub mod process;
 pub mod rootfs;
 pub mod rootless;
+pub mod scheduler;
 pub mod seccomp;
 pub mod signal;
+pub mod subreaper;
 pub mod syscall;
 pub mod tty;
+pub mod user;
 pub mod utils;
 pub mod workload;
@@ -13,16 +13,6 @@ use nix::{fcntl, sched::CloneFlags, sys::stat, unistd};
 use oci_spec::runtime::{LinuxNamespace, LinuxNamespaceType};
 use std::collections;
 
-static ORDERED_NAMESPACES: &[CloneFlags] = &[
-    CloneFlags::CLONE_NEWUSER,
-    CloneFlags::CLONE_NEWPID,
-    CloneFlags::CLONE_NEWUTS,
-    CloneFlags::CLONE_NEWIPC,
-    CloneFlags::CLONE_NEWNET,
-    CloneFlags::CLONE_NEWCGROUP,
-    CloneFlags::CLONE_NEWNS,
-];
-
 /// Holds information about namespaces
 pub struct Namespaces {
     command: Box<dyn Syscall>,
@@ -41,6 +31,42 @@ fn get_clone_flag(namespace_type: LinuxNamespaceType) -> CloneFlags {
     }
 }
 
+// Position of a namespace in the sequence in which namespaces are created or joined.
+fn creation_order(namespace_type: LinuxNamespaceType) -> u8 {
+    match namespace_type {
+        // Every namespace is owned by the user namespace the process is in when
+        // the namespace is created. The user namespace comes first, so that the
+        // other namespaces are owned by it and root of the container has
+        // capabilities over them once the id mappings are written.
+        LinuxNamespaceType::User => 0,
+        // Only applies to children of the process, which is why it is
+        // unshared before the init process is forked.
+        LinuxNamespaceType::Pid => 1,
+        // Independent of the other namespaces.
+        LinuxNamespaceType::Uts => 2,
+        LinuxNamespaceType::Ipc => 3,
+        // Before the mount namespace: sysfs shows the devices of the network
+        // namespace of the process mounting it and mounting sysfs inside a user
+        // namespace requires the network namespace to be owned by it.
+        LinuxNamespaceType::Network => 4,
+        // Before the mount namespace, so that a cgroup filesystem mounted for
+        // the container has the cgroup of the container as root.
+        LinuxNamespaceType::Cgroup => 5,
+        // Last, the rootfs is prepared once all other namespaces are in place.
+        LinuxNamespaceType::Mount => 6,
+    }
+}
+
+/// Sorts namespaces into the sequence in which they have to be created or joined.
+pub fn order_namespaces<'a, I>(namespaces: I) -> Vec<&'a LinuxNamespace>
+where
+    I: IntoIterator<Item = &'a LinuxNamespace>,
+{
+    let mut ordered: Vec<&LinuxNamespace> = namespaces.into_iter().collect();
+    ordered.sort_by_key(|ns| creation_order(ns.typ()));
+    ordered
+}
+
 impl From<Option<&Vec<LinuxNamespace>>> for Namespaces {
     fn from(namespaces: Option<&Vec<LinuxNamespace>>) -> Self {
         let command: Box<dyn Syscall> = create_syscall();
@@ -59,15 +85,14 @@ impl From<Option<&Vec<LinuxNamespace>>> for Namespaces {
 
 impl Namespaces {
     pub fn apply_namespaces<F: Fn(CloneFlags) -> bool>(&self, filter: F) -> Result<()> {
-        let to_enter: Vec<(&CloneFlags, &LinuxNamespace)> = ORDERED_NAMESPACES
-            .iter()
-            .filter(|c| filter(**c))
-            .filter_map(|c| self.namespace_map.get_key_value(c))
+        let to_enter: Vec<&LinuxNamespace> = order_namespaces(self.namespace_map.values())
+            .into_iter()
+            .filter(|ns| filter(get_clone_flag(ns.typ())))
             .collect();
 
-        for (ns_type, ns) in to_enter {
+        for ns in to_enter {
             self.unshare_or_setns(ns)
-                .with_context(|| format!("failed to enter {:?} namespace: {:?}", ns_type, ns))?;
+                .with_context(|| format!("failed to enter {:?} namespace: {:?}", ns.typ(), ns))?;
         }
         Ok(())
     }
@@ -128,6 +153,64 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn test_order_namespaces() {
+        let namespaces: Vec<LinuxNamespace> = [
+            LinuxNamespaceType::Mount,
+            LinuxNamespaceType::Network,
+            LinuxNamespaceType::Cgroup,
+            LinuxNamespaceType::Ipc,
+            LinuxNamespaceType::User,
+            LinuxNamespaceType::Uts,
+            LinuxNamespaceType::Pid,
+        ]
+        .into_iter()
+        .map(|typ| LinuxNamespaceBuilder::default().typ(typ).build().unwrap())
+        .collect();
+
+        let ordered: Vec<LinuxNamespaceType> = order_namespaces(&namespaces)
+            .into_iter()
+            .map(|ns| ns.typ())
+            .collect();
+        assert_eq!(
+            ordered,
+            vec![
+                LinuxNamespaceType::User,
+                LinuxNamespaceType::Pid,
+                LinuxNamespaceType::Uts,
+                LinuxNamespaceType::Ipc,
+                LinuxNamespaceType::Network,
+                LinuxNamespaceType::Cgroup,
+                LinuxNamespaceType::Mount,
+            ]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_apply_namespaces_in_order() {
+        let namespaces: Vec<LinuxNamespace> = [
+            LinuxNamespaceType::Mount,
+            LinuxNamespaceType::Network,
+            LinuxNamespaceType::User,
+        ]
+        .into_iter()
+        .map(|typ| LinuxNamespaceBuilder::default().typ(typ).build().unwrap())
+        .collect();
+        let namespaces = Namespaces::from(Some(&namespaces));
+        let test_command: &TestHelperSyscall = namespaces.command.as_any().downcast_ref().unwrap();
+        assert!(namespaces.apply_namespaces(|_| true).is_ok());
+
+        assert_eq!(
+            test_command.get_unshare_args(),
+            vec![
+                CloneFlags::CLONE_NEWUSER,
+                CloneFlags::CLONE_NEWNET,
+                CloneFlags::CLONE_NEWNS
+            ]
+        );
+    }
+
     #[test]
     #[serial]
     fn test_apply_namespaces() {
@@ -0,0 +1,360 @@
+//! Relay of readiness notifications
+//!
+//! When a container is the main process of a systemd service of Type=notify,
+//! systemd passes the path of its notify socket in `NOTIFY_SOCKET`. That
+//! socket is not reachable from inside the container, so a datagram socket is
+//! bound in the container directory and mounted into the container instead.
+//! The notifications of the container are forwarded to the socket of the host
+//! until the container reports `READY=1`, together with the pid of the
+//! container process, which becomes the main pid of the service. The relay is
+//! opt-in, as a container which does not speak sd_notify would never become
+//! ready.
+use crate::{pidfd::PidFd, process::fork};
+use anyhow::{bail, Context, Result};
+use nix::{
+    errno::Errno,
+    fcntl::OFlag,
+    sys::{
+        signal,
+        socket::{self, MsgFlags, UnixAddr},
+        stat::Mode,
+    },
+    unistd::{self, Pid},
+};
+use oci_spec::runtime::{MountBuilder, Spec};
+use std::{
+    fs::{self, Permissions},
+    io,
+    os::unix::{fs::PermissionsExt, io::AsRawFd, net::UnixDatagram},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Environment variable with the path of the notify socket
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+/// Directory of the relay socket within the container directory
+pub const RELAY_DIR: &str = "notify";
+/// Path at which the directory of the relay socket is mounted in the container
+pub const CONTAINER_RELAY_DIR: &str = "/run/notify";
+const RELAY_FILE: &str = "notify.sock";
+
+/// Time the container has to become ready before the relay gives up
+pub const READY_TIMEOUT: Duration = Duration::from_secs(300);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// notifications are short, this is the limit of sd_notify as well
+const MAX_MESSAGE_SIZE: usize = 4096;
+
+/// Mounts the directory of the relay socket into the container and points
+/// `NOTIFY_SOCKET` of the container process to the relay socket
+pub fn setup_spec(spec: &mut Spec, relay_dir: &Path) -> Result<()> {
+    let mut mounts = spec.mounts().clone().unwrap_or_default();
+    mounts.push(
+        MountBuilder::default()
+            .destination(CONTAINER_RELAY_DIR)
+            .typ("bind")
+            .source(relay_dir)
+            .options(vec![
+                "bind".to_owned(),
+                "nosuid".to_owned(),
+                "noexec".to_owned(),
+                "nodev".to_owned(),
+            ])
+            .build()?,
+    );
+    spec.set_mounts(Some(mounts));
+
+    if let Some(mut process) = spec.process().clone() {
+        let prefix = format!("{}=", NOTIFY_SOCKET_ENV);
+        let mut env: Vec<String> = process
+            .env()
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|var| !var.starts_with(&prefix))
+            .collect();
+        env.push(format!(
+            "{}{}",
+            prefix,
+            Path::new(CONTAINER_RELAY_DIR).join(RELAY_FILE).display()
+        ));
+        process.set_env(Some(env));
+        spec.set_process(Some(process));
+    }
+
+    Ok(())
+}
+
+pub struct NotifyRelay {
+    socket: UnixDatagram,
+}
+
+impl NotifyRelay {
+    /// Binds the relay socket in the directory, replacing the socket of an
+    /// earlier relay. The path of a unix socket is limited to 108 bytes, so
+    /// the directory should be relative to the container directory.
+    pub fn bind(relay_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(relay_dir)
+            .with_context(|| format!("failed to create {:?}", relay_dir))?;
+        let path = relay_dir.join(RELAY_FILE);
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(err).with_context(|| format!("failed to remove {:?}", path))
+            }
+            _ => {}
+        }
+
+        let socket =
+            UnixDatagram::bind(&path).with_context(|| format!("failed to bind {:?}", path))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        // the container process may run as any user
+        fs::set_permissions(&path, Permissions::from_mode(0o777))
+            .with_context(|| format!("failed to set permissions of {:?}", path))?;
+
+        Ok(Self { socket })
+    }
+
+    /// Relays the notifications in a process of its own, so that starting the
+    /// container does not wait for the container to become ready. The relay
+    /// process exits once the container is ready, has exited or did not
+    /// become ready within the timeout. Returns the pid of the relay process.
+    pub fn spawn(self, host_socket: &Path, pid: Pid, timeout: Duration) -> Result<Pid> {
+        fork::container_fork(|| {
+            // whoever started the container may wait for the output of the
+            // runtime to be closed, which must not wait for the relay
+            detach_stdio()?;
+            match self.relay_until_ready(host_socket, pid, timeout) {
+                Ok(()) => Ok(0),
+                Err(err) => {
+                    log::warn!("failed to relay readiness of process {}: {:?}", pid, err);
+                    Ok(1)
+                }
+            }
+        })
+        .context("failed to spawn notify relay")
+    }
+
+    /// Forwards the notifications of the container to the notify socket of the
+    /// host until the container is ready. Malformed notifications are dropped.
+    /// Fails if the container process exits before it is ready or if it is
+    /// not ready within the timeout.
+    pub fn relay_until_ready(&self, host_socket: &Path, pid: Pid, timeout: Duration) -> Result<()> {
+        let host = host_address(host_socket)?;
+        let process = Process::open(pid)?;
+        let deadline = Instant::now() + timeout;
+        let mut buf = vec![0; MAX_MESSAGE_SIZE];
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    if !process.is_alive()? {
+                        bail!("container process {} exited before it was ready", pid);
+                    }
+                    if Instant::now() >= deadline {
+                        bail!(
+                            "container process {} was not ready within {:?}",
+                            pid,
+                            timeout
+                        );
+                    }
+                    continue;
+                }
+                Err(err) => return Err(err).context("failed to receive notification"),
+            };
+
+            let message = match parse_message(&buf[..len]) {
+                Some(message) => message,
+                None => {
+                    log::warn!("ignore malformed notification: {:?}", &buf[..len]);
+                    continue;
+                }
+            };
+
+            let ready = message.lines().any(|line| line == "READY=1");
+            let message = if ready {
+                format!("{}\nMAINPID={}\n", message.trim_end(), pid)
+            } else {
+                message.to_owned()
+            };
+            socket::sendto(
+                self.socket.as_raw_fd(),
+                message.as_bytes(),
+                &host,
+                MsgFlags::empty(),
+            )
+            .with_context(|| format!("failed to forward notification to {:?}", host_socket))?;
+
+            if ready {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// The container process, watched through a pidfd so that a reused pid is not
+// mistaken for the container, or through its pid on kernels without pidfds.
+enum Process {
+    PidFd(PidFd),
+    Pid(Pid),
+}
+
+impl Process {
+    fn open(pid: Pid) -> Result<Self> {
+        match PidFd::open(pid) {
+            Ok(pidfd) => Ok(Process::PidFd(pidfd)),
+            Err(Errno::ENOSYS) => Ok(Process::Pid(pid)),
+            Err(Errno::ESRCH) => bail!("container process {} exited before it was ready", pid),
+            Err(err) => Err(err).with_context(|| format!("failed to open pidfd of {}", pid)),
+        }
+    }
+
+    fn is_alive(&self) -> Result<bool> {
+        match self {
+            Process::PidFd(pidfd) => pidfd.is_alive(),
+            Process::Pid(pid) => Ok(signal::kill(*pid, None) != Err(Errno::ESRCH)),
+        }
+    }
+}
+
+fn detach_stdio() -> Result<()> {
+    let dev_null = nix::fcntl::open("/dev/null", OFlag::O_RDWR, Mode::empty())
+        .context("failed to open /dev/null")?;
+    for fd in 0..3 {
+        unistd::dup2(dev_null, fd).context("failed to redirect stdio to /dev/null")?;
+    }
+    unistd::close(dev_null)?;
+    Ok(())
+}
+
+// The socket of systemd may be in the abstract namespace, which is denoted by
+// a leading @.
+fn host_address(host_socket: &Path) -> Result<UnixAddr> {
+    let path: PathBuf = host_socket.into();
+    let addr = match path.to_str().and_then(|p| p.strip_prefix('@')) {
+        Some(name) => UnixAddr::new_abstract(name.as_bytes()),
+        None => UnixAddr::new(&path),
+    };
+    addr.with_context(|| format!("invalid notify socket {:?}", host_socket))
+}
+
+// A notification consists of newline separated assignments of variables.
+fn parse_message(buf: &[u8]) -> Option<&str> {
+    let message = std::str::from_utf8(buf).ok()?;
+    let mut lines = message.lines().filter(|line| !line.is_empty()).peekable();
+    lines.peek()?;
+    if lines.all(|line| matches!(line.split_once('='), Some((key, _)) if !key.is_empty())) {
+        Some(message)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use oci_spec::runtime::ProcessBuilder;
+    use std::process::Command;
+
+    #[test]
+    fn test_relay_until_ready() -> Result<()> {
+        let tmp = create_temp_dir("test_relay_until_ready")?;
+        let host_path = tmp.join("host.sock");
+        let host = UnixDatagram::bind(&host_path)?;
+        let relay_dir = tmp.join(RELAY_DIR);
+        let relay = NotifyRelay::bind(&relay_dir)?;
+
+        let container = UnixDatagram::unbound()?;
+        let relay_path = relay_dir.join(RELAY_FILE);
+        for message in [
+            &b"\xff\xfe"[..],
+            b"not a notification",
+            b"STATUS=starting",
+            b"READY=1",
+        ] {
+            container.send_to(message, &relay_path)?;
+        }
+
+        let pid = unistd::getpid();
+        relay.relay_until_ready(&host_path, pid, READY_TIMEOUT)?;
+
+        let mut buf = [0; MAX_MESSAGE_SIZE];
+        let len = host.recv(&mut buf)?;
+        assert_eq!(&buf[..len], b"STATUS=starting");
+        let len = host.recv(&mut buf)?;
+        assert_eq!(
+            std::str::from_utf8(&buf[..len])?,
+            format!("READY=1\nMAINPID={}\n", pid)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_relay_container_exited() -> Result<()> {
+        let tmp = create_temp_dir("test_relay_container_exited")?;
+        let host_path = tmp.join("host.sock");
+        let _host = UnixDatagram::bind(&host_path)?;
+        let relay = NotifyRelay::bind(&tmp.join(RELAY_DIR))?;
+
+        let mut child = Command::new("true").spawn()?;
+        child.wait()?;
+        let pid = Pid::from_raw(child.id() as i32);
+        assert!(relay
+            .relay_until_ready(&host_path, pid, READY_TIMEOUT)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_relay_not_ready_within_timeout() -> Result<()> {
+        let tmp = create_temp_dir("test_relay_not_ready_within_timeout")?;
+        let host_path = tmp.join("host.sock");
+        let _host = UnixDatagram::bind(&host_path)?;
+        let relay = NotifyRelay::bind(&tmp.join(RELAY_DIR))?;
+
+        let started = Instant::now();
+        let err = relay
+            .relay_until_ready(&host_path, unistd::getpid(), Duration::from_millis(300))
+            .unwrap_err();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(err.to_string().contains("was not ready within"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_spec() -> Result<()> {
+        let mut spec = Spec::default();
+        let process = ProcessBuilder::default()
+            .env(vec![
+                "PATH=/bin".to_owned(),
+                "NOTIFY_SOCKET=/run/systemd/notify".to_owned(),
+            ])
+            .build()?;
+        spec.set_process(Some(process));
+
+        setup_spec(&mut spec, Path::new("/run/youki/c1/notify"))?;
+
+        let env = spec.process().as_ref().unwrap().env().as_ref().unwrap();
+        assert_eq!(
+            env,
+            &vec![
+                "PATH=/bin".to_owned(),
+                "NOTIFY_SOCKET=/run/notify/notify.sock".to_owned()
+            ]
+        );
+        let mount = spec.mounts().as_ref().unwrap().last().unwrap();
+        assert_eq!(mount.destination(), Path::new(CONTAINER_RELAY_DIR));
+        assert_eq!(
+            mount.source().as_deref(),
+            Some(Path::new("/run/youki/c1/notify"))
+        );
+        Ok(())
+    }
+}
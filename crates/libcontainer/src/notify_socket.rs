@@ -2,7 +2,7 @@ use anyhow::{bail, Context, Result};
 use nix::unistd::{self, close};
 use std::env;
 use std::io::prelude::*;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 
@@ -54,6 +54,20 @@ impl NotifyListener {
     }
 }
 
+impl AsRawFd for NotifyListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl FromRawFd for NotifyListener {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self {
+            socket: UnixListener::from_raw_fd(fd),
+        }
+    }
+}
+
 pub struct NotifySocket {
     path: PathBuf,
 }
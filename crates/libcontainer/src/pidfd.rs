@@ -0,0 +1,240 @@
+//! Process file descriptors (pidfd)
+//!
+//! A pidfd refers to a process rather than to its pid. Once the process has
+//! exited, signals sent through the pidfd fail instead of reaching another
+//! process which was started with the same pid in the meantime. pidfds
+//! require Linux 5.3, on older kernels signals are sent to the pid instead.
+//!
+//! A pidfd can only be opened from a pid, which may have been reused already
+//! by the time it is opened. Processes which outlive the youki invocation,
+//! like the container init, are therefore identified by their pid together
+//! with their start time, which is recorded while the process is known to be
+//! alive.
+use anyhow::{Context, Result};
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+    sys::signal::{self, Signal},
+    unistd::{self, Pid},
+};
+use std::os::unix::prelude::{AsRawFd, RawFd};
+
+pub struct PidFd {
+    fd: RawFd,
+}
+
+impl PidFd {
+    /// Opens a pidfd for the process. Fails with ENOSYS if the kernel does
+    /// not support pidfds and with ESRCH if the process does not exist.
+    pub fn open(pid: Pid) -> nix::Result<Self> {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid.as_raw(), 0) };
+        Errno::result(fd).map(|fd| Self { fd: fd as RawFd })
+    }
+
+    /// Opens a pidfd for the process, if it is the process which was started
+    /// at the given start time. Fails with ESRCH if the pid belongs to a
+    /// process which was started at another time, i.e. if it was reused.
+    pub fn open_started_at(pid: Pid, start_time: u64) -> nix::Result<Self> {
+        let pidfd = Self::open(pid)?;
+        // the process which was started at the start time was alive before
+        // the pidfd was opened, so if it is still the process with the pid
+        // now, the pidfd refers to it
+        if !is_started_at(pid, start_time) {
+            return Err(Errno::ESRCH);
+        }
+        Ok(pidfd)
+    }
+
+    /// Sends the signal to the process. Fails with ESRCH if the process has
+    /// exited, even if its pid has been reused.
+    pub fn send_signal(&self, signal: Signal) -> nix::Result<()> {
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.fd,
+                signal as libc::c_int,
+                std::ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+        Errno::result(res).map(drop)
+    }
+
+    /// Returns true as long as the process has not exited.
+    pub fn is_alive(&self) -> Result<bool> {
+        // a pidfd becomes readable once the process has exited
+        let mut fds = [PollFd::new(self.fd, PollFlags::POLLIN)];
+        let ready = poll(&mut fds, 0).context("failed to poll pidfd")?;
+        Ok(ready == 0)
+    }
+
+    /// Blocks until the process has exited.
+    pub fn wait_for_exit(&self) -> Result<()> {
+        let mut fds = [PollFd::new(self.fd, PollFlags::POLLIN)];
+        loop {
+            match poll(&mut fds, -1) {
+                Err(Errno::EINTR) => continue,
+                res => {
+                    res.context("failed to poll pidfd")?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for PidFd {
+    fn drop(&mut self) {
+        let _ = unistd::close(self.fd);
+    }
+}
+
+/// Returns the start time of the process in clock ticks after boot, as in
+/// /proc/<pid>/stat.
+pub fn start_time(pid: Pid) -> Result<u64> {
+    let stat = procfs::process::Process::new(pid.as_raw())
+        .and_then(|process| process.stat())
+        .with_context(|| format!("failed to read stat of {}", pid))?;
+    Ok(stat.starttime)
+}
+
+/// Returns true if the process with the pid exists and was started at the
+/// given start time.
+pub fn is_started_at(pid: Pid, start_time: u64) -> bool {
+    matches!(self::start_time(pid), Ok(t) if t == start_time)
+}
+
+/// Sends the signal to the process through a pidfd, or to its pid if the
+/// kernel does not support pidfds. If the start time of the process is
+/// given, the signal is only sent if the pid still belongs to the process
+/// which was started at that time, and fails with ESRCH otherwise. Without a
+/// start time, the pid must not be reusable, e.g. because the process is
+/// frozen.
+pub fn kill(pid: Pid, start_time: Option<u64>, signal: Signal) -> nix::Result<()> {
+    let pidfd = match start_time {
+        Some(start_time) => PidFd::open_started_at(pid, start_time),
+        None => PidFd::open(pid),
+    };
+    match pidfd {
+        Ok(pidfd) => pidfd.send_signal(signal),
+        Err(Errno::ENOSYS) => match start_time {
+            Some(start_time) if !is_started_at(pid, start_time) => Err(Errno::ESRCH),
+            _ => signal::kill(pid, signal),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::fork;
+    use anyhow::bail;
+    use nix::sys::wait::{waitpid, WaitStatus};
+    use serial_test::serial;
+
+    fn supported() -> bool {
+        !matches!(PidFd::open(unistd::getpid()), Err(Errno::ENOSYS))
+    }
+
+    #[test]
+    #[serial]
+    fn test_pidfd_signal_after_pid_churn() -> Result<()> {
+        if !supported() {
+            return Ok(());
+        }
+
+        let pid = fork::container_fork(|| loop {
+            unistd::pause();
+        })?;
+        let pidfd = PidFd::open(pid)?;
+        assert!(pidfd.is_alive()?);
+
+        // unrelated processes come and go while the container is running
+        for _ in 0..100 {
+            let churn = fork::container_fork(|| Ok(0))?;
+            waitpid(churn, None)?;
+        }
+
+        pidfd.send_signal(Signal::SIGKILL)?;
+        match waitpid(pid, None)? {
+            WaitStatus::Signaled(p, Signal::SIGKILL, _) if p == pid => {}
+            status => bail!("unexpected wait status {:?}", status),
+        }
+        assert!(!pidfd.is_alive()?);
+
+        // the pid may be reused now, but the pidfd still refers to the
+        // process which has exited
+        assert_eq!(pidfd.send_signal(Signal::SIGKILL), Err(Errno::ESRCH));
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_pidfd_wait_for_exit() -> Result<()> {
+        if !supported() {
+            return Ok(());
+        }
+
+        let pid = fork::container_fork(|| Ok(0))?;
+        let pidfd = PidFd::open(pid)?;
+        pidfd.wait_for_exit()?;
+        assert!(!pidfd.is_alive()?);
+        waitpid(pid, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_kill() -> Result<()> {
+        let pid = fork::container_fork(|| loop {
+            unistd::pause();
+        })?;
+        kill(pid, Some(start_time(pid)?), Signal::SIGTERM)?;
+        match waitpid(pid, None)? {
+            WaitStatus::Signaled(_, Signal::SIGTERM, _) => {}
+            status => bail!("unexpected wait status {:?}", status),
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_kill_rejects_reused_pid() -> Result<()> {
+        let pid = fork::container_fork(|| loop {
+            unistd::pause();
+        })?;
+        // the process with the pid was started at another time than the
+        // recorded one, as it is when the recorded process has exited and its
+        // pid was reused
+        let start_time = start_time(pid)?;
+        let reused = start_time + 1;
+        assert!(is_started_at(pid, start_time));
+        assert!(!is_started_at(pid, reused));
+        if supported() {
+            assert!(matches!(
+                PidFd::open_started_at(pid, reused),
+                Err(Errno::ESRCH)
+            ));
+        }
+        assert_eq!(kill(pid, Some(reused), Signal::SIGKILL), Err(Errno::ESRCH));
+
+        // the process was not signalled
+        assert_eq!(
+            waitpid(pid, Some(nix::sys::wait::WaitPidFlag::WNOHANG))?,
+            WaitStatus::StillAlive
+        );
+        kill(pid, Some(start_time), Signal::SIGKILL)?;
+        match waitpid(pid, None)? {
+            WaitStatus::Signaled(_, Signal::SIGKILL, _) => {}
+            status => bail!("unexpected wait status {:?}", status),
+        }
+        Ok(())
+    }
+}
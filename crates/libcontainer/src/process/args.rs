@@ -1,12 +1,17 @@
 use libcgroups::common::CgroupManager;
 use oci_spec::runtime::Spec;
+use serde::{Deserialize, Serialize};
 use std::os::unix::prelude::RawFd;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::io_priority::IoPriority;
+use crate::process::reexec::Reexec;
 use crate::rootless::Rootless;
+use crate::scheduler::Scheduler;
 use crate::{container::Container, notify_socket::NotifyListener, syscall::Syscall};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ContainerType {
     InitContainer,
     TenantContainer { exec_notify_fd: RawFd },
@@ -23,6 +28,8 @@ pub struct ContainerArgs<'a> {
     pub rootfs: &'a PathBuf,
     /// Socket to communicate the file descriptor of the ptty
     pub console_socket: Option<RawFd>,
+    /// Socket to communicate the stdio file descriptors without a ptty
+    pub stdio_socket: Option<RawFd>,
     /// The Unix Domain Socket to communicate container start
     pub notify_socket: NotifyListener,
     /// File descriptos preserved/passed to the container init process.
@@ -35,4 +42,21 @@ pub struct ContainerArgs<'a> {
     pub cgroup_manager: Box<dyn CgroupManager>,
     /// If the container is to be run in detached mode
     pub detached: bool,
+    /// Run a minimal init as PID 1 of the container which reaps zombies
+    /// and forwards signals to the container process
+    pub init: bool,
+    /// Scheduling policy of the container process
+    pub scheduler: Option<Scheduler>,
+    /// IO priority of the container process
+    pub io_priority: Option<IoPriority>,
+    /// Log the capability sets of the container process before it is executed
+    pub audit_capabilities: bool,
+    /// Create a new session keyring for the container process
+    pub new_keyring: bool,
+    /// Join the existing cgroup without setting its controllers
+    pub join_cgroup: bool,
+    /// Time after which the creation of the container is aborted
+    pub timeout: Option<Duration>,
+    /// Re-execute the runtime for the intermediate process instead of forking
+    pub reexec: Option<Reexec>,
 }
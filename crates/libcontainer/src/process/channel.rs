@@ -1,14 +1,19 @@
-use crate::process::message::Message;
-use anyhow::{bail, Context, Result};
+use crate::{error::ErrorKind, process::message::Message};
+use anyhow::{anyhow, bail, Context, Result};
 use nix::{
-    sys::socket::{self, UnixAddr},
+    errno::Errno,
+    sys::{
+        socket::{self, sockopt, UnixAddr},
+        time::{TimeVal, TimeValLike},
+    },
     unistd::{self, Pid},
 };
 use serde::{Deserialize, Serialize};
 use std::{
     io::{IoSlice, IoSliceMut},
     marker::PhantomData,
-    os::unix::prelude::{AsRawFd, RawFd},
+    os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
+    time::{Duration, Instant},
 };
 
 /// Channel Design
@@ -67,16 +72,57 @@ impl MainSender {
         Ok(())
     }
 
+    /// Reports the failure of a container process, together with its kind
+    pub fn process_failed(&mut self, err: &anyhow::Error) -> Result<()> {
+        let kind = err.downcast_ref::<ErrorKind>().copied();
+        let chain = err.chain().map(|e| e.to_string()).collect();
+        self.sender.send(Message::ProcessFailed(kind, chain))?;
+        Ok(())
+    }
+
     pub fn close(&self) -> Result<()> {
         self.sender.close()
     }
 }
 
+// Recreates the error of a failed container process from its chain of
+// messages, outermost first, with its kind at the same place in the chain
+fn process_failed(kind: Option<ErrorKind>, chain: Vec<String>) -> anyhow::Error {
+    let is_kind = |msg: &str| kind.map_or(false, |kind| msg == kind.to_string());
+    let mut chain = chain.into_iter().rev();
+    let mut err = match (chain.next(), kind) {
+        (Some(root), Some(kind)) if is_kind(&root) => anyhow!(kind),
+        (Some(root), _) => anyhow!(root),
+        (None, _) => anyhow!("container process failed"),
+    };
+    for msg in chain {
+        err = match kind {
+            Some(kind) if is_kind(&msg) => err.context(kind),
+            _ => err.context(msg),
+        };
+    }
+    err
+}
+
 pub struct MainReceiver {
     receiver: Receiver<Message>,
 }
 
 impl MainReceiver {
+    /// Sets the point in time after which waiting for a message fails. No
+    /// deadline is applied if it is set to None
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) -> Result<()> {
+        let timeout = match deadline {
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(timeout) if !timeout.is_zero() => Some(timeout),
+                _ => bail!("timed out waiting for the container"),
+            },
+            None => None,
+        };
+
+        self.receiver.set_timeout(timeout)
+    }
+
     /// Waits for associated intermediate process to send ready message
     /// and return the pid of init process which is forked by intermediate process
     pub fn wait_for_intermediate_ready(&mut self) -> Result<Pid> {
@@ -86,6 +132,7 @@ impl MainReceiver {
             .context("failed to receive a message from the intermediate process")?;
 
         match msg {
+            Message::ProcessFailed(kind, err) => Err(process_failed(kind, err)),
             Message::IntermediateReady(pid) => Ok(Pid::from_raw(pid)),
             Message::ExecFailed(err) => bail!("exec process failed with error {}", err),
             _ => bail!(
@@ -101,6 +148,7 @@ impl MainReceiver {
             .recv()
             .context("failed to wait for mapping request")?;
         match msg {
+            Message::ProcessFailed(kind, err) => Err(process_failed(kind, err)),
             Message::WriteMapping => Ok(()),
             msg => bail!(
                 "receive unexpected message {:?} waiting for mapping request",
@@ -116,6 +164,7 @@ impl MainReceiver {
             .context("failed to wait for seccomp request")?;
 
         match msg {
+            Message::ProcessFailed(kind, err) => Err(process_failed(kind, err)),
             Message::SeccompNotify => {
                 let fd = match fds {
                     Some(fds) => fds[0],
@@ -138,6 +187,7 @@ impl MainReceiver {
             .recv()
             .context("failed to wait for init ready")?;
         match msg {
+            Message::ProcessFailed(kind, err) => Err(process_failed(kind, err)),
             Message::InitReady => Ok(()),
             msg => bail!(
                 "receive unexpected message {:?} waiting for init ready",
@@ -187,11 +237,11 @@ impl IntermediateReceiver {
         let msg = self
             .receiver
             .recv()
-            .context("failed to wait for init ready")?;
+            .context("failed to wait for mapping ack")?;
         match msg {
             Message::MappingWritten => Ok(()),
             msg => bail!(
-                "receive unexpected message {:?} waiting for init ready",
+                "receive unexpected message {:?} waiting for mapping ack",
                 msg
             ),
         }
@@ -310,6 +360,19 @@ impl<T> Receiver<T>
 where
     T: serde::de::DeserializeOwned,
 {
+    /// Sets the time after which waiting for a message fails. Waiting never
+    /// fails because of a timeout if it is set to None
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        // a zero timeval disables the timeout, so round up to one microsecond
+        let timeval = match timeout {
+            Some(timeout) => TimeVal::microseconds(timeout.as_micros().max(1) as i64),
+            None => TimeVal::zero(),
+        };
+        socket::setsockopt(self.receiver, sockopt::ReceiveTimeout, &timeval)
+            .context("failed to set channel timeout")?;
+        Ok(())
+    }
+
     fn peek_size_iovec(&mut self) -> Result<u64> {
         let mut len: u64 = 0;
         let mut iov = [IoSliceMut::new(unsafe {
@@ -318,8 +381,11 @@ where
                 std::mem::size_of::<u64>(),
             )
         })];
-        let _ =
-            socket::recvmsg::<UnixAddr>(self.receiver, &mut iov, None, socket::MsgFlags::MSG_PEEK)?;
+        match socket::recvmsg::<UnixAddr>(self.receiver, &mut iov, None, socket::MsgFlags::MSG_PEEK)
+        {
+            Err(Errno::EAGAIN) => bail!("timed out waiting for the container"),
+            result => result?,
+        };
         match len {
             0 => bail!("channel connection broken"),
             _ => Ok(len),
@@ -435,9 +501,45 @@ fn unix_channel() -> Result<(RawFd, RawFd)> {
     )?)
 }
 
+// The ends of the channels are passed by their file descriptors to a
+// re-executed intermediate process, which restores them from the inherited
+// file descriptors.
+macro_rules! impl_raw_fd {
+    ($($channel:ident { $end:ident: $kind:ident }),* $(,)?) => {
+        $(
+            impl AsRawFd for $channel {
+                fn as_raw_fd(&self) -> RawFd {
+                    self.$end.$end
+                }
+            }
+
+            impl FromRawFd for $channel {
+                unsafe fn from_raw_fd(fd: RawFd) -> Self {
+                    Self {
+                        $end: $kind {
+                            $end: fd,
+                            phantom: PhantomData,
+                        },
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_raw_fd!(
+    MainSender { sender: Sender },
+    MainReceiver { receiver: Receiver },
+    IntermediateSender { sender: Sender },
+    IntermediateReceiver { receiver: Receiver },
+    InitSender { sender: Sender },
+    InitReceiver { receiver: Receiver },
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::LibcontainerError;
     use anyhow::Context;
     use nix::sys::wait;
     use nix::unistd;
@@ -538,6 +640,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn test_channel_deadline() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+
+        let start = Instant::now();
+        receiver.set_deadline(Some(start + Duration::from_millis(100)))?;
+        let err = receiver.wait_for_init_ready().unwrap_err();
+        assert!(format!("{:?}", err).contains("timed out"));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        // an expired deadline fails without waiting
+        assert!(receiver.set_deadline(Some(start)).is_err());
+
+        // messages are received again once the deadline is removed
+        receiver.set_deadline(None)?;
+        sender.init_ready()?;
+        receiver.wait_for_init_ready()?;
+
+        sender.close()?;
+        receiver.close()?;
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_channel_main_graceful_exit() -> Result<()> {
@@ -583,4 +709,36 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_channel_process_failed() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                wait::waitpid(child, None)?;
+                let err = receiver.wait_for_init_ready().unwrap_err();
+                receiver.close()?;
+                let err = LibcontainerError::from(err);
+                assert!(matches!(err, LibcontainerError::Mount(_)));
+                let err = anyhow::Error::from(err);
+                assert_eq!(err.to_string(), "failed to prepare rootfs");
+                assert_eq!(
+                    format!("{:#}", err),
+                    "failed to prepare rootfs: mount error: no such file or directory"
+                );
+            }
+            unistd::ForkResult::Child => {
+                let err = Err::<(), _>(anyhow!("no such file or directory"))
+                    .context(ErrorKind::Mount)
+                    .context("failed to prepare rootfs")
+                    .unwrap_err();
+                sender.process_failed(&err)?;
+                sender.close()?;
+                std::process::exit(0);
+            }
+        };
+
+        Ok(())
+    }
 }
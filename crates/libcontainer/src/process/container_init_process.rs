@@ -1,21 +1,24 @@
 use super::args::{ContainerArgs, ContainerType};
+use super::{init_shim, init_stderr};
 use crate::apparmor;
 use crate::syscall::Syscall;
 use crate::workload::ExecutorManager;
 use crate::{
-    capabilities, hooks, namespaces::Namespaces, process::channel, rootfs::RootFS,
-    rootless::Rootless, seccomp, tty, utils,
+    capabilities, error::ErrorKind, hooks, io_priority, keyring, namespaces::Namespaces,
+    process::channel, rootfs::RootFS, rootless::Rootless, scheduler, seccomp, tty, user, utils,
 };
 use anyhow::{bail, Context, Ok, Result};
 use nix::mount::MsFlags;
 use nix::sched::CloneFlags;
+use nix::sys::signal::{self, SigSet, SigmaskHow};
 use nix::sys::stat::Mode;
+use nix::sys::statvfs::{self, FsFlags};
 use nix::unistd::setsid;
 
 use nix::unistd::{self, Gid, Uid};
 use oci_spec::runtime::{LinuxNamespaceType, Spec, User};
 use std::collections::HashMap;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::{
     env, fs,
     path::{Path, PathBuf},
@@ -75,6 +78,45 @@ fn readonly_path(path: &Path, syscall: &dyn Syscall) -> Result<()> {
     Ok(())
 }
 
+// Remounts the root of the container read only. Only the root mount itself is
+// affected, mounts on top of it (e.g. tmpfs or volumes) keep their own flags.
+// If the root mount carries locked flags, as it does when the mount namespace is
+// owned by another user namespace, the remount has to keep them, otherwise the
+// kernel rejects it with EPERM.
+fn readonly_rootfs(syscall: &dyn Syscall) -> Result<()> {
+    let root = Path::new("/");
+    let flags = MsFlags::MS_RDONLY | MsFlags::MS_REMOUNT | MsFlags::MS_BIND;
+    if let Err(err) = syscall.mount(None, root, None, flags, None) {
+        if !matches!(err.downcast_ref(), Some(nix::errno::Errno::EPERM)) {
+            return Err(err);
+        }
+
+        let locked_flags = locked_mount_flags(statvfs::statvfs(root)?.flags());
+        syscall.mount(None, root, None, flags | locked_flags, None)?;
+    }
+
+    log::debug!("rootfs remounted read only");
+    Ok(())
+}
+
+// The flags reported by statvfs do not share their values with the mount
+// flags, so the ones which may be locked are mapped one by one.
+fn locked_mount_flags(flags: FsFlags) -> MsFlags {
+    let mapping = [
+        (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+        (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+        (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+        (FsFlags::ST_NOATIME, MsFlags::MS_NOATIME),
+        (FsFlags::ST_NODIRATIME, MsFlags::MS_NODIRATIME),
+        #[cfg(not(target_env = "musl"))]
+        (FsFlags::ST_RELATIME, MsFlags::MS_RELATIME),
+    ];
+    mapping
+        .iter()
+        .filter(|(st_flag, _)| flags.contains(*st_flag))
+        .fold(MsFlags::empty(), |acc, (_, ms_flag)| acc | *ms_flag)
+}
+
 // For files, bind mounts /dev/null over the top of the specified path.
 // For directories, mounts read-only tmpfs over the top of the specified path.
 fn masked_path(path: &Path, mount_label: &Option<String>, syscall: &dyn Syscall) -> Result<()> {
@@ -138,7 +180,7 @@ fn apply_rest_namespaces(
     Ok(())
 }
 
-fn reopen_dev_null() -> Result<()> {
+fn reopen_dev_null(stderr: RawFd) -> Result<()> {
     // At this point we should be inside of the container and now
     // we can re-open /dev/null if it is in use to the /dev/null
     // in the container.
@@ -146,8 +188,10 @@ fn reopen_dev_null() -> Result<()> {
     let dev_null = fs::File::open("/dev/null")?;
     let dev_null_fstat_info = nix::sys::stat::fstat(dev_null.as_raw_fd())?;
 
-    // Check if stdin, stdout or stderr point to /dev/null
-    for fd in 0..3 {
+    // Check if stdin, stdout or stderr point to /dev/null. Stderr is still
+    // captured here, so it is the saved descriptor which is restored to fd 2
+    // later on that has to be checked.
+    for fd in [0, 1, stderr] {
         let fstat_info = nix::sys::stat::fstat(fd)?;
 
         if dev_null_fstat_info.st_rdev == fstat_info.st_rdev {
@@ -163,6 +207,7 @@ pub fn container_init_process(
     args: &ContainerArgs,
     main_sender: &mut channel::MainSender,
     init_receiver: &mut channel::InitReceiver,
+    stderr_fd: RawFd,
 ) -> Result<()> {
     let syscall = args.syscall;
     let spec = args.spec;
@@ -175,12 +220,39 @@ pub fn container_init_process(
     let namespaces = Namespaces::from(linux.namespaces().as_ref());
 
     setsid().context("failed to create session")?;
+    // signals which the runtime blocked, e.g. to forward them to the container
+    // process later on, must not stay blocked for the container process
+    signal::sigprocmask(SigmaskHow::SIG_SETMASK, Some(&SigSet::empty()), None)
+        .context("failed to unblock signals")?;
+    if args.new_keyring {
+        // named after the container like runc does, to be recognizable in /proc/keys
+        let name = container.map(|c| format!("_ses.{}", c.id()));
+        match keyring::join_session_keyring(name.as_deref()) {
+            std::result::Result::Ok(serial) => log::debug!("joined session keyring {}", serial),
+            // kernels may be built without keyring support
+            Err(err) => log::warn!("failed to create session keyring: {}", err),
+        }
+    }
     // set up tty if specified
-    if let Some(csocketfd) = args.console_socket {
-        tty::setup_console(&csocketfd).with_context(|| "failed to set up tty")?;
+    let console = if let Some(csocketfd) = args.console_socket {
+        Some(tty::setup_console(&csocketfd, proc.console_size()).context("failed to set up tty")?)
+    } else {
+        None
+    };
+    if let Some(stdio_socketfd) = args.stdio_socket {
+        if console.is_some() {
+            bail!("stdio socket can not be used together with a terminal");
+        }
+        tty::setup_stdio_socket(&stdio_socketfd).context("failed to set up stdio socket")?;
     }
+    // whatever is written to stderr until the init process is ready is passed
+    // to the main process as well, so that it becomes part of the error if the
+    // creation fails
+    let stderr = init_stderr::redirect(stderr_fd).context("failed to capture stderr")?;
 
-    apply_rest_namespaces(&namespaces, spec, syscall)?;
+    apply_rest_namespaces(&namespaces, spec, syscall)
+        .context(ErrorKind::Namespace)
+        .context("failed to apply namespaces")?;
 
     if let Some(true) = proc.no_new_privileges() {
         let _ = prctl::set_no_new_privileges(true);
@@ -202,7 +274,9 @@ pub fn container_init_process(
                 rootfs_path,
                 bind_service,
                 namespaces.get(LinuxNamespaceType::Cgroup).is_some(),
+                console.as_deref(),
             )
+            .context(ErrorKind::Mount)
             .with_context(|| "Failed to prepare rootfs")?;
 
         // Entering into the rootfs jail. If mount namespace is specified, then
@@ -213,10 +287,12 @@ pub fn container_init_process(
             // change the root of filesystem of the process to the rootfs
             syscall
                 .pivot_rootfs(rootfs_path)
+                .context(ErrorKind::Mount)
                 .with_context(|| format!("failed to pivot root to {:?}", rootfs_path))?;
         } else {
             syscall
                 .chroot(rootfs_path)
+                .context(ErrorKind::Mount)
                 .with_context(|| format!("failed to chroot to {:?}", rootfs_path))?;
         }
 
@@ -224,7 +300,7 @@ pub fn container_init_process(
             .adjust_root_mount_propagation(linux)
             .context("failed to set propagation type of root mount")?;
 
-        reopen_dev_null()?;
+        reopen_dev_null(stderr)?;
 
         if let Some(kernel_params) = linux.sysctl() {
             sysctl(kernel_params)
@@ -238,21 +314,7 @@ pub fn container_init_process(
     }
 
     if let Some(true) = spec.root().as_ref().map(|r| r.readonly().unwrap_or(false)) {
-        syscall.mount(
-            None,
-            Path::new("/"),
-            None,
-            MsFlags::MS_RDONLY | MsFlags::MS_REMOUNT | MsFlags::MS_BIND,
-            None,
-        )?
-    }
-
-    if let Some(umask) = proc.user().umask() {
-        if let Some(mode) = Mode::from_bits(umask) {
-            nix::sys::stat::umask(mode);
-        } else {
-            bail!("invalid umask {}", umask);
-        }
+        readonly_rootfs(syscall).context("failed to remount rootfs as read only")?;
     }
 
     if let Some(paths) = linux.readonly_paths() {
@@ -285,15 +347,8 @@ pub fn container_init_process(
         }
     };
 
-    set_supplementary_gids(proc.user(), args.rootless, syscall)
-        .context("failed to set supplementary gids")?;
-
-    syscall
-        .set_id(
-            Uid::from_raw(proc.user().uid()),
-            Gid::from_raw(proc.user().gid()),
-        )
-        .context("failed to configure uid and gid")?;
+    let user = user::resolve(proc.user()).context("failed to resolve user")?;
+    set_user(&user, args.rootless, syscall).context("failed to set user")?;
 
     // Take care of LISTEN_FDS used for systemd-active-socket. If the value is
     // not 0, then we have to preserve those fds as well, and set up the correct
@@ -346,13 +401,25 @@ pub fn container_init_process(
         .close_range(preserve_fds)
         .with_context(|| "failed to clean up extra fds")?;
 
+    // Realtime and deadline policies need CAP_SYS_NICE, so the scheduler has
+    // to be set before the capabilities are dropped.
+    if let Some(scheduler) = &args.scheduler {
+        scheduler::set_scheduler(scheduler).context("failed to set scheduler")?;
+    }
+
+    // The realtime IO class needs CAP_SYS_ADMIN or CAP_SYS_NICE as well.
+    if let Some(io_priority) = &args.io_priority {
+        io_priority::set_io_priority(io_priority).context("failed to set io priority")?;
+    }
+
     // Without no new privileges, seccomp is a privileged operation. We have to
     // do this before dropping capabilities. Otherwise, we should do it later,
     // as close to exec as possible.
     if let Some(seccomp) = linux.seccomp() {
         if proc.no_new_privileges().is_none() {
-            let notify_fd =
-                seccomp::initialize_seccomp(seccomp).context("failed to execute seccomp")?;
+            let notify_fd = seccomp::initialize_seccomp(seccomp)
+                .context(ErrorKind::Seccomp)
+                .context("failed to execute seccomp")?;
             sync_seccomp(notify_fd, main_sender, init_receiver)
                 .context("failed to sync seccomp")?;
         }
@@ -363,6 +430,16 @@ pub fn container_init_process(
         capabilities::drop_privileges(caps, syscall).context("Failed to drop capabilities")?;
     }
 
+    if args.audit_capabilities {
+        let audit = capabilities::audit().context("failed to audit capabilities")?;
+        log::info!(
+            "capabilities of the container process: effective {:?}, permitted {:?}, bounding {:?}",
+            audit.effective,
+            audit.permitted,
+            audit.bounding
+        );
+    }
+
     // Change directory to process.cwd if process.cwd is not empty
     if do_chdir {
         unistd::chdir(proc.cwd()).with_context(|| format!("failed to chdir {:?}", proc.cwd()))?;
@@ -371,7 +448,7 @@ pub fn container_init_process(
     // add HOME into envs if not exists
     let home_in_envs = envs.iter().any(|x| x.starts_with("HOME="));
     if !home_in_envs {
-        if let Some(dir_home) = utils::get_user_home(proc.user().uid()) {
+        if let Some(dir_home) = utils::get_user_home(user.uid()) {
             envs.push(format!("HOME={}", dir_home.to_string_lossy()));
         }
     }
@@ -387,39 +464,20 @@ pub fn container_init_process(
     // notify socket will still need network related syscalls.
     if let Some(seccomp) = linux.seccomp() {
         if proc.no_new_privileges().is_some() {
-            let notify_fd =
-                seccomp::initialize_seccomp(seccomp).context("failed to execute seccomp")?;
+            let notify_fd = seccomp::initialize_seccomp(seccomp)
+                .context(ErrorKind::Seccomp)
+                .context("failed to execute seccomp")?;
             sync_seccomp(notify_fd, main_sender, init_receiver)
                 .context("failed to sync seccomp")?;
         }
     }
 
-    // this checks if the binary to run actually exists and if we have permissions to run it.
-    // Taken from https://github.com/opencontainers/runc/blob/25c9e888686773e7e06429133578038a9abc091d/libcontainer/standard_init_linux.go#L195-L206
     if let Some(args) = proc.args() {
-        let path_var = {
-            let mut ret: &str = "";
-            for var in &envs {
-                if var.starts_with("PATH=") {
-                    ret = var;
-                }
-            }
-            ret
-        };
-        let executable_path = utils::get_executable_path(&args[0], path_var);
-        match executable_path {
-            None => bail!(
-                "executable '{}' for container process does not exist",
-                args[0]
-            ),
-            Some(path) => {
-                if !utils::is_executable(&path)? {
-                    bail!("file {:?} does not have executable permission set", path);
-                }
-            }
-        }
+        verify_binary(args, &envs)?;
     }
 
+    init_stderr::restore(stderr).context("failed to restore stderr")?;
+
     // Notify main process that the init process is ready to execute the
     // payload.  Note, because we are already inside the pid namespace, the pid
     // outside the pid namespace should be recorded by the intermediate process
@@ -442,12 +500,81 @@ pub fn container_init_process(
     }
 
     if proc.args().is_some() {
+        if args.init {
+            let exit_code = init_shim::run(|| ExecutorManager::exec(spec))
+                .context("failed to run init shim")?;
+            std::process::exit(exit_code);
+        }
+
         ExecutorManager::exec(spec)
     } else {
         bail!("on non-Windows, at least one process arg entry is required")
     }
 }
 
+// Switches to the user of the container process. The order matters: the
+// supplementary groups and the gid can only be changed while the process still
+// runs as the privileged user, so they are set before the uid is dropped. The
+// umask is applied last, so that it only affects the files created by the
+// container process and not those the runtime created on its behalf.
+fn set_user(user: &User, rootless: &Option<Rootless>, syscall: &dyn Syscall) -> Result<()> {
+    set_supplementary_gids(user, rootless, syscall).context("failed to set supplementary gids")?;
+
+    syscall
+        .set_id(Uid::from_raw(user.uid()), Gid::from_raw(user.gid()))
+        .context("failed to configure uid and gid")?;
+
+    if let Some(umask) = user.umask() {
+        set_umask(umask)?;
+    }
+
+    Ok(())
+}
+
+// sets the umask of the container process, only the permission bits are valid
+// values, so anything above 0o777 is rejected instead of being silently truncated
+fn set_umask(umask: u32) -> Result<()> {
+    if umask > 0o777 {
+        bail!("invalid umask {:#o}, must be within 0..=0o777", umask);
+    }
+
+    let mode = Mode::from_bits_truncate(umask);
+    nix::sys::stat::umask(mode);
+    Ok(())
+}
+
+// this checks if the binary to run actually exists and if we have permissions to run it.
+// Taken from https://github.com/opencontainers/runc/blob/25c9e888686773e7e06429133578038a9abc091d/libcontainer/standard_init_linux.go#L195-L206
+fn verify_binary(args: &[String], envs: &[String]) -> Result<PathBuf> {
+    let executable = match args.first() {
+        Some(executable) => executable,
+        None => bail!("at least one process arg must be specified"),
+    };
+
+    // the last PATH entry wins, in the same way as it would when the
+    // environment is applied to the container process
+    let path_var = envs
+        .iter()
+        .rev()
+        .find(|var| var.starts_with("PATH="))
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match utils::get_executable_path(executable, path_var) {
+        None => bail!(
+            "executable '{}' for container process does not exist in PATH '{}'",
+            executable,
+            path_var.trim_start_matches("PATH=")
+        ),
+        Some(path) => {
+            if !utils::is_executable(&path)? {
+                bail!("file {:?} does not have executable permission set", path);
+            }
+            Ok(path)
+        }
+    }
+}
+
 // Before 3.19 it was possible for an unprivileged user to enter an user namespace,
 // become root and then call setgroups in order to drop membership in supplementary
 // groups. This allowed access to files which blocked access based on being a member
@@ -472,43 +599,45 @@ pub fn container_init_process(
 //
 // Privileged user starting a normal container: Just add the supplementary groups.
 //
+// Whenever setgroups is possible, the supplementary groups are set even if no
+// additional gids are specified, as the container process would otherwise keep
+// the supplementary groups of the runtime.
 fn set_supplementary_gids(
     user: &User,
     rootless: &Option<Rootless>,
     syscall: &dyn Syscall,
 ) -> Result<()> {
-    if let Some(additional_gids) = user.additional_gids() {
+    let additional_gids = user.additional_gids().as_deref().unwrap_or_default();
+
+    let setgroups =
+        fs::read_to_string("/proc/self/setgroups").context("failed to read setgroups")?;
+    if setgroups.trim() == "deny" {
         if additional_gids.is_empty() {
             return Ok(());
         }
+        bail!("cannot set supplementary gids, setgroup is disabled");
+    }
 
-        let setgroups =
-            fs::read_to_string("/proc/self/setgroups").context("failed to read setgroups")?;
-        if setgroups.trim() == "deny" {
-            bail!("cannot set supplementary gids, setgroup is disabled");
+    let gids: Vec<Gid> = additional_gids
+        .iter()
+        .map(|gid| Gid::from_raw(*gid))
+        .collect();
+
+    match rootless {
+        Some(r) if r.privileged => {
+            syscall.set_groups(&gids).with_context(|| {
+                format!("failed to set privileged supplementary gids: {:?}", gids)
+            })?;
         }
-
-        let gids: Vec<Gid> = additional_gids
-            .iter()
-            .map(|gid| Gid::from_raw(*gid))
-            .collect();
-
-        match rootless {
-            Some(r) if r.privileged => {
-                syscall.set_groups(&gids).with_context(|| {
-                    format!("failed to set privileged supplementary gids: {:?}", gids)
-                })?;
-            }
-            None => {
-                syscall.set_groups(&gids).with_context(|| {
-                    format!("failed to set unprivileged supplementary gids: {:?}", gids)
-                })?;
-            }
-            // this should have been detected during validation
-            _ => unreachable!(
-                "unprivileged users cannot set supplementary gids in rootless container"
-            ),
+        None => {
+            syscall.set_groups(&gids).with_context(|| {
+                format!("failed to set unprivileged supplementary gids: {:?}", gids)
+            })?;
         }
+        // the groups of an unprivileged user can not be changed
+        _ if gids.is_empty() => {}
+        // this should have been detected during validation
+        _ => unreachable!("unprivileged users cannot set supplementary gids in rootless container"),
     }
 
     Ok(())
@@ -539,10 +668,12 @@ mod tests {
         syscall::create_syscall,
         test::{ArgName, MountArgs, TestHelperSyscall},
     };
+    use crate::utils::test_utils;
     use nix::unistd;
     use oci_spec::runtime::{LinuxNamespaceBuilder, SpecBuilder, UserBuilder};
     use serial_test::serial;
     use std::fs;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 
     #[test]
     fn test_readonly_path() -> Result<()> {
@@ -618,16 +749,18 @@ mod tests {
 
     #[test]
     fn test_set_supplementary_gids() -> Result<()> {
-        // gids additional gids is empty case
-        let user = UserBuilder::default().build().unwrap();
-        assert!(set_supplementary_gids(&user, &None, create_syscall().as_ref()).is_ok());
-
         let tests = vec![
+            // the groups of the runtime are cleared if no additional gids are given
+            (
+                UserBuilder::default().build()?,
+                None::<Rootless>,
+                vec![vec![]],
+            ),
             (
                 UserBuilder::default()
                     .additional_gids(vec![33, 34])
                     .build()?,
-                None::<Rootless>,
+                None,
                 vec![vec![Gid::from_raw(33), Gid::from_raw(34)]],
             ),
             // unreachable case
@@ -673,6 +806,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_user() -> Result<()> {
+        if fs::read_to_string("/proc/self/setgroups")?.trim() == "deny" {
+            return Ok(());
+        }
+
+        let user = UserBuilder::default()
+            .uid(1000u32)
+            .gid(1001u32)
+            .additional_gids(vec![33, 34])
+            .umask(0o027u32)
+            .build()?;
+        test_utils::test_in_child_process(|| {
+            let syscall = create_syscall();
+            set_user(&user, &None, syscall.as_ref())?;
+
+            let mocks = syscall
+                .as_any()
+                .downcast_ref::<TestHelperSyscall>()
+                .unwrap();
+            let groups = mocks.get_groups_args();
+            if groups != vec![vec![Gid::from_raw(33), Gid::from_raw(34)]] {
+                bail!("expected supplementary groups [33, 34], got {:?}", groups);
+            }
+            let ids = mocks.get_set_id_args();
+            if ids != vec![(Uid::from_raw(1000), Gid::from_raw(1001))] {
+                bail!("expected uid 1000 and gid 1001, got {:?}", ids);
+            }
+            let umask = nix::sys::stat::umask(Mode::empty());
+            if umask.bits() != 0o027 {
+                bail!("expected umask 0o027, got {:o}", umask.bits());
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_set_user_groups_before_ids() -> Result<()> {
+        if fs::read_to_string("/proc/self/setgroups")?.trim() == "deny" {
+            return Ok(());
+        }
+
+        let user = UserBuilder::default()
+            .uid(1000u32)
+            .gid(1001u32)
+            .additional_gids(vec![33])
+            .build()?;
+        let syscall = create_syscall();
+        let mocks = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        mocks.set_ret_err(ArgName::Groups, || bail!(nix::errno::Errno::EPERM));
+
+        // the ids must not be dropped when the groups could not be set
+        assert!(set_user(&user, &None, syscall.as_ref()).is_err());
+        assert!(mocks.get_set_id_args().is_empty());
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_sync_seccomp() -> Result<()> {
@@ -704,6 +897,58 @@ mod tests {
         Ok(())
     }
 
+    fn create_executable(dir: &Path, name: &str) -> Result<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh\n")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        Ok(path)
+    }
+
+    #[test]
+    fn test_verify_binary_path_lookup() -> Result<()> {
+        let tmp = utils::create_temp_dir("test_verify_binary_path_lookup")?;
+        let bin = tmp.join("bin");
+        fs::create_dir_all(&bin)?;
+        let want = create_executable(&bin, "payload")?;
+
+        let envs = vec![
+            "PATH=/does/not/exist".to_string(),
+            format!("PATH=/does/not/exist:{}", bin.display()),
+        ];
+        let got = verify_binary(&["payload".to_string()], &envs)?;
+        assert_eq!(got, want);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_binary_absolute_path() -> Result<()> {
+        let tmp = utils::create_temp_dir("test_verify_binary_absolute_path")?;
+        let want = create_executable(&tmp, "payload")?;
+
+        // an absolute path must be used as is, even if PATH does not contain it
+        let envs = vec!["PATH=/does/not/exist".to_string()];
+        let got = verify_binary(&[want.display().to_string()], &envs)?;
+        assert_eq!(got, want);
+
+        // an absolute path must never be resolved through PATH
+        let envs = vec![format!("PATH={}", tmp.display())];
+        assert!(verify_binary(&["/payload".to_string()], &envs).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_binary_not_found() -> Result<()> {
+        let tmp = utils::create_temp_dir("test_verify_binary_not_found")?;
+        let envs = vec![format!("PATH={}", tmp.display())];
+
+        let err = verify_binary(&["non-existent".to_string()], &envs).unwrap_err();
+        assert!(err.to_string().contains("'non-existent'"));
+        assert!(verify_binary(&[], &envs).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_masked_path_does_not_exist() {
         let syscall = create_syscall();
@@ -769,6 +1014,85 @@ mod tests {
         assert_eq!(want, got[0]);
     }
 
+    #[test]
+    fn test_readonly_rootfs() -> Result<()> {
+        let syscall = create_syscall();
+        readonly_rootfs(syscall.as_ref())?;
+
+        let want = MountArgs {
+            source: None,
+            target: PathBuf::from("/"),
+            fstype: None,
+            flags: MsFlags::MS_RDONLY | MsFlags::MS_REMOUNT | MsFlags::MS_BIND,
+            data: None,
+        };
+        let got = syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args();
+        assert_eq!(vec![want], got);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_locked_mount_flags() {
+        assert_eq!(locked_mount_flags(FsFlags::empty()), MsFlags::empty());
+        // read only and synchronous are not locked
+        assert_eq!(
+            locked_mount_flags(FsFlags::ST_RDONLY | FsFlags::ST_SYNCHRONOUS),
+            MsFlags::empty()
+        );
+        assert_eq!(
+            locked_mount_flags(
+                FsFlags::ST_NOSUID
+                    | FsFlags::ST_NODEV
+                    | FsFlags::ST_NOEXEC
+                    | FsFlags::ST_NOATIME
+                    | FsFlags::ST_NODIRATIME
+            ),
+            MsFlags::MS_NOSUID
+                | MsFlags::MS_NODEV
+                | MsFlags::MS_NOEXEC
+                | MsFlags::MS_NOATIME
+                | MsFlags::MS_NODIRATIME
+        );
+        // ST_RELATIME has the value of MS_BIND
+        #[cfg(not(target_env = "musl"))]
+        assert_eq!(
+            locked_mount_flags(FsFlags::ST_RELATIME | FsFlags::ST_NOSUID),
+            MsFlags::MS_RELATIME | MsFlags::MS_NOSUID
+        );
+    }
+
+    #[test]
+    fn test_set_umask() -> Result<()> {
+        let tmp = utils::create_temp_dir("test_set_umask")?;
+        test_utils::test_in_child_process(|| {
+            set_umask(0o027)?;
+            let path = tmp.path().join("umask_test");
+            fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .mode(0o666)
+                .open(&path)?;
+            let mode = fs::metadata(&path)?.permissions().mode() & 0o777;
+            if mode != 0o640 {
+                bail!("expected file mode {:#o}, got {:#o}", 0o640, mode);
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_umask_out_of_range() {
+        assert!(set_umask(0o1000).is_err());
+    }
+
     #[test]
     fn test_masked_path_with_unknown_error() {
         let syscall = create_syscall();
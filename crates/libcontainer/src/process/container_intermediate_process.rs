@@ -1,4 +1,4 @@
-use crate::{namespaces::Namespaces, process::channel, process::fork};
+use crate::{error::ErrorKind, namespaces::Namespaces, process::channel, process::fork};
 use anyhow::{Context, Error, Result};
 use libcgroups::common::CgroupManager;
 use nix::unistd::{close, write};
@@ -6,6 +6,7 @@ use nix::unistd::{Gid, Pid, Uid};
 use oci_spec::runtime::{LinuxNamespaceType, LinuxResources};
 use procfs::process::Process;
 use std::convert::From;
+use std::os::unix::io::RawFd;
 
 use super::args::{ContainerArgs, ContainerType};
 use super::container_init_process::container_init_process;
@@ -15,6 +16,7 @@ pub fn container_intermediate_process(
     intermediate_chan: &mut (channel::IntermediateSender, channel::IntermediateReceiver),
     init_chan: &mut (channel::InitSender, channel::InitReceiver),
     main_sender: &mut channel::MainSender,
+    stderr_fd: RawFd,
 ) -> Result<Pid> {
     let (inter_sender, inter_receiver) = intermediate_chan;
     let (init_sender, init_receiver) = init_chan;
@@ -32,12 +34,14 @@ pub fn container_intermediate_process(
     // mapped to an unprivileged user by the user namespace however.
     // In addition this needs to be done before we enter the cgroup namespace as
     // the cgroup of the process will form the root of the cgroup hierarchy in
-    // the cgroup namespace.
+    // the cgroup namespace. The controllers of a joined cgroup are owned by
+    // whoever created it, so they are left alone.
     apply_cgroups(
         args.cgroup_manager.as_ref(),
         linux.resources().as_ref(),
-        matches!(args.container_type, ContainerType::InitContainer),
+        matches!(args.container_type, ContainerType::InitContainer) && !args.join_cgroup,
     )
+    .context(ErrorKind::Cgroup)
     .context("failed to apply cgroups")?;
 
     // if new user is specified in specification, this will be true and new
@@ -47,6 +51,7 @@ pub fn container_intermediate_process(
     if let Some(user_namespace) = namespaces.get(LinuxNamespaceType::User) {
         namespaces
             .unshare_or_setns(user_namespace)
+            .context(ErrorKind::Namespace)
             .with_context(|| format!("failed to enter user namespace: {:?}", user_namespace))?;
         if user_namespace.path().is_none() {
             log::debug!("creating new user namespace");
@@ -54,7 +59,11 @@ pub fn container_intermediate_process(
             // allowed to write the uid/gid maps
             prctl::set_dumpable(true).unwrap();
             main_sender.identifier_mapping_request()?;
-            inter_receiver.wait_for_mapping_ack()?;
+            // the main process aborts the creation instead if writing any
+            // of the mappings failed
+            inter_receiver
+                .wait_for_mapping_ack()
+                .context("failed to wait for uid and gid mappings")?;
             prctl::set_dumpable(false).unwrap();
         }
 
@@ -81,6 +90,7 @@ pub fn container_intermediate_process(
     if let Some(pid_namespace) = namespaces.get(LinuxNamespaceType::Pid) {
         namespaces
             .unshare_or_setns(pid_namespace)
+            .context(ErrorKind::Namespace)
             .with_context(|| format!("failed to enter pid namespace: {:?}", pid_namespace))?;
     }
 
@@ -96,9 +106,11 @@ pub fn container_intermediate_process(
         inter_sender
             .close()
             .context("failed to close sender in the intermediate process")?;
-        match container_init_process(args, main_sender, init_receiver) {
+        match container_init_process(args, main_sender, init_receiver, stderr_fd) {
             Ok(_) => Ok(0),
             Err(e) => {
+                // the main process may not wait for a message anymore
+                let _ = main_sender.process_failed(&e);
                 if let ContainerType::TenantContainer { exec_notify_fd } = args.container_type {
                     let buf = format!("{}", e);
                     write(exec_notify_fd, buf.as_bytes())?;
@@ -113,6 +125,9 @@ pub fn container_intermediate_process(
     if let ContainerType::TenantContainer { exec_notify_fd } = args.container_type {
         close(exec_notify_fd)?;
     }
+    // only the init process writes to the stderr pipe, the main process waits
+    // for the pipe to be closed by the init process
+    close(stderr_fd).context("failed to close init stderr pipe")?;
 
     main_sender
         .intermediate_ready(pid)
@@ -3,6 +3,8 @@ use crate::{
     process::{
         args::{ContainerArgs, ContainerType},
         channel, container_intermediate_process, fork,
+        init_stderr::InitStderr,
+        reexec,
     },
     rootless::Rootless,
     seccomp, utils,
@@ -10,13 +12,23 @@ use crate::{
 use anyhow::{Context, Result};
 use nix::{
     sys::{
+        signal::{self, Signal},
         socket::{self, UnixAddr},
         wait::{waitpid, WaitStatus},
     },
     unistd::{self, Pid},
 };
 use oci_spec::runtime;
-use std::{io::IoSlice, path::Path};
+use std::{
+    io::IoSlice,
+    os::unix::io::RawFd,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+// How long to wait for a message which the killed intermediate process may
+// have sent already
+const PENDING_MESSAGE_TIMEOUT: Duration = Duration::from_millis(10);
 
 pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pid)> {
     // We use a set of channels to communicate between parent and child process.
@@ -27,42 +39,122 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pi
     let (main_sender, main_receiver) = &mut channel::main_channel()?;
     let inter_chan = &mut channel::intermediate_channel()?;
     let init_chan = &mut channel::init_channel()?;
+    let mut init_stderr = InitStderr::new()?;
+    let stderr_fd = init_stderr.write_fd();
 
-    let intermediate_pid = fork::container_fork(|| {
-        let container_pid = container_intermediate_process::container_intermediate_process(
+    let intermediate_pid = match &container_args.reexec {
+        Some(reexec) => reexec::spawn(
             container_args,
+            reexec,
+            main_sender,
             inter_chan,
             init_chan,
-            main_sender,
-        )?;
-
-        if matches!(
-            container_args.container_type,
-            ContainerType::TenantContainer { exec_notify_fd: _ }
-        ) && !container_args.detached
-        {
-            match waitpid(container_pid, None)? {
-                WaitStatus::Exited(_, s) => Ok(s),
-                WaitStatus::Signaled(_, sig, _) => Ok(sig as i32),
-                _ => Ok(0),
-            }
-        } else {
-            Ok(0)
-        }
-    })?;
+            stderr_fd,
+        )?,
+        None => fork::container_fork(|| {
+            run_intermediate(
+                container_args,
+                inter_chan,
+                init_chan,
+                main_sender,
+                stderr_fd,
+            )
+        })?,
+    };
+    init_stderr.capture()?;
+    // The container is given until the deadline to become ready. Once it is
+    // passed, waiting on the channel fails and the processes created so far
+    // are killed. Removing the rest of the partial state is up to the caller.
+    let deadline = container_args
+        .timeout
+        .map(|timeout| Instant::now() + timeout);
+    let mut received_init_pid = None;
     // Close down unused fds. The corresponding fds are duplicated to the
     // child process during fork.
-    main_sender
+    let synced = main_sender
         .close()
-        .context("failed to close unused sender")?;
+        .context("failed to close unused sender")
+        .and_then(|_| {
+            sync_with_container(
+                container_args,
+                intermediate_pid,
+                main_receiver,
+                &mut inter_chan.0,
+                &mut init_chan.0,
+                &mut received_init_pid,
+                deadline,
+            )
+        });
+    let init_pid = match synced {
+        Ok(init_pid) => init_pid,
+        Err(err) => {
+            kill_container_processes(intermediate_pid, received_init_pid, main_receiver);
+            return Err(init_stderr.attach(err));
+        }
+    };
 
-    let (inter_sender, _) = inter_chan;
-    let (init_sender, _) = init_chan;
+    log::debug!("init pid is {:?}", init_pid);
+
+    // here we send both intermediate and init pid, because :
+    // init pid is required for writing it to pid_file (if) given by the high-level runtime
+    // intermediate pid is required in the case when we call exec, as we nned to wait for the
+    // intermediate process to exit, which itself waits for child process (the exec process) to exit
+    // in order to get the proper exit code. We cannot simply wait for the init_pid , that is the actual container
+    // process, as it is not (direect) child of our process
+    Ok((intermediate_pid, init_pid))
+}
+
+/// Runs the intermediate process, which creates the container process. The
+/// intermediate process of a tenant container which is not detached waits
+/// for the container process and takes over its exit code.
+pub(crate) fn run_intermediate(
+    container_args: &ContainerArgs,
+    inter_chan: &mut (channel::IntermediateSender, channel::IntermediateReceiver),
+    init_chan: &mut (channel::InitSender, channel::InitReceiver),
+    main_sender: &mut channel::MainSender,
+    stderr_fd: RawFd,
+) -> Result<i32> {
+    let container_pid = container_intermediate_process::container_intermediate_process(
+        container_args,
+        inter_chan,
+        init_chan,
+        main_sender,
+        stderr_fd,
+    )
+    .map_err(|err| {
+        let _ = main_sender.process_failed(&err);
+        err
+    })?;
 
+    if matches!(
+        container_args.container_type,
+        ContainerType::TenantContainer { exec_notify_fd: _ }
+    ) && !container_args.detached
+    {
+        match waitpid(container_pid, None)? {
+            WaitStatus::Exited(_, s) => Ok(s),
+            WaitStatus::Signaled(_, sig, _) => Ok(sig as i32),
+            _ => Ok(0),
+        }
+    } else {
+        Ok(0)
+    }
+}
+
+fn sync_with_container(
+    container_args: &ContainerArgs,
+    intermediate_pid: Pid,
+    main_receiver: &mut channel::MainReceiver,
+    inter_sender: &mut channel::IntermediateSender,
+    init_sender: &mut channel::InitSender,
+    received_init_pid: &mut Option<Pid>,
+    deadline: Option<Instant>,
+) -> Result<Pid> {
     // If creating a rootless container, the intermediate process will ask
     // the main process to set up uid and gid mapping, once the intermediate
     // process enters into a new user namespace.
     if let Some(rootless) = &container_args.rootless {
+        main_receiver.set_deadline(deadline)?;
         main_receiver.wait_for_mapping_request()?;
         setup_mapping(rootless, intermediate_pid)?;
         inter_sender.mapping_written()?;
@@ -76,7 +168,9 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pi
 
     // The intermediate process will send the init pid once it forks the init
     // process.  The intermediate process should exit after this point.
+    main_receiver.set_deadline(deadline)?;
     let init_pid = main_receiver.wait_for_intermediate_ready()?;
+    *received_init_pid = Some(init_pid);
 
     if let Some(linux) = container_args.spec.linux() {
         if let Some(seccomp) = linux.seccomp() {
@@ -104,19 +198,38 @@ pub fn container_main_process(container_args: &ContainerArgs) -> Result<(Pid, Pi
         .close()
         .context("failed to close unused init sender")?;
 
+    main_receiver.set_deadline(deadline)?;
     main_receiver
         .wait_for_init_ready()
         .context("failed to wait for init ready")?;
 
-    log::debug!("init pid is {:?}", init_pid);
+    Ok(init_pid)
+}
 
-    // here we send both intermediate and init pid, because :
-    // init pid is required for writing it to pid_file (if) given by the high-level runtime
-    // intermediate pid is required in the case when we call exec, as we nned to wait for the
-    // intermediate process to exit, which itself waits for child process (the exec process) to exit
-    // in order to get the proper exit code. We cannot simply wait for the init_pid , that is the actual container
-    // process, as it is not (direect) child of our process
-    Ok((intermediate_pid, init_pid))
+// Kills the processes created so far when the creation is aborted. The
+// intermediate process is killed and reaped first, so that it can not create
+// the init process anymore. The init process is killed explicitly, as a
+// joined cgroup is kept and would leave it running. If its pid has not been
+// received yet, it is received now, in case the intermediate process has sent
+// it before being killed.
+fn kill_container_processes(
+    intermediate_pid: Pid,
+    init_pid: Option<Pid>,
+    main_receiver: &mut channel::MainReceiver,
+) {
+    let _ = signal::kill(intermediate_pid, Signal::SIGKILL);
+    let _ = waitpid(intermediate_pid, None);
+
+    let init_pid = init_pid.or_else(|| {
+        main_receiver
+            .set_deadline(Some(Instant::now() + PENDING_MESSAGE_TIMEOUT))
+            .ok()?;
+        main_receiver.wait_for_intermediate_ready().ok()
+    });
+    if let Some(init_pid) = init_pid {
+        log::debug!("kill init process {}", init_pid);
+        let _ = signal::kill(init_pid, Signal::SIGKILL);
+    }
 }
 
 fn sync_seccomp(
@@ -179,20 +292,25 @@ fn sync_seccomp_send_msg(listener_path: &Path, msg: &[u8], fd: i32) -> Result<()
     Ok(())
 }
 
+// Each of the maps can only be written once, so a partial mapping can not be
+// rolled back. If any write fails, the error is returned before the
+// intermediate process is acknowledged and the creation is aborted, so that the
+// container never runs with an inconsistent mapping.
 fn setup_mapping(rootless: &Rootless, pid: Pid) -> Result<()> {
     log::debug!("write mapping for pid {:?}", pid);
     if !rootless.privileged {
         // The main process is running as an unprivileged user and cannot write the mapping
         // until "deny" has been written to setgroups. See CVE-2014-8989.
-        utils::write_file(format!("/proc/{}/setgroups", pid), "deny")?;
+        utils::write_file(format!("/proc/{}/setgroups", pid), "deny")
+            .with_context(|| format!("failed to deny setgroups of pid {}", pid))?;
     }
 
-    rootless
-        .write_uid_mapping(pid)
-        .context(format!("failed to map uid of pid {}", pid))?;
     rootless
         .write_gid_mapping(pid)
-        .context(format!("failed to map gid of pid {}", pid))?;
+        .with_context(|| format!("failed to write gid_map of pid {}", pid))?;
+    rootless
+        .write_uid_mapping(pid)
+        .with_context(|| format!("failed to write uid_map of pid {}", pid))?;
     Ok(())
 }
 
@@ -201,6 +319,7 @@ mod tests {
     use super::*;
     use crate::process::channel::{intermediate_channel, main_channel};
     use crate::rootless::{get_gid_path, get_uid_path};
+    use anyhow::bail;
     use nix::{
         sched::{unshare, CloneFlags},
         unistd::{self, getgid, getuid},
@@ -210,9 +329,74 @@ mod tests {
     };
     use serial_test::serial;
     use std::fs;
+    use std::time::Duration;
 
     use crate::utils::TempDir;
 
+    #[test]
+    #[serial]
+    fn test_kill_intermediate_on_timeout() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+        // an intermediate process which is stuck and never becomes ready
+        let pid = fork::container_fork(|| loop {
+            unistd::pause();
+        })?;
+        sender.close()?;
+
+        receiver.set_deadline(Some(Instant::now() + Duration::from_millis(100)))?;
+        let err = receiver.wait_for_intermediate_ready().unwrap_err();
+        assert!(format!("{:?}", err).contains("timed out"));
+
+        kill_container_processes(pid, None, receiver);
+        assert_eq!(signal::kill(pid, None), Err(nix::errno::Errno::ESRCH));
+        receiver.close()?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_kill_init_on_timeout() -> Result<()> {
+        let (sender, receiver) = &mut main_channel()?;
+        let (pid_reader, pid_writer) = unistd::pipe()?;
+        // an intermediate process which has created the init process and sent
+        // its pid, but is stuck before the main process waits for the pid
+        let pid = fork::container_fork(|| {
+            let init_pid = fork::container_fork(|| loop {
+                unistd::pause();
+            })?;
+            sender.intermediate_ready(init_pid)?;
+            unistd::write(pid_writer, &init_pid.as_raw().to_ne_bytes())?;
+            loop {
+                unistd::pause();
+            }
+        })?;
+        sender.close()?;
+        unistd::close(pid_writer)?;
+        let mut buf = [0; 4];
+        assert_eq!(unistd::read(pid_reader, &mut buf)?, buf.len());
+        unistd::close(pid_reader)?;
+        let init_pid = i32::from_ne_bytes(buf);
+
+        kill_container_processes(pid, None, receiver);
+        assert_eq!(signal::kill(pid, None), Err(nix::errno::Errno::ESRCH));
+        // the init process is not a child, so it is gone once it is reaped by
+        // another process or a zombie until then
+        let started = Instant::now();
+        while let Ok(stat) = procfs::process::Process::new(init_pid).and_then(|p| p.stat()) {
+            if stat.state == 'Z' {
+                break;
+            }
+            assert!(
+                started.elapsed() < Duration::from_secs(1),
+                "init process {} is still running",
+                init_pid
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        receiver.close()?;
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn setup_uid_mapping_should_succeed() -> Result<()> {
@@ -326,6 +510,70 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn setup_mapping_should_abort_on_gid_map_failure() -> Result<()> {
+        let uid_mappings = vec![LinuxIdMappingBuilder::default()
+            .host_id(getuid())
+            .container_id(0u32)
+            .size(1u32)
+            .build()?];
+        let gid_mappings = vec![LinuxIdMappingBuilder::default()
+            .host_id(getgid())
+            .container_id(0u32)
+            .size(1u32)
+            .build()?];
+        let rootless = Rootless {
+            uid_mappings: Some(&uid_mappings),
+            gid_mappings: Some(&gid_mappings),
+            privileged: true,
+            ..Default::default()
+        };
+        let (mut parent_sender, mut parent_receiver) = main_channel()?;
+        let (child_sender, mut child_receiver) = intermediate_channel()?;
+        match unsafe { unistd::fork()? } {
+            unistd::ForkResult::Parent { child } => {
+                parent_receiver.wait_for_mapping_request()?;
+                parent_receiver.close()?;
+
+                let tempdir = TempDir::new(get_uid_path(&child).parent().unwrap())?;
+                let uid_map_path = tempdir.join("uid_map");
+                let _ = fs::File::create(&uid_map_path)?;
+                // writing to a directory fails
+                fs::create_dir(tempdir.join("gid_map"))?;
+
+                let err = setup_mapping(&rootless, child).unwrap_err();
+                assert_eq!(
+                    err.to_string(),
+                    format!("failed to write gid_map of pid {}", child)
+                );
+                // the uid map is not written once the gid map failed
+                assert_eq!(fs::read_to_string(uid_map_path)?, "");
+
+                // the creation is aborted without acknowledging the mapping
+                child_sender.close()?;
+                match waitpid(child, None)? {
+                    WaitStatus::Exited(_, 0) => bail!("intermediate process continued"),
+                    WaitStatus::Exited(_, _) => {}
+                    status => bail!("unexpected wait status {:?}", status),
+                }
+            }
+            unistd::ForkResult::Child => {
+                prctl::set_dumpable(true).unwrap();
+                unshare(CloneFlags::CLONE_NEWUSER)?;
+                parent_sender.identifier_mapping_request()?;
+                parent_sender.close()?;
+                child_sender.close()?;
+                let code = match child_receiver.wait_for_mapping_ack() {
+                    Ok(_) => 0,
+                    Err(_) => 1,
+                };
+                std::process::exit(code);
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_sync_seccomp() -> Result<()> {
@@ -0,0 +1,133 @@
+//! A minimal init for containers whose entrypoint does not reap its children.
+//!
+//! The shim stays behind as PID 1 of the container, runs the entrypoint as its
+//! only direct child, forwards every signal it receives to it and reaps all
+//! processes which are reparented to it. Once the entrypoint exits, the shim
+//! exits with the same code.
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use nix::sys::signal::{self, SigSet, SigmaskHow, Signal};
+use nix::sys::wait::{self, WaitPidFlag, WaitStatus};
+use nix::unistd::{self, ForkResult, Pid};
+
+/// Runs `entrypoint` in a child process and supervises it. `entrypoint` is
+/// expected to exec and only returns on failure. Returns the exit code of the
+/// entrypoint, a termination by signal is reported as 128 + signal number,
+/// following the convention of shells.
+pub fn run<F: FnOnce() -> Result<()>>(entrypoint: F) -> Result<i32> {
+    // Orphaned processes are reparented to the shim even if it is not PID 1,
+    // e.g. because the container shares the pid namespace of the host.
+    prctl::set_child_subreaper(true)
+        .map_err(Errno::from_i32)
+        .context("failed to become child subreaper")?;
+
+    let all = SigSet::all();
+    let mut old_mask = SigSet::empty();
+    signal::sigprocmask(SigmaskHow::SIG_SETMASK, Some(&all), Some(&mut old_mask))
+        .context("failed to block signals")?;
+
+    let child = match unsafe { unistd::fork()? } {
+        ForkResult::Child => {
+            let result = signal::sigprocmask(SigmaskHow::SIG_SETMASK, Some(&old_mask), None)
+                .context("failed to restore signal mask")
+                .and_then(|_| entrypoint());
+            if let Err(err) = result {
+                log::error!("failed to run container entrypoint: {:?}", err);
+            }
+            std::process::exit(127);
+        }
+        ForkResult::Parent { child } => child,
+    };
+
+    supervise(child, &all)
+}
+
+fn supervise(child: Pid, signals: &SigSet) -> Result<i32> {
+    loop {
+        if let Some(code) = reap(child)? {
+            log::debug!("container entrypoint {} exited with {}", child, code);
+            return Ok(code);
+        }
+
+        let signal = signals.wait().context("failed to wait for signals")?;
+        if signal == Signal::SIGCHLD {
+            continue;
+        }
+
+        if let Err(err) = signal::kill(child, signal) {
+            // the entrypoint may already be gone, its exit is handled on the next reap
+            log::debug!("failed to forward {} to {}: {}", signal, child, err);
+        }
+    }
+}
+
+// Reaps every process which has exited so far and returns the exit code of the
+// entrypoint once it has been reaped. Processes which are reparented to the
+// shim when the entrypoint exits are reaped in the same pass, as they are
+// already waitable by the time the entrypoint is.
+fn reap(child: Pid) -> Result<Option<i32>> {
+    let mut exit_code = None;
+    loop {
+        match wait::waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::StillAlive) | Err(Errno::ECHILD) => return Ok(exit_code),
+            Ok(WaitStatus::Exited(pid, code)) if pid == child => exit_code = Some(code),
+            Ok(WaitStatus::Signaled(pid, signal, _)) if pid == child => {
+                exit_code = Some(128 + signal as i32)
+            }
+            Ok(status) => log::debug!("reaped orphaned process: {:?}", status),
+            Err(Errno::EINTR) => continue,
+            Err(err) => return Err(err).context("failed to reap child processes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils;
+    use anyhow::bail;
+    use nix::sys::wait::{waitid, Id, WaitPidFlag};
+
+    #[test]
+    fn test_reaps_orphans_and_forwards_exit_code() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            let code = run(|| {
+                // leave an exited child behind which the entrypoint never reaps,
+                // it is reparented to the shim when the entrypoint exits
+                let orphan = match unsafe { unistd::fork()? } {
+                    ForkResult::Child => std::process::exit(0),
+                    ForkResult::Parent { child } => child,
+                };
+                waitid(Id::Pid(orphan), WaitPidFlag::WEXITED | WaitPidFlag::WNOWAIT)?;
+                std::process::exit(3);
+            })?;
+
+            if code != 3 {
+                bail!("expected exit code 3, got {}", code);
+            }
+
+            match wait::waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+                Err(Errno::ECHILD) => Ok(()),
+                status => bail!("expected no children left, got {:?}", status),
+            }
+        })
+    }
+
+    #[test]
+    fn test_forwards_signal() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            let code = run(|| {
+                signal::kill(unistd::getppid(), Signal::SIGTERM)?;
+                loop {
+                    unistd::pause();
+                }
+            })?;
+
+            let expected = 128 + Signal::SIGTERM as i32;
+            if code != expected {
+                bail!("expected exit code {}, got {}", expected, code);
+            }
+            Ok(())
+        })
+    }
+}
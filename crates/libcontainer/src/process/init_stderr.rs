@@ -0,0 +1,237 @@
+//! Capture of the stderr of the init process
+//!
+//! The init process reports its errors to the main process over the channel,
+//! but whatever it writes to stderr before it is ready, e.g. the output of a
+//! failing hook, would only end up wherever stderr of the container points to.
+//! Until then stderr of the init process is therefore a pipe to the main
+//! process, which forwards the output to its own stderr and keeps the last
+//! part of it to add to the error if the creation fails.
+use anyhow::{Context, Result};
+use nix::{
+    errno::Errno,
+    fcntl::{self, FcntlArg, OFlag},
+    unistd,
+};
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    os::unix::io::RawFd,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// enough for a few error messages, a chatty init process only keeps the tail
+const CAPACITY: usize = 16 * 1024;
+// time given to a failed init process to deliver what it has written already
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+const STDERR_FD: RawFd = 2;
+
+/// The last bytes written, up to a fixed capacity
+struct RingBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    truncated: bool,
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::with_capacity(capacity),
+            capacity,
+            truncated: false,
+        }
+    }
+
+    fn push(&mut self, buf: &[u8]) {
+        let buf = if buf.len() > self.capacity {
+            self.truncated = true;
+            &buf[buf.len() - self.capacity..]
+        } else {
+            buf
+        };
+
+        let overflow = (self.data.len() + buf.len()).saturating_sub(self.capacity);
+        if overflow > 0 {
+            self.truncated = true;
+            self.data.drain(..overflow);
+        }
+        self.data.extend(buf);
+    }
+
+    fn contents(&self) -> String {
+        let (front, back) = self.data.as_slices();
+        let contents = String::from_utf8_lossy(&[front, back].concat()).into_owned();
+        if self.truncated {
+            format!("...{}", contents)
+        } else {
+            contents
+        }
+    }
+}
+
+pub struct InitStderr {
+    read_fd: RawFd,
+    write_fd: RawFd,
+    buffer: Arc<Mutex<RingBuffer>>,
+    done: Option<mpsc::Receiver<()>>,
+}
+
+impl InitStderr {
+    pub fn new() -> Result<Self> {
+        let (read_fd, write_fd) =
+            unistd::pipe2(OFlag::O_CLOEXEC).context("failed to create init stderr pipe")?;
+        Ok(Self {
+            read_fd,
+            write_fd,
+            buffer: Arc::new(Mutex::new(RingBuffer::new(CAPACITY))),
+            done: None,
+        })
+    }
+
+    /// The end of the pipe the init process writes to. It has to be closed in
+    /// every process except for the init process.
+    pub fn write_fd(&self) -> RawFd {
+        self.write_fd
+    }
+
+    /// Starts to capture the stderr of the init process once it is forked.
+    pub fn capture(&mut self) -> Result<()> {
+        unistd::close(self.write_fd).context("failed to close init stderr pipe")?;
+
+        let (sender, receiver) = mpsc::channel();
+        let read_fd = self.read_fd;
+        let buffer = Arc::clone(&self.buffer);
+        thread::Builder::new()
+            .name("init-stderr".to_owned())
+            .spawn(move || {
+                forward(read_fd, &buffer);
+                let _ = sender.send(());
+            })
+            .context("failed to spawn init stderr thread")?;
+        self.done = Some(receiver);
+
+        Ok(())
+    }
+
+    /// Adds whatever the init process has written to stderr to the error of
+    /// the failed creation of the container.
+    pub fn attach(&self, err: anyhow::Error) -> anyhow::Error {
+        if let Some(done) = &self.done {
+            let _ = done.recv_timeout(DRAIN_TIMEOUT);
+        }
+
+        let stderr = match self.buffer.lock() {
+            Ok(buffer) => buffer.contents(),
+            Err(_) => return err,
+        };
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            err
+        } else {
+            err.context(format!("stderr of the container init process: {}", stderr))
+        }
+    }
+}
+
+fn forward(read_fd: RawFd, buffer: &Mutex<RingBuffer>) {
+    let mut buf = [0; 4096];
+    loop {
+        let len = match unistd::read(read_fd, &mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(Errno::EINTR) => continue,
+            Err(err) => {
+                log::debug!("failed to read stderr of the init process: {}", err);
+                break;
+            }
+        };
+
+        let _ = io::stderr().write_all(&buf[..len]);
+        if let Ok(mut buffer) = buffer.lock() {
+            buffer.push(&buf[..len]);
+        }
+    }
+    let _ = unistd::close(read_fd);
+}
+
+/// Points stderr of the init process to the pipe. Returns stderr as it was
+/// before, which has to be restored once the init process is ready.
+pub fn redirect(write_fd: RawFd) -> Result<RawFd> {
+    let stderr = fcntl::fcntl(STDERR_FD, FcntlArg::F_DUPFD_CLOEXEC(STDERR_FD + 1))
+        .context("failed to duplicate stderr")?;
+    unistd::dup2(write_fd, STDERR_FD).context("failed to redirect stderr")?;
+    unistd::close(write_fd).context("failed to close init stderr pipe")?;
+    Ok(stderr)
+}
+
+/// Restores stderr of the init process, which ends the capture.
+pub fn restore(stderr: RawFd) -> Result<()> {
+    unistd::dup2(stderr, STDERR_FD).context("failed to restore stderr")?;
+    unistd::close(stderr).context("failed to close duplicated stderr")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::fork;
+    use anyhow::{anyhow, bail};
+    use nix::sys::wait::waitpid;
+    use serial_test::serial;
+
+    #[test]
+    fn test_ring_buffer_is_bounded() {
+        let mut buffer = RingBuffer::new(8);
+        buffer.push(b"abc");
+        assert_eq!(buffer.contents(), "abc");
+
+        buffer.push(b"defghij");
+        assert_eq!(buffer.data.len(), 8);
+        assert_eq!(buffer.contents(), "...cdefghij");
+
+        buffer.push(b"0123456789");
+        assert_eq!(buffer.contents(), "...23456789");
+    }
+
+    #[test]
+    #[serial]
+    fn test_init_stderr_is_attached_to_error() -> Result<()> {
+        let mut init_stderr = InitStderr::new()?;
+        let write_fd = init_stderr.write_fd();
+        let pid = fork::container_fork(|| {
+            let stderr = redirect(write_fd)?;
+            unistd::write(STDERR_FD, b"failed to mount /proc\n")?;
+            restore(stderr)?;
+            unistd::write(STDERR_FD, b"written after the init process was ready\n")?;
+            bail!("init process failed")
+        })?;
+        init_stderr.capture()?;
+        waitpid(pid, None)?;
+
+        let err = init_stderr.attach(anyhow!("init process failed"));
+        assert_eq!(
+            err.to_string(),
+            "stderr of the container init process: failed to mount /proc"
+        );
+        assert_eq!(err.root_cause().to_string(), "init process failed");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_nothing_attached_without_stderr() -> Result<()> {
+        let mut init_stderr = InitStderr::new()?;
+        let write_fd = init_stderr.write_fd();
+        let pid = fork::container_fork(|| {
+            redirect(write_fd)?;
+            Ok(0)
+        })?;
+        init_stderr.capture()?;
+        waitpid(pid, None)?;
+
+        let err = init_stderr.attach(anyhow!("init process failed"));
+        assert_eq!(format!("{:#}", err), "init process failed");
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 /// Used as a wrapper for messages to be sent between child and parent processes
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorKind;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Message {
     IntermediateReady(i32),
@@ -10,4 +12,5 @@ pub enum Message {
     SeccompNotify,
     SeccompNotifyDone,
     ExecFailed(String),
+    ProcessFailed(Option<ErrorKind>, Vec<String>),
 }
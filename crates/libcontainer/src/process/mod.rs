@@ -7,4 +7,7 @@ pub mod container_init_process;
 pub mod container_intermediate_process;
 pub mod container_main_process;
 pub mod fork;
+pub mod init_shim;
+pub mod init_stderr;
 pub mod message;
+pub mod reexec;
@@ -0,0 +1,372 @@
+//! Creation of the intermediate process by re-executing the runtime
+//!
+//! A forked child of a runtime with several threads inherits whatever the
+//! other threads held at the time of the fork, e.g. the locks of the allocator
+//! or of stdio, and setting up the namespaces and mounts of the container may
+//! hang on them. Instead the runtime can be executed once more as
+//! `/proc/self/exe` with arguments of its choice, upon which it has to call
+//! [`run`]. The fresh process has a single thread only. The arguments of the
+//! container are passed over a pipe, the channels and the other file
+//! descriptors of the container are inherited.
+use super::{
+    args::{ContainerArgs, ContainerType},
+    channel, container_main_process,
+};
+use crate::{
+    container::Container, io_priority::IoPriority, notify_socket::NotifyListener,
+    rootless::Rootless, scheduler::Scheduler, syscall::syscall::create_syscall,
+};
+use anyhow::{Context, Result};
+use nix::{
+    fcntl::{self, FcntlArg, FdFlag, OFlag},
+    unistd::{self, Pid},
+};
+use oci_spec::runtime::Spec;
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    ffi::OsString,
+    fs::File,
+    io::{self, Read, Write},
+    os::unix::{
+        io::{AsRawFd, FromRawFd, RawFd},
+        process::CommandExt,
+    },
+    path::PathBuf,
+    process::Command,
+};
+
+/// Environment variable with the file descriptor the arguments are read from
+const PIPE_ENV: &str = "_LIBCONTAINER_REEXEC_PIPE";
+const SELF_EXE: &str = "/proc/self/exe";
+
+/// Re-execution of the runtime in place of the fork of the intermediate
+/// process
+#[derive(Debug, Clone)]
+pub struct Reexec {
+    /// Arguments of the runtime upon which it calls [`run`]
+    pub args: Vec<OsString>,
+    /// Cgroup of the container, the manager cannot be passed to the
+    /// re-executed runtime and is created again
+    pub cgroups_path: PathBuf,
+    /// If systemd is used for cgroup management
+    pub use_systemd: bool,
+    /// Id of the container
+    pub container_id: String,
+}
+
+// The channels of the intermediate process, as closing the unused ends is up
+// to the intermediate process all of them are passed.
+#[derive(Debug, Serialize, Deserialize)]
+struct Channels {
+    main_sender: RawFd,
+    inter_sender: RawFd,
+    inter_receiver: RawFd,
+    init_sender: RawFd,
+    init_receiver: RawFd,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Payload {
+    container_type: ContainerType,
+    spec: Spec,
+    rootfs: PathBuf,
+    console_socket: Option<RawFd>,
+    stdio_socket: Option<RawFd>,
+    notify_socket: RawFd,
+    preserve_fds: i32,
+    container: Option<Container>,
+    cgroups_path: PathBuf,
+    use_systemd: bool,
+    container_id: String,
+    detached: bool,
+    init: bool,
+    scheduler: Option<Scheduler>,
+    io_priority: Option<IoPriority>,
+    audit_capabilities: bool,
+    new_keyring: bool,
+    join_cgroup: bool,
+    channels: Channels,
+    stderr_fd: RawFd,
+}
+
+impl Payload {
+    // File descriptors which have to be inherited by the re-executed runtime.
+    // The preserved file descriptors are not close on exec to begin with.
+    fn fds(&self) -> Vec<RawFd> {
+        let mut fds = vec![
+            self.notify_socket,
+            self.channels.main_sender,
+            self.channels.inter_sender,
+            self.channels.inter_receiver,
+            self.channels.init_sender,
+            self.channels.init_receiver,
+            self.stderr_fd,
+        ];
+        fds.extend(self.console_socket);
+        fds.extend(self.stdio_socket);
+        if let ContainerType::TenantContainer { exec_notify_fd } = self.container_type {
+            fds.push(exec_notify_fd);
+        }
+        fds
+    }
+}
+
+/// Starts the intermediate process by re-executing the runtime and returns
+/// its pid.
+pub(crate) fn spawn(
+    args: &ContainerArgs,
+    reexec: &Reexec,
+    main_sender: &channel::MainSender,
+    inter_chan: &(channel::IntermediateSender, channel::IntermediateReceiver),
+    init_chan: &(channel::InitSender, channel::InitReceiver),
+    stderr_fd: RawFd,
+) -> Result<Pid> {
+    let payload = Payload {
+        container_type: args.container_type,
+        spec: args.spec.clone(),
+        rootfs: args.rootfs.clone(),
+        console_socket: args.console_socket,
+        stdio_socket: args.stdio_socket,
+        notify_socket: args.notify_socket.as_raw_fd(),
+        preserve_fds: args.preserve_fds,
+        container: args.container.clone(),
+        cgroups_path: reexec.cgroups_path.clone(),
+        use_systemd: reexec.use_systemd,
+        container_id: reexec.container_id.clone(),
+        detached: args.detached,
+        init: args.init,
+        scheduler: args.scheduler.clone(),
+        io_priority: args.io_priority.clone(),
+        audit_capabilities: args.audit_capabilities,
+        new_keyring: args.new_keyring,
+        join_cgroup: args.join_cgroup,
+        channels: Channels {
+            main_sender: main_sender.as_raw_fd(),
+            inter_sender: inter_chan.0.as_raw_fd(),
+            inter_receiver: inter_chan.1.as_raw_fd(),
+            init_sender: init_chan.0.as_raw_fd(),
+            init_receiver: init_chan.1.as_raw_fd(),
+        },
+        stderr_fd,
+    };
+
+    let payload_fds = payload.fds();
+    let payload = serde_json::to_vec(&payload).context("failed to serialize arguments")?;
+    spawn_with(
+        Command::new(SELF_EXE).args(&reexec.args),
+        &payload,
+        &payload_fds,
+    )
+}
+
+// Executes the command, which inherits the file descriptors. The payload is
+// written to a pipe once the command is started, as it may exceed the
+// capacity of the pipe.
+fn spawn_with(command: &mut Command, payload: &[u8], fds: &[RawFd]) -> Result<Pid> {
+    let (read_fd, write_fd) =
+        unistd::pipe2(OFlag::O_CLOEXEC).context("failed to create reexec pipe")?;
+    let mut inherited = fds.to_vec();
+    inherited.push(read_fd);
+
+    command.env(PIPE_ENV, read_fd.to_string());
+    // only async-signal-safe calls are allowed between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            for fd in &inherited {
+                fcntl::fcntl(*fd, FcntlArg::F_SETFD(FdFlag::empty())).map_err(io::Error::from)?;
+            }
+            Ok(())
+        });
+    }
+
+    let spawned = command.spawn();
+    let _ = unistd::close(read_fd);
+    let mut pipe = unsafe { File::from_raw_fd(write_fd) };
+    let child = spawned.context("failed to re-execute the runtime")?;
+    let pid = Pid::from_raw(child.id() as i32);
+    pipe.write_all(payload)
+        .with_context(|| format!("failed to pass arguments to {}", pid))?;
+
+    Ok(pid)
+}
+
+/// Sets up the container in the re-executed runtime, the counterpart of a
+/// [`Reexec`]. Returns the exit code of the intermediate process.
+pub fn run() -> Result<i32> {
+    let fd: RawFd = env::var(PIPE_ENV)
+        .with_context(|| format!("{} is not set, the runtime is not re-executed", PIPE_ENV))?
+        .parse()
+        .with_context(|| format!("invalid {}", PIPE_ENV))?;
+    // the container process must not see it
+    env::remove_var(PIPE_ENV);
+    let payload = read_payload(fd)?;
+
+    let channels = &payload.channels;
+    let (mut main_sender, mut inter_chan, mut init_chan) = unsafe {
+        (
+            channel::MainSender::from_raw_fd(channels.main_sender),
+            (
+                channel::IntermediateSender::from_raw_fd(channels.inter_sender),
+                channel::IntermediateReceiver::from_raw_fd(channels.inter_receiver),
+            ),
+            (
+                channel::InitSender::from_raw_fd(channels.init_sender),
+                channel::InitReceiver::from_raw_fd(channels.init_receiver),
+            ),
+        )
+    };
+
+    // the exec resets the dumpable flag set by the main process
+    if let Some(linux) = payload.spec.linux() {
+        if linux.namespaces().is_some() {
+            prctl::set_dumpable(false).unwrap();
+        }
+    }
+
+    let syscall = create_syscall();
+    let rootless = Rootless::new(&payload.spec);
+    let cgroup_manager = libcgroups::common::create_cgroup_manager(
+        &payload.cgroups_path,
+        payload.use_systemd,
+        &payload.container_id,
+    );
+    let (rootless, cgroup_manager) = match (rootless, cgroup_manager) {
+        (Ok(rootless), Ok(cgroup_manager)) => (rootless, cgroup_manager),
+        (Err(err), _) | (_, Err(err)) => {
+            let err = err.context("failed to restore the arguments of the container");
+            let _ = main_sender.process_failed(&err);
+            return Err(err);
+        }
+    };
+
+    let container_args = ContainerArgs {
+        container_type: payload.container_type,
+        syscall: syscall.as_ref(),
+        spec: &payload.spec,
+        rootfs: &payload.rootfs,
+        console_socket: payload.console_socket,
+        stdio_socket: payload.stdio_socket,
+        notify_socket: unsafe { NotifyListener::from_raw_fd(payload.notify_socket) },
+        preserve_fds: payload.preserve_fds,
+        container: &payload.container,
+        rootless: &rootless,
+        cgroup_manager,
+        detached: payload.detached,
+        init: payload.init,
+        scheduler: payload.scheduler.clone(),
+        io_priority: payload.io_priority.clone(),
+        audit_capabilities: payload.audit_capabilities,
+        new_keyring: payload.new_keyring,
+        join_cgroup: payload.join_cgroup,
+        // the deadline is up to the main process
+        timeout: None,
+        reexec: None,
+    };
+
+    container_main_process::run_intermediate(
+        &container_args,
+        &mut inter_chan,
+        &mut init_chan,
+        &mut main_sender,
+        payload.stderr_fd,
+    )
+}
+
+fn read_payload(fd: RawFd) -> Result<Payload> {
+    let mut pipe = unsafe { File::from_raw_fd(fd) };
+    let mut buf = Vec::new();
+    pipe.read_to_end(&mut buf)
+        .context("failed to read arguments of the re-executed runtime")?;
+    serde_json::from_slice(&buf).context("failed to parse arguments of the re-executed runtime")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::sys::wait::{waitpid, WaitStatus};
+    use serial_test::serial;
+
+    fn payload() -> Payload {
+        Payload {
+            container_type: ContainerType::TenantContainer { exec_notify_fd: 12 },
+            spec: Spec::default(),
+            rootfs: PathBuf::from("/var/run/docker/bundle/rootfs"),
+            console_socket: None,
+            stdio_socket: Some(11),
+            notify_socket: 3,
+            preserve_fds: 0,
+            container: None,
+            cgroups_path: PathBuf::from("/youki/74f1a4cb3801"),
+            use_systemd: false,
+            container_id: "74f1a4cb3801".to_owned(),
+            detached: true,
+            init: false,
+            scheduler: None,
+            io_priority: None,
+            audit_capabilities: false,
+            new_keyring: false,
+            join_cgroup: false,
+            channels: Channels {
+                main_sender: 4,
+                inter_sender: 5,
+                inter_receiver: 6,
+                init_sender: 7,
+                init_receiver: 8,
+            },
+            stderr_fd: 9,
+        }
+    }
+
+    #[test]
+    fn test_payload_fds() {
+        let mut fds = payload().fds();
+        fds.sort_unstable();
+        assert_eq!(fds, vec![3, 4, 5, 6, 7, 8, 9, 11, 12]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_payload() -> Result<()> {
+        let (read_fd, write_fd) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        let mut pipe = unsafe { File::from_raw_fd(write_fd) };
+        pipe.write_all(&serde_json::to_vec(&payload())?)?;
+        drop(pipe);
+
+        let read = read_payload(read_fd)?;
+        assert!(matches!(
+            read.container_type,
+            ContainerType::TenantContainer { exec_notify_fd: 12 }
+        ));
+        assert_eq!(read.spec, Spec::default());
+        assert_eq!(read.cgroups_path, PathBuf::from("/youki/74f1a4cb3801"));
+        assert_eq!(read.fds(), payload().fds());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_spawn_passes_payload_and_fds() -> Result<()> {
+        // the payload exceeds the capacity of a pipe, only its size is written
+        // to the inherited output pipe, which is close on exec
+        let (read_fd, write_fd) = unistd::pipe2(OFlag::O_CLOEXEC)?;
+        // sh may only redirect file descriptors up to 9
+        let script = format!(
+            "wc -c </proc/self/fd/${} >/proc/self/fd/{}",
+            PIPE_ENV, write_fd
+        );
+        let payload = vec![b'x'; 256 * 1024];
+        let pid = spawn_with(
+            Command::new("sh").arg("-c").arg(script),
+            &payload,
+            &[write_fd],
+        )?;
+        unistd::close(write_fd)?;
+
+        let mut output = String::new();
+        unsafe { File::from_raw_fd(read_fd) }.read_to_string(&mut output)?;
+        assert_eq!(output.trim(), payload.len().to_string());
+        assert_eq!(waitpid(pid, None)?, WaitStatus::Exited(pid, 0));
+        Ok(())
+    }
+}
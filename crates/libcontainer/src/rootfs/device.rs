@@ -33,7 +33,7 @@ impl Device {
         I: IntoIterator<Item = &'a LinuxDevice>,
     {
         let old_mode = umask(Mode::from_bits_truncate(0o000));
-        devices
+        let result = devices
             .into_iter()
             .map(|dev| {
                 if !dev.path().starts_with("/dev") {
@@ -46,13 +46,23 @@ impl Device {
                     self.mknod_dev(rootfs, dev)
                 }
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<Result<Vec<_>>>();
         umask(old_mode);
 
-        Ok(())
+        result.map(|_| ())
     }
 
+    // Device nodes cannot be created without CAP_MKNOD in the initial user
+    // namespace, so in rootless mode the device of the host is bind mounted
+    // instead. Ownership and mode are those of the host device in that case.
     fn bind_dev(&self, rootfs: &Path, dev: &LinuxDevice) -> Result<()> {
+        if !dev.path().exists() {
+            bail!(
+                "device {} does not exist on the host and can not be created in rootless mode",
+                dev.path().display()
+            );
+        }
+
         let full_container_path = create_container_dev_path(rootfs, dev)
             .with_context(|| format!("could not create container path for device {:?}", dev))?;
 
@@ -84,10 +94,11 @@ impl Device {
         let full_container_path = create_container_dev_path(rootfs, dev)
             .with_context(|| format!("could not create container path for device {:?}", dev))?;
 
+        // as runc does, devices without an explicit file mode are accessible to everyone
         self.syscall.mknod(
             &full_container_path,
             to_sflag(dev.typ()),
-            Mode::from_bits_truncate(dev.file_mode().unwrap_or(0)),
+            Mode::from_bits_truncate(dev.file_mode().unwrap_or(0o666)),
             makedev(dev.major(), dev.minor()),
         )?;
         self.syscall.chown(
@@ -98,6 +109,31 @@ impl Device {
 
         Ok(())
     }
+
+    /// Bind mounts the pseudo terminal of the container process to /dev/console.
+    pub fn setup_console(&self, rootfs: &Path, console: &Path) -> Result<()> {
+        let container_console = utils::secure_join(rootfs, Path::new("dev/console"))
+            .context("could not join rootfs with /dev/console")?;
+        crate::utils::create_dir_all(container_console.parent().unwrap_or_else(|| Path::new("")))?;
+
+        let fd = open(
+            &container_console,
+            OFlag::O_RDWR | OFlag::O_CREAT,
+            Mode::from_bits_truncate(0o644),
+        )?;
+        close(fd)?;
+        self.syscall
+            .mount(
+                Some(console),
+                &container_console,
+                Some("bind"),
+                MsFlags::MS_BIND,
+                None,
+            )
+            .with_context(|| format!("failed to bind mount {:?} to /dev/console", console))?;
+
+        Ok(())
+    }
 }
 
 fn create_container_dev_path(rootfs: &Path, dev: &LinuxDevice) -> Result<PathBuf> {
@@ -137,15 +173,65 @@ mod tests {
             .bind_dev(
                 tmp_dir.path(),
                 &LinuxDeviceBuilder::default()
-                    .path(PathBuf::from("/null"))
+                    .path(PathBuf::from("/dev/null"))
+                    .build()
+                    .unwrap(),
+            )
+            .is_ok());
+
+        let want = MountArgs {
+            source: Some(PathBuf::from("/dev/null")),
+            target: tmp_dir.path().join("dev/null"),
+            fstype: Some("bind".to_string()),
+            flags: MsFlags::MS_BIND,
+            data: None,
+        };
+        let got = &device
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args()[0];
+        assert_eq!(want, *got);
+    }
+
+    #[test]
+    fn test_bind_dev_without_host_source() {
+        let tmp_dir = TempDir::new("/tmp/test_bind_dev_without_host_source").unwrap();
+        let device = Device::new();
+        let err = device
+            .bind_dev(
+                tmp_dir.path(),
+                &LinuxDeviceBuilder::default()
+                    .path(PathBuf::from("/dev/youki-missing-device"))
+                    .typ(LinuxDeviceType::C)
+                    .major(10)
+                    .minor(200)
                     .build()
                     .unwrap(),
             )
+            .unwrap_err();
+        assert!(err.to_string().contains("/dev/youki-missing-device"));
+
+        let mocks = device
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        assert!(mocks.get_mount_args().is_empty());
+    }
+
+    #[test]
+    fn test_setup_console() {
+        let tmp_dir = TempDir::new("/tmp/test_setup_console").unwrap();
+        let device = Device::new();
+        assert!(device
+            .setup_console(tmp_dir.path(), Path::new("/dev/pts/3"))
             .is_ok());
 
         let want = MountArgs {
-            source: Some(PathBuf::from("/null")),
-            target: tmp_dir.path().join("null"),
+            source: Some(PathBuf::from("/dev/pts/3")),
+            target: tmp_dir.path().join("dev/console"),
             fstype: Some("bind".to_string()),
             flags: MsFlags::MS_BIND,
             data: None,
@@ -157,6 +243,45 @@ mod tests {
             .unwrap()
             .get_mount_args()[0];
         assert_eq!(want, *got);
+        assert!(tmp_dir.path().join("dev/console").is_file());
+    }
+
+    #[test]
+    fn test_mknod_dev_default_mode() {
+        let tmp_dir = TempDir::new("/tmp/test_mknod_dev_default_mode").unwrap();
+        let device = Device::new();
+        assert!(device
+            .mknod_dev(
+                tmp_dir.path(),
+                &LinuxDeviceBuilder::default()
+                    .path(PathBuf::from("/dev/fuse"))
+                    .major(10)
+                    .minor(229)
+                    .typ(LinuxDeviceType::C)
+                    .build()
+                    .unwrap(),
+            )
+            .is_ok());
+
+        let mocks = device
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        let want_mknod = MknodArgs {
+            path: tmp_dir.path().join("dev/fuse"),
+            kind: SFlag::S_IFCHR,
+            perm: Mode::from_bits_truncate(0o666),
+            dev: 2789,
+        };
+        assert_eq!(want_mknod, mocks.get_mknod_args()[0]);
+
+        let want_chown = ChownArgs {
+            path: tmp_dir.path().join("dev/fuse"),
+            owner: None,
+            group: None,
+        };
+        assert_eq!(want_chown, mocks.get_chown_args()[0]);
     }
 
     #[test]
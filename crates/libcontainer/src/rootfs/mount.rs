@@ -15,13 +15,14 @@ use libcgroups::common::DEFAULT_CGROUP_ROOT;
 use nix::{dir::Dir, errno::Errno, fcntl::OFlag, mount::MsFlags, sys::stat::Mode};
 use oci_spec::runtime::{Mount as SpecMount, MountBuilder as SpecMountBuilder};
 use procfs::process::{MountInfo, MountOptFields, Process};
+use std::collections::HashMap;
 use std::fs::{canonicalize, create_dir_all, OpenOptions};
 use std::mem;
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 
 #[cfg(feature = "v1")]
-use std::{borrow::Cow, collections::HashMap};
+use std::borrow::Cow;
 
 #[derive(Debug)]
 pub struct MountOptions<'a> {
@@ -72,6 +73,21 @@ impl Mount {
                     }
                 }
             }
+            Some("overlay") => {
+                validate_overlay_data(&mount_option_config.data)
+                    .with_context(|| format!("invalid overlay mount {:?}", mount.destination()))?;
+                self.mount_into_container(mount, options.root, &mount_option_config, options.label)
+                    .with_context(|| format!("failed to mount overlay: {:?}", mount))?
+            }
+            Some("proc") => {
+                mount_option_config.data = proc_data(&mount_option_config.data)
+                    .with_context(|| format!("invalid proc mount {:?}", mount.destination()))?;
+                self.mount_into_container(mount, options.root, &mount_option_config, options.label)
+                    .with_context(|| format!("failed to mount proc: {:?}", mount))?
+            }
+            Some("devpts") => self
+                .mount_devpts(mount, options, &mount_option_config)
+                .with_context(|| format!("failed to mount devpts: {:?}", mount))?,
             _ => {
                 if *mount.destination() == PathBuf::from("/dev") {
                     mount_option_config.flags &= !MsFlags::MS_RDONLY;
@@ -97,6 +113,69 @@ impl Mount {
         Ok(())
     }
 
+    /// Mounts a new devpts instance on /dev/pts, for specs which do not
+    /// configure one, so that ptys can be allocated through /dev/ptmx.
+    pub fn setup_devpts(&self, options: &MountOptions) -> Result<()> {
+        let devpts = SpecMountBuilder::default()
+            .destination(PathBuf::from("/dev/pts"))
+            .typ("devpts")
+            .source(PathBuf::from("devpts"))
+            .options(vec![
+                "nosuid".to_string(),
+                "noexec".to_string(),
+                "newinstance".to_string(),
+                "ptmxmode=0666".to_string(),
+                "mode=0620".to_string(),
+                "gid=5".to_string(),
+            ])
+            .build()?;
+
+        self.setup_mount(&devpts, options)
+    }
+
+    // Kernels before 4.7 which are built without support for multiple devpts
+    // instances reject the newinstance options with EINVAL, in which case the
+    // instance of the host is mounted instead. Any other error is returned.
+    fn mount_devpts(
+        &self,
+        mount: &SpecMount,
+        options: &MountOptions,
+        mount_option_config: &MountOptionConfig,
+    ) -> Result<()> {
+        let err = match self.mount_into_container(
+            mount,
+            options.root,
+            mount_option_config,
+            options.label,
+        ) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        if err.root_cause().downcast_ref() != Some(&Errno::EINVAL) {
+            return Err(err);
+        }
+
+        let data = mount_option_config
+            .data
+            .split(',')
+            .filter(|o| *o != "newinstance" && !o.starts_with("ptmxmode="))
+            .collect::<Vec<_>>()
+            .join(",");
+        if data == mount_option_config.data {
+            return Err(err);
+        }
+
+        log::warn!(
+            "failed to mount devpts as new instance, falling back to the instance of the host: {:?}",
+            err
+        );
+        let fallback = MountOptionConfig {
+            data,
+            ..mount_option_config.clone()
+        };
+        self.mount_into_container(mount, options.root, &fallback, options.label)
+    }
+
     #[cfg(feature = "v1")]
     fn mount_cgroup_v1(&self, cgroup_mount: &SpecMount, options: &MountOptions) -> Result<()> {
         log::debug!("Mounting cgroup v1 filesystem");
@@ -477,16 +556,147 @@ impl Mount {
     }
 }
 
+const OVERLAY_DIR_OPTIONS: [&str; 3] = ["lowerdir", "upperdir", "workdir"];
+
+/// Resolves the relative lowerdir, upperdir and workdir options of an overlay
+/// mount against the bundle, other options are returned as they are.
+pub fn resolve_overlay_options(options: &[String], bundle: &Path) -> Result<Vec<String>> {
+    options
+        .iter()
+        .map(|option| match option.split_once('=') {
+            Some((key, dirs)) if OVERLAY_DIR_OPTIONS.contains(&key) => {
+                let dirs = dirs
+                    .split(':')
+                    .map(|dir| {
+                        let dir = bundle.join(dir).canonicalize_safely()?;
+                        Ok(dir.to_string_lossy().into_owned())
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(format!("{}={}", key, dirs.join(":")))
+            }
+            _ => Ok(option.clone()),
+        })
+        .collect()
+}
+
+// An overlay needs at least one lowerdir. upperdir and workdir are only valid
+// together, without them the overlay is read only.
+fn validate_overlay_data(data: &str) -> Result<()> {
+    let mut dirs: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (key, value) in data.split(',').filter_map(|o| o.split_once('=')) {
+        if OVERLAY_DIR_OPTIONS.contains(&key) {
+            dirs.insert(key, value.split(':').collect());
+        }
+    }
+
+    if !dirs.contains_key("lowerdir") {
+        bail!("lowerdir is missing");
+    }
+    match (dirs.contains_key("upperdir"), dirs.contains_key("workdir")) {
+        (true, false) => bail!("workdir is missing, it is required with upperdir"),
+        (false, true) => bail!("upperdir is missing, it is required with workdir"),
+        _ => {}
+    }
+
+    for (key, paths) in dirs {
+        for path in paths {
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                bail!("{} {:?} is not an absolute path", key, path);
+            }
+            if !path.is_dir() {
+                bail!("{} {:?} does not exist", key, path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Validates the options of a proc mount, so that a typo in the hardening of
+// /proc does not silently leave it unhardened. The hardening options without a
+// value default to their safe setting, hiding the processes of other users and
+// everything but the processes.
+fn proc_data(data: &str) -> Result<String> {
+    let mut options = Vec::new();
+    for option in data.split(',').filter(|o| !o.is_empty()) {
+        let option = match option.split_once('=') {
+            None if option == "hidepid" => "hidepid=2".to_owned(),
+            None if option == "subset" => "subset=pid".to_owned(),
+            Some(("hidepid", value))
+                if [
+                    "0",
+                    "1",
+                    "2",
+                    "4",
+                    "off",
+                    "noaccess",
+                    "invisible",
+                    "ptraceable",
+                ]
+                .contains(&value) =>
+            {
+                option.to_owned()
+            }
+            Some(("subset", "pid")) => option.to_owned(),
+            Some(("gid", value)) if value.parse::<u32>().is_ok() => option.to_owned(),
+            _ => bail!("unknown proc option {:?}", option),
+        };
+        options.push(option);
+    }
+
+    Ok(options.join(","))
+}
+
 #[cfg(test)]
 mod tests {
-    #[cfg(feature = "v1")]
     use std::fs;
 
     use super::*;
-    use crate::syscall::test::{MountArgs, TestHelperSyscall};
+    use crate::rootfs::utils::sort_mounts;
+    use crate::syscall::test::{ArgName, MountArgs, TestHelperSyscall};
     use crate::utils::create_temp_dir;
     use anyhow::Result;
 
+    #[test]
+    fn test_mount_in_dependency_order() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_mount_in_dependency_order")?;
+        let mounts: Vec<SpecMount> = ["/var/lib/x", "/var/lib", "/var"]
+            .iter()
+            .map(|destination| {
+                SpecMountBuilder::default()
+                    .destination(PathBuf::from(destination))
+                    .typ("tmpfs")
+                    .source(PathBuf::from("tmpfs"))
+                    .build()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let m = Mount::new();
+        for mount in sort_mounts(&mounts) {
+            m.mount_into_container(mount, tmp_dir.path(), &parse_mount(mount), None)?;
+        }
+
+        let targets: Vec<PathBuf> = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args()
+            .into_iter()
+            .map(|args| args.target)
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                tmp_dir.path().join("var"),
+                tmp_dir.path().join("var/lib"),
+                tmp_dir.path().join("var/lib/x"),
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_mount_to_container() {
         let tmp_dir = create_temp_dir("test_mount_to_container").unwrap();
@@ -583,6 +793,272 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_setup_hardened_proc() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_setup_hardened_proc")?;
+        let proc = SpecMountBuilder::default()
+            .destination(PathBuf::from("/proc"))
+            .typ("proc")
+            .source(PathBuf::from("proc"))
+            .options(vec![
+                "nosuid".to_string(),
+                "subset=pid".to_string(),
+                "hidepid=2".to_string(),
+            ])
+            .build()?;
+
+        let m = Mount::new();
+        m.setup_mount(
+            &proc,
+            &MountOptions {
+                root: tmp_dir.path(),
+                label: None,
+                cgroup_ns: false,
+            },
+        )?;
+
+        let want = vec![MountArgs {
+            source: Some(PathBuf::from("proc")),
+            target: tmp_dir.path().join("proc"),
+            fstype: Some("proc".to_string()),
+            flags: MsFlags::MS_NOSUID,
+            data: Some("subset=pid,hidepid=2".to_string()),
+        }];
+        let got = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args();
+        assert_eq!(want, got);
+        Ok(())
+    }
+
+    #[test]
+    fn test_proc_data() -> Result<()> {
+        assert_eq!(proc_data("")?, "");
+        assert_eq!(proc_data("hidepid,subset")?, "hidepid=2,subset=pid");
+        assert_eq!(
+            proc_data("hidepid=invisible,gid=4")?,
+            "hidepid=invisible,gid=4"
+        );
+        assert!(proc_data("hidepid=3").is_err());
+        assert!(proc_data("subset=sys").is_err());
+        assert!(proc_data("hidepd=2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_devpts() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_setup_devpts")?;
+        let m = Mount::new();
+        m.setup_devpts(&MountOptions {
+            root: tmp_dir.path(),
+            label: None,
+            cgroup_ns: false,
+        })?;
+
+        let want = vec![MountArgs {
+            source: Some(PathBuf::from("devpts")),
+            target: tmp_dir.path().join("dev/pts"),
+            fstype: Some("devpts".to_string()),
+            flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
+            data: Some("newinstance,ptmxmode=0666,mode=0620,gid=5".to_string()),
+        }];
+        let got = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args();
+        assert_eq!(want, got);
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_devpts_without_newinstance() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_setup_devpts_without_newinstance")?;
+        let m = Mount::new();
+        let mocks = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        // the mount is attempted with and without the label before falling back
+        mocks.set_ret_err(ArgName::Mount, || bail!(Errno::EINVAL));
+        mocks.set_ret_err_times(ArgName::Mount, 2);
+
+        m.setup_devpts(&MountOptions {
+            root: tmp_dir.path(),
+            label: None,
+            cgroup_ns: false,
+        })?;
+
+        let want = vec![MountArgs {
+            source: Some(PathBuf::from("devpts")),
+            target: tmp_dir.path().join("dev/pts"),
+            fstype: Some("devpts".to_string()),
+            flags: MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
+            data: Some("mode=0620,gid=5".to_string()),
+        }];
+        assert_eq!(want, mocks.get_mount_args());
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_devpts_fails_without_fallback() -> Result<()> {
+        let tmp_dir = create_temp_dir("test_setup_devpts_fails_without_fallback")?;
+        let m = Mount::new();
+        let mocks = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap();
+        // only EINVAL means that new instances are not supported
+        mocks.set_ret_err(ArgName::Mount, || bail!(Errno::EPERM));
+
+        let err = m
+            .setup_devpts(&MountOptions {
+                root: tmp_dir.path(),
+                label: None,
+                cgroup_ns: false,
+            })
+            .unwrap_err();
+        assert!(format!("{:?}", err).contains("EPERM"));
+        assert!(mocks.get_mount_args().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_overlay_options() -> Result<()> {
+        let options = vec![
+            "lowerdir=lower1:/abs/lower2".to_string(),
+            "upperdir=upper".to_string(),
+            "workdir=work".to_string(),
+            "index=off".to_string(),
+        ];
+        let got = resolve_overlay_options(&options, Path::new("/bundle"))?;
+        assert_eq!(
+            got,
+            vec![
+                "lowerdir=/bundle/lower1:/abs/lower2".to_string(),
+                "upperdir=/bundle/upper".to_string(),
+                "workdir=/bundle/work".to_string(),
+                "index=off".to_string(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_overlay_data() -> Result<()> {
+        let tmp = create_temp_dir("test_validate_overlay_data")?;
+        for dir in ["lower", "upper", "work"] {
+            fs::create_dir(tmp.join(dir))?;
+        }
+        let dir = |name: &str| tmp.join(name).display().to_string();
+
+        let rw = format!(
+            "lowerdir={},upperdir={},workdir={}",
+            dir("lower"),
+            dir("upper"),
+            dir("work")
+        );
+        assert!(validate_overlay_data(&rw).is_ok());
+        let ro = format!("lowerdir={}:{}", dir("lower"), dir("upper"));
+        assert!(validate_overlay_data(&ro).is_ok());
+
+        let errors = [
+            (
+                format!("upperdir={},workdir={}", dir("upper"), dir("work")),
+                "lowerdir is missing",
+            ),
+            (
+                format!("lowerdir={},upperdir={}", dir("lower"), dir("upper")),
+                "workdir is missing",
+            ),
+            (
+                format!("lowerdir={},workdir={}", dir("lower"), dir("work")),
+                "upperdir is missing",
+            ),
+            (format!("lowerdir={}", dir("missing")), "does not exist"),
+            ("lowerdir=lower".to_string(), "not an absolute path"),
+        ];
+        for (data, want) in errors {
+            let err = validate_overlay_data(&data).expect_err(&data);
+            assert!(err.to_string().contains(want), "{}: {}", data, err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_overlay_mount() -> Result<()> {
+        let tmp = create_temp_dir("test_setup_overlay_mount")?;
+        for dir in ["lower", "upper", "work"] {
+            fs::create_dir(tmp.join(dir))?;
+        }
+        let data = format!(
+            "lowerdir={},upperdir={},workdir={}",
+            tmp.join("lower").display(),
+            tmp.join("upper").display(),
+            tmp.join("work").display()
+        );
+        let rootfs = tmp.join("rootfs");
+        let m = Mount::new();
+        let mount = SpecMountBuilder::default()
+            .destination(PathBuf::from("/merged"))
+            .typ("overlay")
+            .source(PathBuf::from("overlay"))
+            .options(data.split(',').map(String::from).collect::<Vec<_>>())
+            .build()?;
+        m.setup_mount(
+            &mount,
+            &MountOptions {
+                root: &rootfs,
+                label: None,
+                cgroup_ns: false,
+            },
+        )?;
+
+        let want = vec![MountArgs {
+            source: Some(PathBuf::from("overlay")),
+            target: rootfs.join("merged"),
+            fstype: Some("overlay".to_string()),
+            flags: MsFlags::empty(),
+            data: Some(data),
+        }];
+        let got = m
+            .syscall
+            .as_any()
+            .downcast_ref::<TestHelperSyscall>()
+            .unwrap()
+            .get_mount_args();
+        assert_eq!(want, got);
+
+        // nothing is mounted for an overlay without workdir
+        let m = Mount::new();
+        let mount = SpecMountBuilder::default()
+            .destination(PathBuf::from("/merged"))
+            .typ("overlay")
+            .source(PathBuf::from("overlay"))
+            .options(vec![
+                format!("lowerdir={}", tmp.join("lower").display()),
+                format!("upperdir={}", tmp.join("upper").display()),
+            ])
+            .build()?;
+        assert!(m
+            .setup_mount(
+                &mount,
+                &MountOptions {
+                    root: &rootfs,
+                    label: None,
+                    cgroup_ns: false,
+                },
+            )
+            .is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_make_parent_mount_private() {
         let tmp_dir = create_temp_dir("test_make_parent_mount_private").unwrap();
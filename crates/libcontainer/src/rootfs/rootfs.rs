@@ -2,12 +2,12 @@ use super::{
     device::Device,
     mount::{Mount, MountOptions},
     symlink::Symlink,
-    utils::default_devices,
+    utils::{default_devices, sort_mounts},
 };
 use crate::syscall::{syscall::create_syscall, Syscall};
 use anyhow::{bail, Context, Result};
 use nix::mount::MsFlags;
-use oci_spec::runtime::{Linux, Spec};
+use oci_spec::runtime::{Linux, LinuxDevice, Spec};
 use std::path::Path;
 
 /// Holds information about rootfs
@@ -34,6 +34,7 @@ impl RootFS {
         rootfs: &Path,
         bind_devices: bool,
         cgroup_ns: bool,
+        console: Option<&Path>,
     ) -> Result<()> {
         log::debug!("Prepare rootfs: {:?}", rootfs);
         let mut flags = MsFlags::MS_REC;
@@ -71,7 +72,30 @@ impl RootFS {
             cgroup_ns,
         };
 
+        // Devices can not be created on a read only rootfs, so they are put
+        // on a tmpfs unless the spec already provides a mount for /dev.
+        let readonly = spec.root().as_ref().and_then(|r| r.readonly()) == Some(true);
+        let dev_mounted = spec
+            .mounts()
+            .iter()
+            .flatten()
+            .any(|m| m.destination() == Path::new("/dev"));
+        if readonly && !dev_mounted {
+            let dev = rootfs.join("dev");
+            crate::utils::create_dir_all(&dev)?;
+            self.syscall
+                .mount(
+                    Some(Path::new("tmpfs")),
+                    &dev,
+                    Some("tmpfs"),
+                    MsFlags::MS_NOSUID | MsFlags::MS_STRICTATIME,
+                    Some("mode=755,size=65536k"),
+                )
+                .context("failed to mount tmpfs on /dev")?;
+        }
+
         if let Some(mounts) = spec.mounts() {
+            let mounts = sort_mounts(mounts);
             for mount in mounts {
                 mounter
                     .setup_mount(mount, &global_options)
@@ -79,6 +103,17 @@ impl RootFS {
             }
         }
 
+        let devpts_mounted = spec
+            .mounts()
+            .iter()
+            .flatten()
+            .any(|m| m.destination() == Path::new("/dev/pts"));
+        if !devpts_mounted {
+            mounter
+                .setup_devpts(&global_options)
+                .context("failed to setup /dev/pts")?;
+        }
+
         let symlinker = Symlink::new();
         symlinker
             .setup_kcore_symlink(rootfs)
@@ -88,15 +123,12 @@ impl RootFS {
             .context("failed to setup default symlinks")?;
 
         let devicer = Device::new();
-        if let Some(added_devices) = linux.devices() {
-            devicer.create_devices(
-                rootfs,
-                default_devices().iter().chain(added_devices),
-                bind_devices,
-            )
-        } else {
-            devicer.create_devices(rootfs, &default_devices(), bind_devices)
-        }?;
+        devicer.create_devices(rootfs, &container_devices(linux), bind_devices)?;
+        if let Some(console) = console {
+            devicer
+                .setup_console(rootfs, console)
+                .context("failed to setup console")?;
+        }
 
         symlinker.setup_ptmx(rootfs)?;
         Ok(())
@@ -120,3 +152,60 @@ impl RootFS {
         Ok(())
     }
 }
+
+// The default devices, followed by the devices of the spec. A device which is
+// configured explicitly in the spec takes precedence over the default one.
+fn container_devices(linux: &Linux) -> Vec<LinuxDevice> {
+    let added_devices = linux.devices().clone().unwrap_or_default();
+    let mut devices: Vec<LinuxDevice> = default_devices()
+        .into_iter()
+        .filter(|d| !added_devices.iter().any(|a| a.path() == d.path()))
+        .collect();
+    devices.extend(added_devices);
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::{LinuxBuilder, LinuxDeviceBuilder, LinuxDeviceType};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_container_devices_default() -> Result<()> {
+        let linux = LinuxBuilder::default().devices(vec![]).build()?;
+        assert_eq!(container_devices(&linux), default_devices());
+        Ok(())
+    }
+
+    #[test]
+    fn test_container_devices_skips_configured() -> Result<()> {
+        let null = LinuxDeviceBuilder::default()
+            .path(PathBuf::from("/dev/null"))
+            .typ(LinuxDeviceType::C)
+            .major(1)
+            .minor(3)
+            .file_mode(0o600u32)
+            .uid(1000u32)
+            .build()?;
+        let fuse = LinuxDeviceBuilder::default()
+            .path(PathBuf::from("/dev/fuse"))
+            .typ(LinuxDeviceType::C)
+            .major(10)
+            .minor(229)
+            .build()?;
+        let linux = LinuxBuilder::default()
+            .devices(vec![null.clone(), fuse.clone()])
+            .build()?;
+
+        let devices = container_devices(&linux);
+        assert_eq!(devices.len(), default_devices().len() + 1);
+        let nulls: Vec<_> = devices
+            .iter()
+            .filter(|d| d.path() == Path::new("/dev/null"))
+            .collect();
+        assert_eq!(nulls, vec![&null]);
+        assert!(devices.contains(&fuse));
+        Ok(())
+    }
+}
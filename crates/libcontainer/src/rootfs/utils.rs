@@ -3,7 +3,9 @@ use nix::{mount::MsFlags, sys::stat::SFlag, NixPath};
 use oci_spec::runtime::{LinuxDevice, LinuxDeviceBuilder, LinuxDeviceType, Mount};
 use procfs::process::MountInfo;
 use std::{
-    path::{Path, PathBuf},
+    cmp::Reverse,
+    collections::BinaryHeap,
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 
@@ -194,6 +196,67 @@ pub fn find_parent_mount(rootfs: &Path, mount_infos: Vec<MountInfo>) -> Result<M
     Ok(parent_mount_info)
 }
 
+/// Orders the mounts so that every mount is applied after the mounts on parent
+/// destinations, which would hide it otherwise. Mounts on the same destination
+/// and unrelated mounts keep the order of the spec.
+pub fn sort_mounts(mounts: &[Mount]) -> Vec<&Mount> {
+    let destinations: Vec<_> = mounts
+        .iter()
+        .map(|m| normalize_destination(m.destination()))
+        .collect();
+
+    // number of mounts which have to be applied before each mount, and the
+    // mounts waiting for each mount
+    let mut pending = vec![0; mounts.len()];
+    let mut dependents = vec![Vec::new(); mounts.len()];
+    for (i, dest) in destinations.iter().enumerate() {
+        for (j, other) in destinations.iter().enumerate() {
+            let is_parent = dest != other && dest.starts_with(other);
+            if is_parent || (dest == other && j < i) {
+                pending[i] += 1;
+                dependents[j].push(i);
+            }
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..mounts.len())
+        .filter(|&i| pending[i] == 0)
+        .map(Reverse)
+        .collect();
+    // a mount only depends on mounts on shorter destinations or on earlier
+    // mounts on the same destination, so every mount is sorted eventually
+    let mut sorted = Vec::with_capacity(mounts.len());
+    while let Some(Reverse(i)) = ready.pop() {
+        sorted.push(&mounts[i]);
+        for &dependent in &dependents[i] {
+            pending[dependent] -= 1;
+            if pending[dependent] == 0 {
+                ready.push(Reverse(dependent));
+            }
+        }
+    }
+
+    sorted
+}
+
+// Resolves the destination lexically, a relative destination is relative to
+// the root of the container. Like within the container, ".." of the root is
+// the root itself.
+fn normalize_destination(destination: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in destination.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+
+    normalized
+}
+
 #[cfg(test)]
 mod tests {
     use crate::syscall::linux::MountAttr;
@@ -515,4 +578,53 @@ mod tests {
             mount_option_config
         );
     }
+
+    fn mount(destination: &str) -> Mount {
+        MountBuilder::default()
+            .destination(PathBuf::from(destination))
+            .typ("tmpfs")
+            .source(PathBuf::from("tmpfs"))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sort_mounts() -> anyhow::Result<()> {
+        let mounts = vec![
+            mount("/var/lib/x"),
+            mount("/tmp"),
+            mount("/var/lib"),
+            mount("/var/lib/x/./y/../z"),
+            mount("var"),
+            mount("/var/lib"),
+        ];
+
+        let sorted: Vec<_> = sort_mounts(&mounts)
+            .into_iter()
+            .map(|m| m.destination().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "/tmp",
+                "var",
+                "/var/lib",
+                "/var/lib",
+                "/var/lib/x",
+                "/var/lib/x/./y/../z"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_mounts_parent_of_root() {
+        // "/../etc" is "/etc", which is the parent of "/etc/ssl"
+        let mounts = vec![mount("/etc/ssl"), mount("/../../etc"), mount("/proc/..")];
+        let sorted: Vec<_> = sort_mounts(&mounts)
+            .into_iter()
+            .map(|m| m.destination().to_str().unwrap())
+            .collect();
+        assert_eq!(sorted, vec!["/proc/..", "/../../etc", "/etc/ssl"]);
+    }
 }
@@ -287,11 +287,19 @@ fn write_id_mapping(
                 })
                 .collect();
 
-            Command::new(map_binary.unwrap())
+            let output = Command::new(map_binary.context("no binary to write the id mapping")?)
                 .arg(pid.to_string())
                 .args(args)
                 .output()
                 .with_context(|| format!("failed to execute {:?}", map_binary))?;
+            if !output.status.success() {
+                bail!(
+                    "{:?} failed with {}: {}",
+                    map_binary,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
         }
     }
 
@@ -0,0 +1,292 @@
+//! Scheduling policy of the container process (`process.scheduler`).
+//!
+//! The runtime spec version supported by oci-spec-rs does not cover the scheduler
+//! block yet, so it is read directly from the raw json of the configuration.
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::utils;
+
+/// Scheduling policies as defined by sched(7).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Policy {
+    #[serde(rename = "SCHED_OTHER")]
+    Other,
+    #[serde(rename = "SCHED_FIFO")]
+    Fifo,
+    #[serde(rename = "SCHED_RR")]
+    RoundRobin,
+    #[serde(rename = "SCHED_BATCH")]
+    Batch,
+    #[serde(rename = "SCHED_ISO")]
+    Iso,
+    #[serde(rename = "SCHED_IDLE")]
+    Idle,
+    #[serde(rename = "SCHED_DEADLINE")]
+    Deadline,
+}
+
+impl Policy {
+    fn as_raw(self) -> u32 {
+        match self {
+            Policy::Other => 0,
+            Policy::Fifo => 1,
+            Policy::RoundRobin => 2,
+            Policy::Batch => 3,
+            Policy::Iso => 4,
+            Policy::Idle => 5,
+            Policy::Deadline => 6,
+        }
+    }
+
+    fn is_realtime(self) -> bool {
+        matches!(self, Policy::Fifo | Policy::RoundRobin)
+    }
+}
+
+/// Flags which can be passed to sched_setattr(2).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Flag {
+    #[serde(rename = "SCHED_FLAG_RESET_ON_FORK")]
+    ResetOnFork,
+    #[serde(rename = "SCHED_FLAG_RECLAIM")]
+    Reclaim,
+    #[serde(rename = "SCHED_FLAG_DL_OVERRUN")]
+    DlOverrun,
+    #[serde(rename = "SCHED_FLAG_KEEP_POLICY")]
+    KeepPolicy,
+    #[serde(rename = "SCHED_FLAG_KEEP_PARAMS")]
+    KeepParams,
+    #[serde(rename = "SCHED_FLAG_UTIL_CLAMP_MIN")]
+    UtilClampMin,
+    #[serde(rename = "SCHED_FLAG_UTIL_CLAMP_MAX")]
+    UtilClampMax,
+}
+
+impl Flag {
+    fn as_raw(self) -> u64 {
+        match self {
+            Flag::ResetOnFork => 0x01,
+            Flag::Reclaim => 0x02,
+            Flag::DlOverrun => 0x04,
+            Flag::KeepPolicy => 0x08,
+            Flag::KeepParams => 0x10,
+            Flag::UtilClampMin => 0x20,
+            Flag::UtilClampMax => 0x40,
+        }
+    }
+}
+
+/// Scheduler configuration of the container process.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Scheduler {
+    pub policy: Option<Policy>,
+    pub nice: Option<i32>,
+    pub priority: Option<i32>,
+    pub flags: Option<Vec<Flag>>,
+    pub runtime: Option<u64>,
+    pub deadline: Option<u64>,
+    pub period: Option<u64>,
+}
+
+/// Mirrors `struct sched_attr` of the kernel uapi.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SchedAttr {
+    size: u32,
+    sched_policy: u32,
+    sched_flags: u64,
+    sched_nice: i32,
+    sched_priority: u32,
+    sched_runtime: u64,
+    sched_deadline: u64,
+    sched_period: u64,
+}
+
+impl Scheduler {
+    /// Reads the scheduler of the process from the config.json of a bundle.
+    pub fn from_config<P: AsRef<Path>>(config: P) -> Result<Option<Self>> {
+        utils::read_json_pointer(config.as_ref(), "/process/scheduler")
+    }
+
+    /// Reads the scheduler from a process.json, as passed to exec.
+    pub fn from_process<P: AsRef<Path>>(process: P) -> Result<Option<Self>> {
+        utils::read_json_pointer(process.as_ref(), "/scheduler")
+    }
+
+    /// Validates the scheduler configuration and maps it onto the attributes
+    /// understood by the kernel.
+    fn to_sched_attr(&self) -> Result<SchedAttr> {
+        let policy = self.policy.context("scheduler policy is required")?;
+        let nice = self.nice.unwrap_or_default();
+        let priority = self.priority.unwrap_or_default();
+
+        if !(-20..=19).contains(&nice) {
+            bail!(
+                "invalid scheduler nice value {}, must be within -20..=19",
+                nice
+            );
+        }
+
+        if policy.is_realtime() {
+            if !(1..=99).contains(&priority) {
+                bail!(
+                    "invalid scheduler priority {} for {:?}, must be within 1..=99",
+                    priority,
+                    policy
+                );
+            }
+        } else if priority != 0 {
+            bail!(
+                "scheduler priority can only be specified for SCHED_FIFO or SCHED_RR, got {:?}",
+                policy
+            );
+        }
+
+        let deadline_fields = [self.runtime, self.deadline, self.period];
+        if policy != Policy::Deadline && deadline_fields.iter().any(Option::is_some) {
+            bail!(
+                "scheduler runtime, deadline and period can only be specified for SCHED_DEADLINE, got {:?}",
+                policy
+            );
+        }
+
+        Ok(SchedAttr {
+            size: std::mem::size_of::<SchedAttr>() as u32,
+            sched_policy: policy.as_raw(),
+            sched_flags: self
+                .flags
+                .iter()
+                .flatten()
+                .fold(0, |flags, flag| flags | flag.as_raw()),
+            sched_nice: nice,
+            sched_priority: priority as u32,
+            sched_runtime: self.runtime.unwrap_or_default(),
+            sched_deadline: self.deadline.unwrap_or_default(),
+            sched_period: self.period.unwrap_or_default(),
+        })
+    }
+}
+
+/// Applies the scheduler configuration to the calling process.
+pub fn set_scheduler(scheduler: &Scheduler) -> Result<()> {
+    let attr = scheduler.to_sched_attr()?;
+
+    // sched_setscheduler is sufficient for a plain policy and priority, anything
+    // else (nice value, flags or deadline parameters) needs sched_setattr
+    let res = if attr.sched_policy != Policy::Deadline.as_raw()
+        && attr.sched_flags == 0
+        && attr.sched_nice == 0
+    {
+        let param = libc::sched_param {
+            sched_priority: attr.sched_priority as i32,
+        };
+        unsafe { libc::sched_setscheduler(0, attr.sched_policy as i32, &param) as libc::c_long }
+    } else {
+        unsafe { libc::syscall(libc::SYS_sched_setattr, 0, &attr as *const SchedAttr, 0) }
+    };
+
+    if res != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("failed to set scheduler {:?}", scheduler));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_fifo_with_priority() -> Result<()> {
+        let scheduler = Scheduler {
+            policy: Some(Policy::Fifo),
+            priority: Some(50),
+            ..Default::default()
+        };
+
+        let attr = scheduler.to_sched_attr()?;
+        assert_eq!(attr.sched_policy, 1);
+        assert_eq!(attr.sched_priority, 50);
+        assert_eq!(attr.sched_flags, 0);
+        assert_eq!(attr.size, 48);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fifo_priority_out_of_range() {
+        let scheduler = Scheduler {
+            policy: Some(Policy::Fifo),
+            priority: Some(100),
+            ..Default::default()
+        };
+        assert!(scheduler.to_sched_attr().is_err());
+    }
+
+    #[test]
+    fn test_priority_without_realtime_policy() {
+        let scheduler = Scheduler {
+            policy: Some(Policy::Other),
+            priority: Some(10),
+            ..Default::default()
+        };
+        assert!(scheduler.to_sched_attr().is_err());
+    }
+
+    #[test]
+    fn test_deadline_fields_require_deadline_policy() -> Result<()> {
+        let scheduler = Scheduler {
+            policy: Some(Policy::Fifo),
+            priority: Some(10),
+            runtime: Some(10_000_000),
+            ..Default::default()
+        };
+        assert!(scheduler.to_sched_attr().is_err());
+
+        let scheduler = Scheduler {
+            policy: Some(Policy::Deadline),
+            runtime: Some(10_000_000),
+            deadline: Some(20_000_000),
+            period: Some(30_000_000),
+            flags: Some(vec![Flag::ResetOnFork, Flag::DlOverrun]),
+            ..Default::default()
+        };
+        let attr = scheduler.to_sched_attr()?;
+        assert_eq!(attr.sched_policy, 6);
+        assert_eq!(attr.sched_flags, 0x05);
+        assert_eq!(attr.sched_runtime, 10_000_000);
+        assert_eq!(attr.sched_deadline, 20_000_000);
+        assert_eq!(attr.sched_period, 30_000_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_config() -> Result<()> {
+        let tmp = create_temp_dir("test_scheduler_from_config")?;
+        let config = tmp.path().join("config.json");
+        fs::write(
+            &config,
+            r#"{"process": {"scheduler": {"policy": "SCHED_RR", "priority": 5}}}"#,
+        )?;
+        let scheduler = Scheduler::from_config(&config)?;
+        assert_eq!(
+            scheduler,
+            Some(Scheduler {
+                policy: Some(Policy::RoundRobin),
+                priority: Some(5),
+                ..Default::default()
+            })
+        );
+
+        fs::write(&config, r#"{"process": {}}"#)?;
+        assert_eq!(Scheduler::from_config(&config)?, None);
+
+        Ok(())
+    }
+}
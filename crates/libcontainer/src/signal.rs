@@ -5,7 +5,7 @@ use nix::sys::signal::Signal as NixSignal;
 use std::convert::TryFrom;
 
 /// POSIX Signal
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Signal(NixSignal);
 
 impl TryFrom<&str> for Signal {
@@ -68,7 +68,8 @@ impl From<NixSignal> for Signal {
 }
 
 impl Signal {
-    pub(crate) fn into_raw(self) -> NixSignal {
+    /// Returns the signal as used by nix
+    pub fn into_raw(self) -> NixSignal {
         self.0
     }
 }
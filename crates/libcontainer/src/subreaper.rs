@@ -0,0 +1,240 @@
+//! Child subreaper
+//!
+//! Orphaned processes are re-parented to their nearest ancestor which is a
+//! child subreaper, instead of to the init of their pid namespace. When youki
+//! supervises a container, it becomes a subreaper, so that descendants of the
+//! container which outlive their parent are reaped by youki rather than being
+//! left as zombies on the host. Signals sent to youki in the meantime are
+//! forwarded to the container process, except for SIGKILL and SIGSTOP, which
+//! can not be caught and still act on youki itself.
+use anyhow::{bail, Context, Result};
+use nix::{
+    errno::Errno,
+    sys::{
+        signal::{self, SigSet, SigmaskHow, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+/// Makes the calling process a child subreaper. This needs to be done before
+/// the container is created, so that the container process is re-parented to
+/// the calling process once the intermediate process exits.
+pub fn set_subreaper() -> Result<()> {
+    prctl::set_child_subreaper(true)
+        .map_err(Errno::from_i32)
+        .context("failed to become child subreaper")
+}
+
+/// Blocks the signals to forward, together with SIGCHLD, so that they stay
+/// pending until [`reap_until`] relays them instead of acting on the calling
+/// process. This needs to be done before the container is created, otherwise
+/// a signal which arrives before the container is waited for is lost or
+/// terminates the calling process. The container process does not inherit the
+/// blocked signals. Returns the signal mask as it was before.
+pub fn block_signals(forward: &[Signal]) -> Result<SigSet> {
+    let signals = signal_set(forward)?;
+    let mut old_mask = SigSet::empty();
+    signal::sigprocmask(SigmaskHow::SIG_BLOCK, Some(&signals), Some(&mut old_mask))
+        .context("failed to block signals")?;
+    Ok(old_mask)
+}
+
+/// Blocks until the process has exited and returns its exit code, a
+/// termination by signal is reported as 128 + signal number. Every other child
+/// which exits in the meantime, e.g. a re-parented descendant of the container,
+/// is reaped as well. The signals to forward are relayed to the process instead
+/// of acting on the calling process.
+pub fn reap_until(pid: Pid, forward: &[Signal]) -> Result<i32> {
+    let old_mask = block_signals(forward)?;
+    let result = forward_until_exit(pid, &signal_set(forward)?);
+    signal::sigprocmask(SigmaskHow::SIG_SETMASK, Some(&old_mask), None)
+        .context("failed to restore signal mask")?;
+    result
+}
+
+fn signal_set(forward: &[Signal]) -> Result<SigSet> {
+    let mut signals = SigSet::empty();
+    for &signal in forward {
+        if matches!(signal, Signal::SIGKILL | Signal::SIGSTOP | Signal::SIGCHLD) {
+            bail!("{} can not be forwarded", signal);
+        }
+        signals.add(signal);
+    }
+    signals.add(Signal::SIGCHLD);
+    Ok(signals)
+}
+
+fn forward_until_exit(pid: Pid, signals: &SigSet) -> Result<i32> {
+    loop {
+        if let Some(code) = reap_exited(pid)? {
+            return Ok(code);
+        }
+
+        let signal = signals.wait().context("failed to wait for signals")?;
+        if signal == Signal::SIGCHLD {
+            continue;
+        }
+
+        log::debug!("forward {} to {}", signal, pid);
+        if let Err(err) = signal::kill(pid, signal) {
+            // the process may already be gone, its exit is handled on the next reap
+            log::debug!("failed to forward {} to {}: {}", signal, pid, err);
+        }
+    }
+}
+
+// Reaps the children which have exited so far, without waiting for the ones
+// still running, and returns the exit code of the process once it is reaped.
+fn reap_exited(pid: Pid) -> Result<Option<i32>> {
+    let mut exit_code = None;
+    loop {
+        match waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::StillAlive) => return Ok(exit_code),
+            Err(Errno::ECHILD) if exit_code.is_none() => {
+                bail!("{} is not a child of this process", pid)
+            }
+            Err(Errno::ECHILD) => return Ok(exit_code),
+            Ok(WaitStatus::Exited(p, code)) if p == pid => exit_code = Some(code),
+            Ok(WaitStatus::Signaled(p, signal, _)) if p == pid => {
+                exit_code = Some(128 + signal as i32)
+            }
+            Ok(status) => log::debug!("reaped re-parented process: {:?}", status),
+            Err(Errno::EINTR) => continue,
+            Err(err) => return Err(err).context("failed to reap child processes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils;
+    use anyhow::bail;
+    use nix::unistd::{self, ForkResult};
+    use std::{path::Path, thread, time::Duration};
+
+    #[test]
+    fn test_reap_grandchild_outliving_its_parent() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            set_subreaper()?;
+            let (read_end, write_end) = unistd::pipe()?;
+
+            let init = match unsafe { unistd::fork()? } {
+                ForkResult::Child => {
+                    // a process of the container forks a grandchild and exits
+                    // before it, the grandchild is re-parented to the subreaper
+                    match unsafe { unistd::fork()? } {
+                        ForkResult::Child => {
+                            if let ForkResult::Child = unsafe { unistd::fork()? } {
+                                let pid = unistd::getpid().as_raw().to_string();
+                                unistd::write(write_end, pid.as_bytes())?;
+                                thread::sleep(Duration::from_millis(50));
+                                std::process::exit(0);
+                            }
+                            std::process::exit(0);
+                        }
+                        ForkResult::Parent { child } => {
+                            waitpid(child, None)?;
+                        }
+                    }
+                    thread::sleep(Duration::from_millis(200));
+                    std::process::exit(3);
+                }
+                ForkResult::Parent { child } => child,
+            };
+            unistd::close(write_end)?;
+
+            let mut buf = [0u8; 16];
+            let len = unistd::read(read_end, &mut buf)?;
+            let grandchild: i32 = std::str::from_utf8(&buf[..len])?.parse()?;
+
+            let code = reap_until(init, &[])?;
+            if code != 3 {
+                bail!("expected exit code 3, got {}", code);
+            }
+
+            // the grandchild was reaped, it is neither a child anymore nor a
+            // zombie anywhere else
+            match waitpid(Pid::from_raw(grandchild), Some(WaitPidFlag::WNOHANG)) {
+                Err(Errno::ECHILD) => {}
+                status => bail!("expected grandchild to be reaped, got {:?}", status),
+            }
+            if Path::new(&format!("/proc/{}", grandchild)).exists() {
+                bail!("grandchild {} has not been reaped", grandchild);
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_reap_until_signaled() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            let pid = match unsafe { unistd::fork()? } {
+                ForkResult::Child => loop {
+                    unistd::pause();
+                },
+                ForkResult::Parent { child } => child,
+            };
+            signal::kill(pid, Signal::SIGKILL)?;
+            let code = reap_until(pid, &[])?;
+            if code != 137 {
+                bail!("expected exit code 137, got {}", code);
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_reap_until_forwards_signal() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            // SIGTERM is blocked before the container is started, so that it
+            // is pending for the supervisor instead of terminating it
+            let old_mask = block_signals(&[Signal::SIGTERM, Signal::SIGINT])?;
+
+            let pid = match unsafe { unistd::fork()? } {
+                ForkResult::Child => {
+                    old_mask.thread_set_mask()?;
+                    signal::kill(unistd::getppid(), Signal::SIGTERM)?;
+                    loop {
+                        unistd::pause();
+                    }
+                }
+                ForkResult::Parent { child } => child,
+            };
+
+            let code = reap_until(pid, &[Signal::SIGTERM, Signal::SIGINT])?;
+            if code != 128 + Signal::SIGTERM as i32 {
+                bail!(
+                    "expected the container to be terminated by SIGTERM, got {}",
+                    code
+                );
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_block_signals() -> Result<()> {
+        test_utils::test_in_child_process(|| {
+            block_signals(&[Signal::SIGTERM])?;
+            // the signal stays pending instead of terminating the process
+            signal::kill(unistd::getpid(), Signal::SIGTERM)?;
+            let mut term = SigSet::empty();
+            term.add(Signal::SIGTERM);
+            let signal = term.wait()?;
+            if signal != Signal::SIGTERM {
+                bail!("expected SIGTERM to be pending, got {}", signal);
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_reap_until_rejects_uncatchable_signals() {
+        for signal in [Signal::SIGKILL, Signal::SIGSTOP] {
+            assert!(block_signals(&[signal]).is_err());
+            assert!(reap_until(unistd::getpid(), &[signal]).is_err());
+        }
+    }
+}
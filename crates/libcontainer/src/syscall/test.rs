@@ -62,6 +62,7 @@ pub enum ArgName {
     Domainname,
     Groups,
     Capability,
+    Id,
 }
 
 impl ArgName {
@@ -77,6 +78,7 @@ impl ArgName {
             ArgName::Domainname,
             ArgName::Groups,
             ArgName::Capability,
+            ArgName::Id,
         ]
         .iter()
         .copied()
@@ -147,8 +149,8 @@ impl Syscall for TestHelperSyscall {
             .act(ArgName::Namespace, Box::new((rawfd, nstype)))
     }
 
-    fn set_id(&self, _uid: Uid, _gid: Gid) -> anyhow::Result<()> {
-        unimplemented!()
+    fn set_id(&self, uid: Uid, gid: Gid) -> anyhow::Result<()> {
+        self.mocks.act(ArgName::Id, Box::new((uid, gid)))
     }
 
     fn unshare(&self, flags: CloneFlags) -> anyhow::Result<()> {
@@ -342,6 +344,15 @@ impl TestHelperSyscall {
             .collect::<Vec<String>>()
     }
 
+    pub fn get_set_id_args(&self) -> Vec<(Uid, Gid)> {
+        self.mocks
+            .fetch(ArgName::Id)
+            .values
+            .iter()
+            .map(|x| *x.downcast_ref::<(Uid, Gid)>().unwrap())
+            .collect::<Vec<(Uid, Gid)>>()
+    }
+
     pub fn get_groups_args(&self) -> Vec<Vec<Gid>> {
         self.mocks
             .fetch(ArgName::Groups)
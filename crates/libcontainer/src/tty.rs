@@ -4,7 +4,7 @@ use std::io::IoSlice;
 use std::os::unix::fs::symlink;
 use std::os::unix::io::AsRawFd;
 use std::os::unix::prelude::RawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use anyhow::{bail, Result};
@@ -12,6 +12,8 @@ use nix::errno::Errno;
 use nix::sys::socket::{self, UnixAddr};
 use nix::unistd::close;
 use nix::unistd::dup2;
+use nix::unistd::ttyname;
+use oci_spec::runtime::Box as ConsoleSize;
 
 const STDIN: i32 = 0;
 const STDOUT: i32 = 1;
@@ -44,7 +46,10 @@ pub fn setup_console_socket(
     Ok(csocketfd)
 }
 
-pub fn setup_console(console_fd: &RawFd) -> Result<()> {
+/// Creates the pseudo terminal of the container process and sends its master to
+/// the console socket. Returns the path of the slave, which is bind mounted to
+/// /dev/console of the container.
+pub fn setup_console(console_fd: &RawFd, console_size: Option<ConsoleSize>) -> Result<PathBuf> {
     // You can also access pty master, but it is better to use the API.
     // ref. https://github.com/containerd/containerd/blob/261c107ffc4ff681bc73988f64e3f60c32233b37/vendor/github.com/containerd/go-runc/console.go#L139-L154
     let openpty_result =
@@ -66,8 +71,53 @@ pub fn setup_console(console_fd: &RawFd) -> Result<()> {
         log::warn!("could not TIOCSCTTY");
     };
     let slave = openpty_result.slave;
+    let slave_path = ttyname(slave).context("could not get name of pty slave")?;
+    if let Some(console_size) = console_size {
+        set_console_size(slave, console_size).context("could not set console size")?;
+    }
     connect_stdio(&slave, &slave, &slave).context("could not dup tty to stderr")?;
     close(console_fd.as_raw_fd()).context("could not close console socket")?;
+    Ok(slave_path)
+}
+
+/// Sends stdin, stdout and stderr of the calling process, in this order, over
+/// the stdio socket. These are the stdio the container process keeps over
+/// exec, so the receiver gets hold of the actual stdio of the container.
+pub fn setup_stdio_socket(stdio_fd: &RawFd) -> Result<()> {
+    let fds = [STDIN, STDOUT, STDERR];
+    let name: &[u8] = b"stdio";
+    let iov = [IoSlice::new(name)];
+    let cmsg = socket::ControlMessage::ScmRights(&fds);
+    socket::sendmsg::<UnixAddr>(
+        stdio_fd.as_raw_fd(),
+        &iov,
+        &[cmsg],
+        socket::MsgFlags::empty(),
+        None,
+    )
+    .context("failed to send stdio fds")?;
+
+    close(stdio_fd.as_raw_fd()).context("could not close stdio socket")?;
+    Ok(())
+}
+
+// Sets the window size of the terminal, a zero height or width keeps the
+// default size of the terminal.
+fn set_console_size(fd: RawFd, console_size: ConsoleSize) -> Result<()> {
+    if console_size.height() == 0 || console_size.width() == 0 {
+        return Ok(());
+    }
+
+    let winsize = libc::winsize {
+        ws_row: u16::try_from(console_size.height()).context("console height is too large")?,
+        ws_col: u16::try_from(console_size.width()).context("console width is too large")?,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &winsize) } < 0 {
+        bail!("could not TIOCSWINSZ: {}", std::io::Error::last_os_error());
+    }
+
     Ok(())
 }
 
@@ -86,12 +136,16 @@ mod tests {
 
     use std::env;
     use std::fs::{self, File};
+    use std::io::IoSliceMut;
     use std::os::unix::net::UnixListener;
     use std::path::PathBuf;
 
+    use nix::sys::stat::fstat;
+    use nix::unistd::pipe;
+    use oci_spec::runtime::BoxBuilder;
     use serial_test::serial;
 
-    use crate::utils::{create_temp_dir, TempDir};
+    use crate::utils::{create_temp_dir, test_utils, TempDir};
 
     const CONSOLE_SOCKET: &str = "console-socket";
 
@@ -150,7 +204,117 @@ mod tests {
         let lis = UnixListener::bind(Path::join(&testdir, "console-socket"));
         assert!(lis.is_ok());
         let fd = setup_console_socket(&rundir_path, &socket_path, CONSOLE_SOCKET);
-        let status = setup_console(&fd.unwrap());
+        let status = setup_console(&fd.unwrap(), None);
         assert!(status.is_ok());
     }
+
+    fn get_console_size(fd: RawFd) -> Result<libc::winsize> {
+        let mut winsize = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut winsize) } < 0 {
+            bail!("could not TIOCGWINSZ");
+        }
+        Ok(winsize)
+    }
+
+    #[test]
+    fn test_set_console_size() -> Result<()> {
+        let pty = nix::pty::openpty(None, None)?;
+        let console_size = BoxBuilder::default().height(40u64).width(120u64).build()?;
+        set_console_size(pty.slave, console_size)?;
+
+        let winsize = get_console_size(pty.slave)?;
+        assert_eq!(winsize.ws_row, 40);
+        assert_eq!(winsize.ws_col, 120);
+
+        close(pty.master)?;
+        close(pty.slave)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_console_size_zero() -> Result<()> {
+        let pty = nix::pty::openpty(None, None)?;
+        let before = get_console_size(pty.slave)?;
+        set_console_size(pty.slave, ConsoleSize::default())?;
+
+        let after = get_console_size(pty.slave)?;
+        assert_eq!(before.ws_row, after.ws_row);
+        assert_eq!(before.ws_col, after.ws_col);
+
+        close(pty.master)?;
+        close(pty.slave)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_stdio_socket() -> Result<()> {
+        let (receiver, sender) = socket::socketpair(
+            socket::AddressFamily::Unix,
+            socket::SockType::Stream,
+            None,
+            socket::SockFlag::SOCK_CLOEXEC,
+        )?;
+
+        let (stdout_read, stdout_write) = pipe()?;
+        test_utils::test_in_child_process(|| {
+            dup2(stdout_write, STDOUT)?;
+            setup_stdio_socket(&sender)?;
+            nix::unistd::write(STDOUT, b"hello")?;
+            Ok(())
+        })?;
+        close(sender)?;
+
+        let mut buf = [0u8; 5];
+        let mut iov = [IoSliceMut::new(&mut buf)];
+        let mut cmsgspace = nix::cmsg_space!([RawFd; 3]);
+        let msg = socket::recvmsg::<UnixAddr>(
+            receiver,
+            &mut iov,
+            Some(&mut cmsgspace),
+            socket::MsgFlags::MSG_CMSG_CLOEXEC,
+        )?;
+        let fds: Vec<RawFd> = msg
+            .cmsgs()
+            .find_map(|cmsg| match cmsg {
+                socket::ControlMessageOwned::ScmRights(fds) => Some(fds),
+                _ => None,
+            })
+            .context("no fds received")?;
+        assert_eq!(fds.len(), 3);
+        for fd in &fds {
+            fstat(*fd)?;
+        }
+
+        // the second fd is the stdout of the child process itself
+        let stdout = fstat(fds[1])?;
+        let pipe_stat = fstat(stdout_write)?;
+        assert_eq!(
+            (stdout.st_dev, stdout.st_ino),
+            (pipe_stat.st_dev, pipe_stat.st_ino)
+        );
+        nix::unistd::write(fds[1], b" back")?;
+        close(stdout_write)?;
+        let mut out = [0u8; 10];
+        let mut read = 0;
+        while read < out.len() {
+            let len = nix::unistd::read(stdout_read, &mut out[read..])?;
+            if len == 0 {
+                break;
+            }
+            read += len;
+        }
+        assert_eq!(&out[..read], b"hello back");
+
+        for fd in fds {
+            close(fd)?;
+        }
+        close(stdout_read)?;
+        close(receiver)?;
+        Ok(())
+    }
 }
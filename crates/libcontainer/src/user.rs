@@ -0,0 +1,165 @@
+//! Resolution of the user of the container process by name
+use anyhow::{bail, Context, Result};
+use oci_spec::runtime::User;
+use std::{fs, io::ErrorKind, path::Path};
+
+const PASSWD: &str = "etc/passwd";
+const GROUP: &str = "etc/group";
+
+/// Resolves the username of the user, if there is one, to a uid and gid by
+/// looking it up in /etc/passwd of the container. The username can also name
+/// a group as `user:group`, which is looked up in /etc/group of the container
+/// and replaces the primary group of the user. The resolved ids take the place
+/// of the uid and gid of the user. This has to be called once the process has
+/// entered the rootfs of the container.
+pub fn resolve(user: &User) -> Result<User> {
+    resolve_in(user, Path::new("/"))
+}
+
+fn resolve_in(user: &User, root: &Path) -> Result<User> {
+    let username = match user.username() {
+        Some(username) if !username.is_empty() => username,
+        _ => return Ok(user.clone()),
+    };
+
+    let (name, group) = match username.split_once(':') {
+        Some((name, group)) => (name, Some(group)),
+        None => (username.as_str(), None),
+    };
+
+    let passwd = root.join(PASSWD);
+    let (uid, mut gid) = match find_entry(&passwd, name)? {
+        Some(entry) => (parse_id(&entry, 2, &passwd)?, parse_id(&entry, 3, &passwd)?),
+        None => match name.parse() {
+            Ok(uid) => (uid, user.gid()),
+            Err(_) => bail!(
+                "user {} does not exist in /etc/passwd of the container",
+                name
+            ),
+        },
+    };
+
+    if let Some(group) = group {
+        let group_file = root.join(GROUP);
+        gid = match find_entry(&group_file, group)? {
+            Some(entry) => parse_id(&entry, 2, &group_file)?,
+            None => match group.parse() {
+                Ok(gid) => gid,
+                Err(_) => bail!(
+                    "group {} of user {} does not exist in /etc/group of the container",
+                    group,
+                    name
+                ),
+            },
+        };
+    }
+
+    log::debug!("resolved user {} to uid {} and gid {}", username, uid, gid);
+    let mut resolved = user.clone();
+    resolved.set_uid(uid).set_gid(gid);
+    Ok(resolved)
+}
+
+// Looks up the entry with the name in a file with the format of /etc/passwd
+// or /etc/group. A file which does not exist has no entries.
+fn find_entry(path: &Path, name: &str) -> Result<Option<Vec<String>>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {:?}", path)),
+    };
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split(':').map(str::to_owned).collect::<Vec<_>>())
+        .find(|fields| fields.first().map(String::as_str) == Some(name)))
+}
+
+fn parse_id(entry: &[String], field: usize, path: &Path) -> Result<u32> {
+    let id = entry.get(field).map(String::as_str).unwrap_or_default();
+    id.parse()
+        .with_context(|| format!("invalid id {:?} of {} in {:?}", id, entry[0], path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::create_temp_dir;
+    use oci_spec::runtime::UserBuilder;
+
+    fn create_rootfs(name: &str) -> Result<crate::utils::TempDir> {
+        let tmp = create_temp_dir(name)?;
+        fs::create_dir_all(tmp.join("etc"))?;
+        fs::write(
+            tmp.join(PASSWD),
+            "# users of the container\n\
+             root:x:0:0:root:/root:/bin/sh\n\
+             www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin\n\
+             app:x:1000:1001:app:/home/app:/bin/sh\n",
+        )?;
+        fs::write(
+            tmp.join(GROUP),
+            "root:x:0:\nwww-data:x:33:\napp:x:1001:\ndocker:x:999:app\n",
+        )?;
+        Ok(tmp)
+    }
+
+    #[test]
+    fn test_resolve_username() -> Result<()> {
+        let rootfs = create_rootfs("test_resolve_username")?;
+        let user = UserBuilder::default()
+            .username("app")
+            .additional_gids(vec![5])
+            .umask(0o022u32)
+            .build()?;
+
+        let resolved = resolve_in(&user, &rootfs)?;
+        assert_eq!(resolved.uid(), 1000);
+        assert_eq!(resolved.gid(), 1001);
+        assert_eq!(resolved.additional_gids(), &Some(vec![5]));
+        assert_eq!(resolved.umask(), Some(0o022));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_username_with_group() -> Result<()> {
+        let rootfs = create_rootfs("test_resolve_username_with_group")?;
+        for (username, uid, gid) in [
+            ("www-data:docker", 33, 999),
+            ("app:33", 1000, 33),
+            ("2000:app", 2000, 1001),
+        ] {
+            let user = UserBuilder::default().username(username).build()?;
+            let resolved = resolve_in(&user, &rootfs)?;
+            assert_eq!((resolved.uid(), resolved.gid()), (uid, gid), "{}", username);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_without_username() -> Result<()> {
+        let user = UserBuilder::default().uid(33u32).gid(34u32).build()?;
+        assert_eq!(resolve_in(&user, Path::new("/does/not/exist"))?, user);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_unknown_username() -> Result<()> {
+        let rootfs = create_rootfs("test_resolve_unknown_username")?;
+        let user = UserBuilder::default().username("nobody").build()?;
+        let err = resolve_in(&user, &rootfs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "user nobody does not exist in /etc/passwd of the container"
+        );
+
+        let user = UserBuilder::default().username("app:staff").build()?;
+        let err = resolve_in(&user, &rootfs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "group staff of user app does not exist in /etc/group of the container"
+        );
+        Ok(())
+    }
+}
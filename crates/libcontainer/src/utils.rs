@@ -6,6 +6,7 @@ use nix::sys::stat::Mode;
 use nix::sys::statfs;
 use nix::unistd;
 use nix::unistd::{Uid, User};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::fs::{self, DirBuilder, File};
@@ -203,6 +204,22 @@ pub fn create_dir_all_with_mode<P: AsRef<Path>>(path: P, owner: u32, mode: Mode)
     }
 }
 
+/// Reads the value at the json `pointer` (RFC 6901) of the file at `path`. This is
+/// used for fields of the runtime spec which are not covered by oci-spec-rs yet.
+pub fn read_json_pointer<T: DeserializeOwned>(path: &Path, pointer: &str) -> Result<Option<T>> {
+    let file = fs::File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let value: serde_json::Value =
+        serde_json::from_reader(file).with_context(|| format!("failed to parse {:?}", path))?;
+    match value.pointer(pointer) {
+        Some(value) => {
+            let value = serde_json::from_value(value.clone())
+                .with_context(|| format!("invalid value of {} in {:?}", pointer, path))?;
+            Ok(Some(value))
+        }
+        None => Ok(None),
+    }
+}
+
 // Make sure a given path is on procfs. This is to avoid the security risk that
 // /proc path is mounted over. Ref: CVE-2019-16884
 pub fn ensure_procfs(path: &Path) -> Result<()> {
@@ -331,13 +348,17 @@ pub fn get_temp_dir_path(test_name: &str) -> PathBuf {
     std::env::temp_dir().join(test_name)
 }
 
+/// Resolves the executable `name` against the `PATH=` entry `path_var`. Names
+/// containing a `/` are taken as they are and bypass the lookup.
 pub fn get_executable_path(name: &str, path_var: &str) -> Option<PathBuf> {
-    let paths = path_var.trim_start_matches("PATH=");
     // if path has / in it, we have to assume absolute path, as per runc impl
-    if name.contains('/') && PathBuf::from(name).exists() {
-        return Some(PathBuf::from(name));
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return if path.exists() { Some(path) } else { None };
     }
-    for path in paths.split(':') {
+
+    let paths = path_var.trim_start_matches("PATH=");
+    for path in paths.split(':').filter(|p| !p.is_empty()) {
         let potential_path = PathBuf::from(path).join(name);
         if potential_path.exists() {
             return Some(potential_path);
@@ -9,6 +9,9 @@ pub struct Exec {
     /// Unix socket (file) path , which will receive file descriptor of the writing end of the pseudoterminal
     #[clap(long)]
     pub console_socket: Option<PathBuf>,
+    /// Unix socket (file) path, which will receive file descriptors of stdin, stdout and stderr of the container process when no pseudoterminal is used
+    #[clap(long)]
+    pub stdio_socket: Option<PathBuf>,
     #[clap(short, long)]
     pub tty: bool,
     #[clap(long)]
@@ -20,6 +23,9 @@ pub struct Exec {
     /// Environment variables that should be set in the container
     #[clap(short, long, value_parser = parse_key_val::<String, String>, number_of_values = 1)]
     pub env: Vec<(String, String)>,
+    /// Environment variables of the host that should be passed to the process in addition to the environment of the container, unless either of them sets them itself
+    #[clap(long, number_of_values = 1)]
+    pub keep_env: Vec<String>,
     /// Prevent the process from gaining additional privileges
     #[clap(long)]
     pub no_new_privs: bool,
@@ -29,6 +35,12 @@ pub struct Exec {
     /// Detach from the container process
     #[clap(short, long)]
     pub detach: bool,
+    /// Log the capability sets the process ends up with
+    #[clap(long)]
+    pub audit_capabilities: bool,
+    /// Run the process in a sub-cgroup of the container's cgroup, which is created if it does not exist. On cgroup v2, the container's cgroup needs controllers enabled for its children
+    #[clap(long)]
+    pub cgroup: Option<PathBuf>,
     /// Identifier of the container
     #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
     pub container_id: String,
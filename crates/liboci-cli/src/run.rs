@@ -14,9 +14,43 @@ pub struct Run {
     /// Unix socket (file) path , which will receive file descriptor of the writing end of the pseudoterminal
     #[clap(short, long)]
     pub console_socket: Option<PathBuf>,
+    /// Unix socket (file) path, which will receive file descriptors of stdin, stdout and stderr of the container process when no pseudoterminal is used
+    #[clap(long)]
+    pub stdio_socket: Option<PathBuf>,
     /// Pass N additional file descriptors to the container (stdio + $LISTEN_FDS + N in total)
     #[clap(long, default_value = "0")]
     pub preserve_fds: i32,
+    /// Run an init inside the container that forwards signals and reaps processes
+    #[clap(long)]
+    pub init: bool,
+    /// Fail if the pids limit of the container exceeds the pids available on the host
+    #[clap(long)]
+    pub strict_pids_limit: bool,
+    /// Log the capability sets the container process ends up with
+    #[clap(long)]
+    pub audit_capabilities: bool,
+    /// Seconds after which the creation of the container is aborted, 0 disables the timeout
+    #[clap(long, default_value = "120")]
+    pub create_timeout: u64,
+    /// Create a new session keyring for the container
+    #[clap(long)]
+    pub new_keyring: bool,
+    /// Relay the sd_notify readiness of the container to the socket in NOTIFY_SOCKET
+    #[clap(long)]
+    pub notify_relay: bool,
+    /// Cgroup of the container instead of the cgroups path in config.json, an existing absolute path is joined without setting its controllers
+    #[clap(long)]
+    pub cgroup_path: Option<PathBuf>,
+    /// Stay in the foreground as child subreaper, reaping orphaned descendants of the container, and exit with the exit code of the container
+    #[clap(long)]
+    pub subreaper: bool,
+    /// Signals which are forwarded to the container while staying in the foreground with --subreaper
+    #[clap(
+        long = "forward-signal",
+        value_delimiter = ',',
+        default_value = "HUP,INT,QUIT,TERM,USR1,USR2,WINCH"
+    )]
+    pub forward_signals: Vec<String>,
     /// name of the container instance to be started
     #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
     pub container_id: String,
@@ -1,6 +1,6 @@
 //! Handles the creation of a new container
 use anyhow::Result;
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf, time::Duration};
 
 use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall};
 use liboci_cli::Create;
@@ -10,15 +10,30 @@ use liboci_cli::Create;
 // can be given impression that is is running on a complete system, but on the system which
 // it is running, it is just another process, and has attributes such as pid, file descriptors, etc.
 // associated with it like any other process.
-pub fn create(args: Create, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
+pub fn create(
+    args: Create,
+    root_path: PathBuf,
+    systemd_cgroup: bool,
+    reexec_args: Vec<OsString>,
+) -> Result<()> {
     let syscall = create_syscall();
     ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
         .with_pid_file(args.pid_file.as_ref())?
         .with_console_socket(args.console_socket.as_ref())
+        .with_stdio_socket(args.stdio_socket.as_ref())
         .with_root_path(root_path)?
         .with_preserved_fds(args.preserve_fds)
+        .with_capability_audit(args.audit_capabilities)
+        .with_timeout((args.create_timeout > 0).then_some(Duration::from_secs(args.create_timeout)))
+        .with_reexec(Some(reexec_args))
         .as_init(&args.bundle)
+        .with_config(args.config.as_ref())
+        .with_cgroup_path(args.cgroup_path.as_ref())
+        .with_notify_socket(super::notify_socket(args.notify_relay)?)
         .with_systemd(systemd_cgroup)
+        .with_init(args.init)
+        .with_strict_pids_limit(args.strict_pids_limit)
+        .with_new_keyring(args.new_keyring)
         .build()?;
 
     Ok(())
@@ -1,22 +1,27 @@
 use anyhow::Result;
 use nix::sys::wait::{waitpid, WaitStatus};
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf};
 
 use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall};
 use liboci_cli::Exec;
 
-pub fn exec(args: Exec, root_path: PathBuf) -> Result<i32> {
+pub fn exec(args: Exec, root_path: PathBuf, reexec_args: Vec<OsString>) -> Result<i32> {
     let syscall = create_syscall();
     let pid = ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
         .with_root_path(root_path)?
         .with_console_socket(args.console_socket.as_ref())
+        .with_stdio_socket(args.stdio_socket.as_ref())
         .with_pid_file(args.pid_file.as_ref())?
+        .with_capability_audit(args.audit_capabilities)
+        .with_reexec(Some(reexec_args))
         .as_tenant()
         .with_detach(args.detach)
         .with_cwd(args.cwd.as_ref())
         .with_env(args.env.clone().into_iter().collect())
+        .with_keep_env(args.keep_env.clone())
         .with_process(args.process.as_ref())
         .with_no_new_privs(args.no_new_privs)
+        .with_cgroup(args.cgroup.as_ref())
         .with_container_args(args.command.clone())
         .build()?;
 
@@ -28,9 +33,15 @@ pub fn exec(args: Exec, root_path: PathBuf) -> Result<i32> {
         return Ok(0);
     }
 
-    match waitpid(pid, None)? {
-        WaitStatus::Exited(_, status) => Ok(status),
-        WaitStatus::Signaled(_, sig, _) => Ok(sig as i32),
-        _ => Ok(0),
+    Ok(exit_code(waitpid(pid, None)?))
+}
+
+// The intermediate process takes over the exit code of the exec'd process,
+// which is the exit code of exec.
+pub(crate) fn exit_code(status: WaitStatus) -> i32 {
+    match status {
+        WaitStatus::Exited(_, status) => status,
+        WaitStatus::Signaled(_, sig, _) => sig as i32,
+        _ => 0,
     }
 }
@@ -0,0 +1,91 @@
+//! Contains functionality of the gc command, which removes leftover cgroups
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use libcgroups::common;
+use libcontainer::config::YoukiConfig;
+
+/// Remove leftover cgroups of containers which have no processes left
+#[derive(Parser, Debug)]
+pub struct Gc {}
+
+pub fn gc(_: Gc, root_path: PathBuf) -> Result<()> {
+    let root_path = fs::canonicalize(root_path)?;
+    // the cgroup of each container is recorded in the config within its
+    // directory in the root directory, so only cgroups created by youki
+    // are taken into account
+    for container_dir in fs::read_dir(root_path)? {
+        let container_dir = container_dir?.path();
+        let config = match YoukiConfig::load(&container_dir) {
+            Ok(config) => config,
+            Err(err) => {
+                log::debug!("skip {:?}: {:?}", container_dir, err);
+                continue;
+            }
+        };
+
+        let cgroup_path = match collectable_cgroup(&config) {
+            Some(cgroup_path) => cgroup_path,
+            None => continue,
+        };
+
+        for path in common::get_cgroup_hierarchy_paths(cgroup_path)? {
+            let removed = common::remove_empty_cgroups(&path)
+                .with_context(|| format!("failed to remove empty cgroup {:?}", path))?;
+            for cgroup in removed {
+                println!("removed {}", cgroup.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the cgroup of the container, if it is up to youki to remove it
+fn collectable_cgroup(config: &YoukiConfig) -> Option<&Path> {
+    // a joined cgroup belongs to the manager which created it, which may
+    // still want to use it once it is empty
+    if config.join_cgroup {
+        log::debug!("skip joined cgroup {:?}", config.cgroup_path);
+        return None;
+    }
+
+    // systemd removes the transient units it manages together with
+    // their cgroups once they are empty
+    if config.cgroup_path.to_string_lossy().contains(':') {
+        log::debug!("skip systemd managed cgroup {:?}", config.cgroup_path);
+        return None;
+    }
+
+    Some(&config.cgroup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oci_spec::runtime::{LinuxBuilder, SpecBuilder};
+
+    fn config(cgroups_path: &str, join_cgroup: bool) -> Result<YoukiConfig> {
+        let spec = SpecBuilder::default()
+            .linux(LinuxBuilder::default().cgroups_path(cgroups_path).build()?)
+            .build()?;
+        let mut config = YoukiConfig::from_spec(&spec, "test", false)?;
+        config.join_cgroup = join_cgroup;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_collectable_cgroup() -> Result<()> {
+        let created = config("/youki/test", false)?;
+        assert_eq!(collectable_cgroup(&created), Some(Path::new("/youki/test")));
+        assert_eq!(collectable_cgroup(&config("/youki/test", true)?), None);
+        assert_eq!(
+            collectable_cgroup(&config("system.slice:youki:test", false)?),
+            None
+        );
+        Ok(())
+    }
+}
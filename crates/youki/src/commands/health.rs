@@ -0,0 +1,243 @@
+//! Contains functionality of the health command, which probes if a container is healthy
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use nix::errno::Errno;
+use nix::sys::signal::{self, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use serde::Serialize;
+
+use libcontainer::{
+    container::builder::ContainerBuilder, pidfd::PidFd, syscall::syscall::create_syscall,
+};
+
+use crate::commands::exec;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Run a probe command inside a running container and report its health as json
+#[derive(Parser, Debug)]
+pub struct Health {
+    /// Seconds after which the probe is stopped and the container reported unhealthy
+    #[clap(long, default_value = "30")]
+    pub timeout: u64,
+    /// Identifier of the container
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+    /// Probe command, a zero exit code means the container is healthy
+    #[clap(required = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Healthy,
+    Unhealthy,
+}
+
+/// Result of a health probe as printed by the health command
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: Status,
+    /// Exit code of the probe, none if it timed out
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+pub fn health(args: Health, root_path: PathBuf) -> Result<()> {
+    // the pid file is the only way to learn the pid of the probe itself, the
+    // builder returns the intermediate process which waits for the probe
+    let pid_file = root_path
+        .join(&args.container_id)
+        .join(format!("health-{}.pid", process::id()));
+    let syscall = create_syscall();
+    // the probe is run like exec does, in the namespaces of the container
+    let intermediate = ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
+        .with_root_path(root_path)?
+        .with_pid_file(Some(&pid_file))?
+        .as_tenant()
+        .with_container_args(args.command.clone())
+        .build()
+        .with_context(|| format!("failed to run probe in container {}", args.container_id))?;
+    let probe = read_pid_file(&pid_file).and_then(Probe::open);
+    let _ = fs::remove_file(&pid_file);
+
+    let start = Instant::now();
+    let exit_code = wait_with_timeout(intermediate, &probe?, Duration::from_secs(args.timeout))?;
+    let report = HealthReport::new(exit_code, start.elapsed());
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
+
+impl HealthReport {
+    fn new(exit_code: Option<i32>, duration: Duration) -> Self {
+        Self {
+            status: match exit_code {
+                Some(0) => Status::Healthy,
+                _ => Status::Unhealthy,
+            },
+            exit_code,
+            timed_out: exit_code.is_none(),
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+fn read_pid_file(path: &Path) -> Result<Pid> {
+    let pid = fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
+    let pid = pid
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid pid {:?} in {:?}", pid, path))?;
+    Ok(Pid::from_raw(pid))
+}
+
+// The probe, opened as a pidfd right after it was spawned, so that killing it
+// on a timeout can not hit another process which reused its pid. On kernels
+// without pidfds it is signalled through its pid.
+enum Probe {
+    PidFd(Pid, PidFd),
+    Pid(Pid),
+    // the probe has exited and was reaped by the intermediate process already
+    Exited(Pid),
+}
+
+impl Probe {
+    fn open(pid: Pid) -> Result<Self> {
+        match PidFd::open(pid) {
+            Ok(pidfd) => Ok(Probe::PidFd(pid, pidfd)),
+            Err(Errno::ENOSYS) => Ok(Probe::Pid(pid)),
+            Err(Errno::ESRCH) => Ok(Probe::Exited(pid)),
+            Err(err) => Err(err).with_context(|| format!("failed to open pidfd of probe {}", pid)),
+        }
+    }
+
+    fn pid(&self) -> Pid {
+        match self {
+            Probe::PidFd(pid, _) | Probe::Pid(pid) | Probe::Exited(pid) => *pid,
+        }
+    }
+
+    fn kill(&self) -> nix::Result<()> {
+        match self {
+            Probe::PidFd(_, pidfd) => pidfd.send_signal(Signal::SIGKILL),
+            Probe::Pid(pid) => signal::kill(*pid, Signal::SIGKILL),
+            Probe::Exited(_) => Err(Errno::ESRCH),
+        }
+    }
+}
+
+// Waits for the intermediate process, which waits for the probe, to exit and
+// returns the exit code of the probe, as exec does. A probe which runs for
+// longer than the timeout is killed and None is returned.
+fn wait_with_timeout(intermediate: Pid, probe: &Probe, timeout: Duration) -> Result<Option<i32>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match waitpid(intermediate, Some(WaitPidFlag::WNOHANG)) {
+            Ok(WaitStatus::StillAlive) | Err(Errno::EINTR) => {}
+            Ok(status) => return Ok(Some(exec::exit_code(status))),
+            Err(err) => return Err(err).context("failed to wait for probe"),
+        }
+
+        if Instant::now() >= deadline {
+            log::warn!("probe {} did not finish within {:?}", probe.pid(), timeout);
+            match probe.kill() {
+                // the probe has exited in the meantime
+                Ok(()) | Err(Errno::ESRCH) => {}
+                Err(err) => return Err(err).context("failed to kill probe"),
+            }
+            waitpid(intermediate, None).context("failed to reap probe")?;
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libcontainer::process::fork;
+    use nix::unistd;
+    use serial_test::serial;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::unix::io::FromRawFd;
+    use std::process::Command;
+
+    // Runs the probe like the tenant builder does, in a child of an
+    // intermediate process which takes over the exit code of the probe.
+    // Returns the pid of the intermediate process and the probe.
+    fn spawn(script: &str) -> Result<(Pid, Probe)> {
+        let (reader, writer) = unistd::pipe()?;
+        let intermediate = fork::container_fork(|| {
+            let probe = Command::new("sh").arg("-c").arg(script).spawn()?;
+            unsafe { File::from_raw_fd(writer) }.write_all(&probe.id().to_le_bytes())?;
+            let status = waitpid(Pid::from_raw(probe.id() as i32), None)?;
+            Ok(exec::exit_code(status))
+        })?;
+        unistd::close(writer)?;
+        let mut probe = [0; 4];
+        unsafe { File::from_raw_fd(reader) }.read_exact(&mut probe)?;
+        let probe = Probe::open(Pid::from_raw(u32::from_le_bytes(probe) as i32))?;
+        Ok((intermediate, probe))
+    }
+
+    #[test]
+    #[serial]
+    fn test_probe_succeeds() -> Result<()> {
+        let (intermediate, probe) = spawn("exit 0")?;
+        let exit_code = wait_with_timeout(intermediate, &probe, Duration::from_secs(10))?;
+        assert_eq!(exit_code, Some(0));
+
+        let report = HealthReport::new(exit_code, Duration::from_millis(5));
+        assert_eq!(report.status, Status::Healthy);
+        assert!(!report.timed_out);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_probe_fails() -> Result<()> {
+        let (intermediate, probe) = spawn("exit 3")?;
+        let exit_code = wait_with_timeout(intermediate, &probe, Duration::from_secs(10))?;
+        assert_eq!(exit_code, Some(3));
+        assert_eq!(
+            HealthReport::new(exit_code, Duration::ZERO).status,
+            Status::Unhealthy
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_probe_times_out() -> Result<()> {
+        let start = Instant::now();
+        let (intermediate, probe) = spawn("exec sleep 30")?;
+        let exit_code = wait_with_timeout(intermediate, &probe, Duration::from_millis(100))?;
+        assert_eq!(exit_code, None);
+        assert!(start.elapsed() < Duration::from_secs(10));
+        // the probe itself is killed, not only the intermediate process
+        assert_eq!(signal::kill(probe.pid(), None), Err(Errno::ESRCH));
+        // and the pidfd refers to the probe which has exited, even if its pid
+        // is reused
+        assert_eq!(probe.kill(), Err(Errno::ESRCH));
+
+        let report = HealthReport::new(exit_code, start.elapsed());
+        assert_eq!(report.status, Status::Unhealthy);
+        assert!(report.timed_out);
+        let json = serde_json::to_value(&report)?;
+        assert_eq!(json["status"], "unhealthy");
+        assert_eq!(json["exit_code"], serde_json::Value::Null);
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+//! Contains the hidden init command, with which youki re-executes itself to
+//! set up a container in a process with a single thread
+use std::ffi::OsString;
+
+use anyhow::Result;
+use clap::Parser;
+use liboci_cli::GlobalOpts;
+
+/// Set up a container, only used by youki itself
+#[derive(Parser, Debug)]
+pub struct Init {}
+
+/// Arguments with which youki re-executes itself to set up a container. The
+/// re-executed youki logs the same way as the calling one.
+pub fn reexec_args(global: &GlobalOpts) -> Vec<OsString> {
+    let mut args = Vec::new();
+    if global.debug {
+        args.push("--debug".into());
+    }
+    if let Some(log) = &global.log {
+        args.push("--log".into());
+        args.push(log.into());
+    }
+    if let Some(log_format) = &global.log_format {
+        args.push("--log-format".into());
+        args.push(log_format.into());
+    }
+    args.push("init".into());
+    args
+}
+
+pub fn init(_: Init) -> Result<i32> {
+    libcontainer::process::reexec::run()
+}
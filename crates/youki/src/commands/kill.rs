@@ -13,6 +13,7 @@ pub fn kill(args: Kill, root_path: PathBuf) -> Result<()> {
     match container.kill(signal, args.all) {
         Ok(_) => Ok(()),
         Err(e) => {
+            let e = anyhow::Error::from(e);
             // see https://github.com/containers/youki/issues/1314
             if container.status() == ContainerStatus::Stopped {
                 return Err(e.context("container not running"));
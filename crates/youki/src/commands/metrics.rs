@@ -0,0 +1,27 @@
+//! Contains functionality of the metrics command, which prints the cgroup
+//! statistics of a container in the OpenMetrics text format
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use libcgroups::metrics::render_openmetrics;
+
+use crate::commands::load_container;
+
+/// Print the resource statistics of a container in the Prometheus text format
+#[derive(Parser, Debug)]
+pub struct Metrics {
+    /// Identifier of the container
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+}
+
+pub fn metrics(args: Metrics, root_path: PathBuf) -> Result<()> {
+    let mut container = load_container(root_path, &args.container_id)?;
+    let stats = container
+        .stats()
+        .with_context(|| format!("failed to get stats of container {}", args.container_id))?;
+    print!("{}", render_openmetrics(container.id(), &stats));
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
 use anyhow::{bail, Context, Result};
 use std::{
-    fs,
+    env, fs,
     path::{Path, PathBuf},
 };
 
 use libcgroups::common::CgroupManager;
-use libcontainer::container::Container;
+use libcontainer::{container::Container, notify_relay::NOTIFY_SOCKET_ENV};
 
 pub mod checkpoint;
 pub mod completion;
@@ -13,9 +13,13 @@ pub mod create;
 pub mod delete;
 pub mod events;
 pub mod exec;
+pub mod gc;
+pub mod health;
 pub mod info;
+pub mod init;
 pub mod kill;
 pub mod list;
+pub mod metrics;
 pub mod pause;
 pub mod ps;
 pub mod resume;
@@ -24,6 +28,7 @@ pub mod spec_json;
 pub mod start;
 pub mod state;
 pub mod update;
+pub mod wait;
 
 fn construct_container_root<P: AsRef<Path>>(root_path: P, container_id: &str) -> Result<PathBuf> {
     // resolves relative paths, symbolic links etc. and get complete path
@@ -53,6 +58,19 @@ fn container_exists<P: AsRef<Path>>(root_path: P, container_id: &str) -> Result<
     Ok(container_root.exists())
 }
 
+// The readiness of the container is only relayed if it was asked for, as a
+// NOTIFY_SOCKET in the environment does not mean that the container speaks
+// sd_notify.
+fn notify_socket(notify_relay: bool) -> Result<Option<PathBuf>> {
+    if !notify_relay {
+        return Ok(None);
+    }
+    match env::var_os(NOTIFY_SOCKET_ENV) {
+        Some(path) => Ok(Some(PathBuf::from(path))),
+        None => bail!("--notify-relay requires {} to be set", NOTIFY_SOCKET_ENV),
+    }
+}
+
 fn create_cgroup_manager<P: AsRef<Path>>(
     root_path: P,
     container_id: &str,
@@ -65,3 +83,35 @@ fn create_cgroup_manager<P: AsRef<Path>>(
 
     libcgroups::common::create_cgroup_manager(cgroups_path, systemd_cgroup, container.id())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::determine_root_path;
+    use libcontainer::container::ContainerStatus;
+    use libcontainer::utils::{get_temp_dir_path, TempDir};
+
+    #[test]
+    fn test_containers_are_isolated_by_root() -> Result<()> {
+        let temp_dir = TempDir::new(get_temp_dir_path("isolated_roots"))?;
+        let root_a = determine_root_path(Some(temp_dir.join("a")))?;
+        let root_b = determine_root_path(Some(temp_dir.join("b")))?;
+
+        let container_root = root_a.join("isolated");
+        fs::create_dir_all(&container_root)?;
+        Container::new(
+            "isolated",
+            ContainerStatus::Created,
+            None,
+            &temp_dir,
+            &container_root,
+        )?
+        .save()?;
+
+        assert!(container_exists(&root_a, "isolated")?);
+        assert!(load_container(&root_a, "isolated").is_ok());
+        assert!(!container_exists(&root_b, "isolated")?);
+        assert!(load_container(&root_b, "isolated").is_err());
+        Ok(())
+    }
+}
@@ -1,21 +1,64 @@
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf, time::Duration};
 
 use anyhow::{Context, Result};
-use libcontainer::{container::builder::ContainerBuilder, syscall::syscall::create_syscall};
+use libcontainer::{
+    container::builder::ContainerBuilder, signal::Signal, subreaper,
+    syscall::syscall::create_syscall,
+};
 use liboci_cli::Run;
 
-pub fn run(args: Run, root_path: PathBuf, systemd_cgroup: bool) -> Result<()> {
+pub fn run(
+    args: Run,
+    root_path: PathBuf,
+    systemd_cgroup: bool,
+    reexec_args: Vec<OsString>,
+) -> Result<i32> {
+    let forward = args
+        .forward_signals
+        .iter()
+        .map(|signal| Signal::try_from(signal.as_str()))
+        .collect::<Result<Vec<_>>>()?;
+    if args.subreaper {
+        subreaper::set_subreaper()?;
+        // a signal which arrives while the container is created and started
+        // is kept pending until it can be forwarded
+        let forward: Vec<_> = forward.iter().map(|signal| signal.into_raw()).collect();
+        subreaper::block_signals(&forward)?;
+    }
+
     let syscall = create_syscall();
     let mut container = ContainerBuilder::new(args.container_id.clone(), syscall.as_ref())
         .with_pid_file(args.pid_file.as_ref())?
         .with_console_socket(args.console_socket.as_ref())
+        .with_stdio_socket(args.stdio_socket.as_ref())
         .with_root_path(root_path)?
         .with_preserved_fds(args.preserve_fds)
+        .with_capability_audit(args.audit_capabilities)
+        .with_timeout((args.create_timeout > 0).then_some(Duration::from_secs(args.create_timeout)))
+        .with_reexec(Some(reexec_args))
         .as_init(&args.bundle)
+        .with_cgroup_path(args.cgroup_path.as_ref())
+        .with_notify_socket(super::notify_socket(args.notify_relay)?)
         .with_systemd(systemd_cgroup)
+        .with_init(args.init)
+        .with_strict_pids_limit(args.strict_pids_limit)
+        .with_new_keyring(args.new_keyring)
         .build()?;
+    if args.subreaper {
+        // this process becomes the parent of the container process, which
+        // it reaps and whose exit code it records
+        container.set_records_exit_code(true).save()?;
+    }
 
     container
         .start()
-        .with_context(|| format!("failed to start container {}", args.container_id))
+        .with_context(|| format!("failed to start container {}", args.container_id))?;
+
+    if !args.subreaper {
+        return Ok(0);
+    }
+    let exit_code = container
+        .wait_as_subreaper(&forward)
+        .with_context(|| format!("failed to wait for container {}", args.container_id))?;
+    Ok(exit_code)
 }
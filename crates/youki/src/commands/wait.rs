@@ -0,0 +1,25 @@
+//! Contains functionality of the wait command, which blocks until a container has exited
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::commands::load_container;
+
+/// Wait until a container has exited and print its exit code
+#[derive(Parser, Debug)]
+pub struct Wait {
+    /// Identifier of the container
+    #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new(), required = true)]
+    pub container_id: String,
+}
+
+pub fn wait(args: Wait, root_path: PathBuf) -> Result<i32> {
+    let mut container = load_container(root_path, &args.container_id)?;
+    let exit_code = container
+        .wait()
+        .with_context(|| format!("failed to wait for container {}", args.container_id))?;
+    println!("{}", exit_code);
+
+    Ok(exit_code)
+}
@@ -38,10 +38,11 @@ pub fn init(
     let format = detect_log_format(log_format).context("failed to detect log format")?;
     let _ = LOG_FILE.get_or_init(|| -> Option<File> {
         log_file.map(|path| {
+            // youki re-executes itself with the same log file, so it is
+            // shared by several processes
             OpenOptions::new()
                 .create(true)
-                .write(true)
-                .truncate(false)
+                .append(true)
                 .open(path)
                 .expect("failed opening log file")
         })
@@ -11,7 +11,7 @@ use clap::CommandFactory;
 use clap::{crate_version, Parser};
 use nix::libc;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use crate::commands::info;
 use libcontainer::rootless::rootless_required;
@@ -46,7 +46,13 @@ enum SubCommand {
 
     // Youki specific extensions
     Info(info::Info),
+    Gc(commands::gc::Gc),
+    Health(commands::health::Health),
+    Wait(commands::wait::Wait),
+    Metrics(commands::metrics::Metrics),
     Completion(commands::completion::Completion),
+    #[clap(hide = true)]
+    Init(commands::init::Init),
 }
 
 /// output Youki version in Moby compatible format
@@ -83,6 +89,7 @@ fn main() -> Result<()> {
 
     let opts = Opts::parse();
     let mut app = Opts::command();
+    let reexec_args = commands::init::reexec_args(&opts.global);
 
     if let Err(e) = crate::logger::init(opts.global.debug, opts.global.log, opts.global.log_format)
     {
@@ -94,13 +101,25 @@ fn main() -> Result<()> {
         nix::unistd::geteuid(),
         std::env::args_os()
     );
+    // the re-executed init gets everything from the youki which started it,
+    // determining the root path would only create a directory, and without
+    // the root passed on, not even the one in use
+    if let SubCommand::Init(init) = opts.subcmd {
+        return match commands::init::init(init) {
+            Ok(exit_code) => std::process::exit(exit_code),
+            Err(e) => {
+                log::error!("error in executing command: {:?}", e);
+                Err(e)
+            }
+        };
+    }
     let root_path = determine_root_path(opts.global.root)?;
     let systemd_cgroup = opts.global.systemd_cgroup;
 
     let cmd_result = match opts.subcmd {
         SubCommand::Standard(cmd) => match cmd {
             StandardCmd::Create(create) => {
-                commands::create::create(create, root_path, systemd_cgroup)
+                commands::create::create(create, root_path, systemd_cgroup, reexec_args)
             }
             StandardCmd::Start(start) => commands::start::start(start, root_path),
             StandardCmd::Kill(kill) => commands::kill::kill(kill, root_path),
@@ -112,7 +131,7 @@ fn main() -> Result<()> {
                 commands::checkpoint::checkpoint(checkpoint, root_path)
             }
             CommonCmd::Events(events) => commands::events::events(events, root_path),
-            CommonCmd::Exec(exec) => match commands::exec::exec(exec, root_path) {
+            CommonCmd::Exec(exec) => match commands::exec::exec(exec, root_path, reexec_args) {
                 Ok(exit_code) => std::process::exit(exit_code),
                 Err(e) => {
                     eprintln!("exec failed : {}", e);
@@ -123,15 +142,32 @@ fn main() -> Result<()> {
             CommonCmd::Pause(pause) => commands::pause::pause(pause, root_path),
             CommonCmd::Ps(ps) => commands::ps::ps(ps, root_path),
             CommonCmd::Resume(resume) => commands::resume::resume(resume, root_path),
-            CommonCmd::Run(run) => commands::run::run(run, root_path, systemd_cgroup),
+            CommonCmd::Run(run) => {
+                match commands::run::run(run, root_path, systemd_cgroup, reexec_args) {
+                    Ok(0) => Ok(()),
+                    Ok(exit_code) => std::process::exit(exit_code),
+                    Err(e) => Err(e),
+                }
+            }
             CommonCmd::Spec(spec) => commands::spec_json::spec(spec),
             CommonCmd::Update(update) => commands::update::update(update, root_path),
         },
 
         SubCommand::Info(info) => commands::info::info(info),
+        SubCommand::Gc(gc) => commands::gc::gc(gc, root_path),
+        SubCommand::Health(health) => commands::health::health(health, root_path),
+        SubCommand::Metrics(metrics) => commands::metrics::metrics(metrics, root_path),
+        SubCommand::Wait(wait) => match commands::wait::wait(wait, root_path) {
+            Ok(exit_code) => std::process::exit(exit_code),
+            Err(e) => {
+                eprintln!("wait failed : {}", e);
+                std::process::exit(-1);
+            }
+        },
         SubCommand::Completion(completion) => {
             commands::completion::completion(completion, &mut app)
         }
+        SubCommand::Init(_) => unreachable!("init is run before the root path is determined"),
     };
 
     if let Err(ref e) = cmd_result {
@@ -143,10 +179,15 @@ fn main() -> Result<()> {
 fn determine_root_path(root_path: Option<PathBuf>) -> Result<PathBuf> {
     let uid = getuid().as_raw();
 
+    // an explicitly given root always takes precedence, so that invocations
+    // with different roots never see the containers of each other
     if let Some(path) = root_path {
         if !path.exists() {
             create_dir_all_with_mode(&path, uid, Mode::S_IRWXU)?;
         }
+        if !path.is_dir() {
+            bail!("root path {:?} is not a directory", path);
+        }
         let path = path.canonicalize()?;
         return Ok(path);
     }
@@ -157,9 +198,8 @@ fn determine_root_path(root_path: Option<PathBuf>) -> Result<PathBuf> {
         return Ok(path);
     }
 
-    // see https://specifications.freedesktop.org/basedir-spec/basedir-spec-latest.html
-    if let Ok(path) = std::env::var("XDG_RUNTIME_DIR") {
-        let path = Path::new(&path).join("youki");
+    if let Some(path) = xdg_runtime_dir() {
+        let path = path.join("youki");
         if create_dir_all_with_mode(&path, uid, Mode::S_IRWXU).is_ok() {
             return Ok(path);
         }
@@ -188,6 +228,14 @@ fn determine_root_path(root_path: Option<PathBuf>) -> Result<PathBuf> {
     bail!("could not find a storage location with suitable permissions for the current user");
 }
 
+// see https://specifications.freedesktop.org/basedir-spec/basedir-spec-latest.html
+// relative paths are invalid and have to be ignored, an empty value included
+fn xdg_runtime_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
 #[cfg(not(test))]
 fn get_default_not_rootless_path() -> PathBuf {
     PathBuf::from("/run/youki")
@@ -210,17 +258,20 @@ fn get_default_rootless_path(uid: libc::uid_t) -> PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use crate::determine_root_path;
+    use crate::{commands, determine_root_path, Opts, SubCommand};
     use anyhow::{Context, Result};
+    use clap::Parser;
     use libcontainer::utils::{get_temp_dir_path, TempDir};
     use nix::sys::stat::Mode;
     use nix::unistd::getuid;
+    use serial_test::serial;
     use std::fs;
     use std::fs::Permissions;
     use std::os::unix::fs::PermissionsExt;
     use std::path::{Path, PathBuf};
 
     #[test]
+    #[serial]
     fn test_determine_root_path_use_specified_by_user() -> Result<()> {
         // Create directory if it does not exist and return absolute path.
         let specified_path = get_temp_dir_path("provided_path");
@@ -241,6 +292,39 @@ mod tests {
     }
 
     #[test]
+    #[serial]
+    fn test_determine_root_path_specified_not_a_directory() -> Result<()> {
+        let specified_path = get_temp_dir_path("provided_file");
+        let _temp_dir = TempDir::new(&specified_path).context("failed to create temp dir")?;
+        let file = specified_path.join("root");
+        fs::write(&file, "").context("failed to create file")?;
+
+        assert!(determine_root_path(Some(file)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_determine_root_path_specified_takes_precedence() -> Result<()> {
+        std::env::set_var("YOUKI_USE_ROOTLESS", "true");
+        let xdg_dir = get_temp_dir_path("xdg_runtime_precedence");
+        std::env::set_var("XDG_RUNTIME_DIR", &xdg_dir);
+
+        let specified_path = get_temp_dir_path("provided_path_precedence");
+        let _temp_dir = TempDir::new(&specified_path).context("failed to create temp dir")?;
+        let path = determine_root_path(Some(specified_path.clone()))
+            .context("failed with specified path")?;
+        assert_eq!(path, specified_path);
+        assert!(!xdg_dir.join("youki").exists());
+
+        std::env::remove_var("XDG_RUNTIME_DIR");
+        std::env::remove_var("YOUKI_USE_ROOTLESS");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
     fn test_determine_root_path_non_rootless() -> Result<()> {
         // If we do not have root privileges skip the test as it will not succeed.
         if !getuid().is_root() {
@@ -266,6 +350,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn test_determine_root_path_rootless() -> Result<()> {
         std::env::set_var("YOUKI_USE_ROOTLESS", "true");
 
@@ -289,6 +374,14 @@ mod tests {
         assert_eq!(path, default_rootless_path);
         assert!(path.exists());
 
+        // A relative or empty XDG_RUNTIME_DIR is ignored
+        for invalid in ["", "xdg_runtime"] {
+            std::env::set_var("XDG_RUNTIME_DIR", invalid);
+            let path = determine_root_path(None).context("failed with invalid $XDG_RUNTIME_DIR")?;
+            assert_eq!(path, default_rootless_path);
+        }
+        std::env::remove_var("XDG_RUNTIME_DIR");
+
         // Set invalid permissions to default rootless path so that it fails for the next test.
         fs::set_permissions(
             default_rootless_path,
@@ -322,6 +415,7 @@ mod tests {
 
         assert!(determine_root_path(None).is_err());
 
+        std::env::remove_var("YOUKI_USE_ROOTLESS");
         Ok(())
     }
 
@@ -331,4 +425,30 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_reexec_args_run_init() -> Result<()> {
+        let opts = Opts::try_parse_from([
+            "youki",
+            "--debug",
+            "--log",
+            "/var/log/youki.log",
+            "--root",
+            "/run/youki",
+            "create",
+            "74f1a4cb3801",
+        ])?;
+        let mut args = vec!["youki".into()];
+        args.extend(commands::init::reexec_args(&opts.global));
+        assert_eq!(
+            args,
+            ["youki", "--debug", "--log", "/var/log/youki.log", "init"]
+        );
+
+        let reexec = Opts::try_parse_from(args)?;
+        assert!(matches!(reexec.subcmd, SubCommand::Init(_)));
+        assert!(reexec.global.debug);
+        assert_eq!(reexec.global.log, opts.global.log);
+        Ok(())
+    }
 }
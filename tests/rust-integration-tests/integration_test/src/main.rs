@@ -1,13 +1,24 @@
 mod tests;
 mod utils;
 
+use crate::tests::config_stdin::get_config_stdin_test;
+use crate::tests::container_guard::get_container_guard_test;
+use crate::tests::default_devices::get_default_devices_test;
+use crate::tests::dev_null::get_dev_null_test;
+use crate::tests::devpts::get_devpts_test;
+use crate::tests::exec_keep_env::get_exec_keep_env_test;
+use crate::tests::health::get_health_test;
 use crate::tests::hooks::get_hooks_tests;
 use crate::tests::hostname::get_hostname_test;
 use crate::tests::lifecycle::{ContainerCreate, ContainerLifecycle};
 use crate::tests::linux_ns_itype::get_ns_itype_tests;
 use crate::tests::mounts_recursive::get_mounts_recursive_test;
+use crate::tests::overlay::get_overlay_test;
 use crate::tests::pidfile::get_pidfile_test;
+use crate::tests::process_umask::get_process_umask_test;
 use crate::tests::readonly_paths::get_ro_paths_test;
+use crate::tests::reexec::get_reexec_test;
+use crate::tests::root_readonly::get_root_readonly_test;
 use crate::tests::seccomp_notify::get_seccomp_notify_test;
 use crate::tests::tlb::get_tlb_test;
 use crate::utils::support::{set_runtime_path, set_runtimetest_path};
@@ -33,38 +44,26 @@ struct Opts {
 enum SubCommand {
     /// run the integration tests
     Run(Run),
-    /// list available integration tests
+    /// list the names of all test groups and their tests
     List,
 }
 
 #[derive(Parser, Debug)]
 struct Run {
     /// Path for the container runtime to be tested
-    #[clap(long)]
-    runtime: PathBuf,
+    #[clap(long, required_unless_present = "list")]
+    runtime: Option<PathBuf>,
     /// Path for the runtimetest binary, which will be used to run tests inside the container
-    #[clap(long)]
-    runtimetest: PathBuf,
+    #[clap(long, required_unless_present = "list")]
+    runtimetest: Option<PathBuf>,
     /// Selected tests to be run, format should be
     /// space separated groups, eg
     /// -t group1::test1,test3 group2 group3::test5
-    #[clap(short, long, num_args(1..), value_delimiter = ' ')]
+    #[clap(short, long, visible_alias = "test", num_args(1..), value_delimiter = ' ')]
     tests: Option<Vec<String>>,
-}
-
-// parse test string given in commandline option as pair of testgroup name and tests belonging to that
-fn parse_tests(tests: &[String]) -> Vec<(&str, Option<Vec<&str>>)> {
-    let mut ret = Vec::with_capacity(tests.len());
-    for test in tests {
-        if test.contains("::") {
-            let (mod_name, test_names) = test.split_once("::").unwrap();
-            let _tests = test_names.split(',').collect();
-            ret.push((mod_name, Some(_tests)));
-        } else {
-            ret.push((test, None));
-        }
-    }
-    ret
+    /// List the names of all test groups and their tests instead of running them
+    #[clap(long)]
+    list: bool,
 }
 
 fn main() -> Result<()> {
@@ -88,10 +87,23 @@ fn main() -> Result<()> {
     let cgroup_v1_memory = cgroups::memory::get_test_group();
     let cgroup_v1_network = cgroups::network::get_test_group();
     let cgroup_v1_blkio = cgroups::blkio::get_test_group();
+    let cgroup_join = cgroups::join::get_test_group();
+    let cgroup_exec = cgroups::exec::get_test_group();
     let seccomp_notify = get_seccomp_notify_test();
     let ro_paths = get_ro_paths_test();
     let hostname = get_hostname_test();
     let mounts_recursive = get_mounts_recursive_test();
+    let process_umask = get_process_umask_test();
+    let root_readonly = get_root_readonly_test();
+    let default_devices = get_default_devices_test();
+    let devpts = get_devpts_test();
+    let overlay = get_overlay_test();
+    let config_stdin = get_config_stdin_test();
+    let reexec = get_reexec_test();
+    let health = get_health_test();
+    let dev_null = get_dev_null_test();
+    let container_guard = get_container_guard_test();
+    let exec_keep_env = get_exec_keep_env_test();
 
     tm.add_test_group(Box::new(cl));
     tm.add_test_group(Box::new(cc));
@@ -105,10 +117,23 @@ fn main() -> Result<()> {
     tm.add_test_group(Box::new(cgroup_v1_memory));
     tm.add_test_group(Box::new(cgroup_v1_network));
     tm.add_test_group(Box::new(cgroup_v1_blkio));
+    tm.add_test_group(Box::new(cgroup_join));
+    tm.add_test_group(Box::new(cgroup_exec));
     tm.add_test_group(Box::new(seccomp_notify));
     tm.add_test_group(Box::new(ro_paths));
     tm.add_test_group(Box::new(hostname));
     tm.add_test_group(Box::new(mounts_recursive));
+    tm.add_test_group(Box::new(process_umask));
+    tm.add_test_group(Box::new(root_readonly));
+    tm.add_test_group(Box::new(default_devices));
+    tm.add_test_group(Box::new(devpts));
+    tm.add_test_group(Box::new(overlay));
+    tm.add_test_group(Box::new(config_stdin));
+    tm.add_test_group(Box::new(reexec));
+    tm.add_test_group(Box::new(health));
+    tm.add_test_group(Box::new(dev_null));
+    tm.add_test_group(Box::new(container_guard));
+    tm.add_test_group(Box::new(exec_keep_env));
 
     tm.add_cleanup(Box::new(cgroups::cleanup_v1));
     tm.add_cleanup(Box::new(cgroups::cleanup_v2));
@@ -137,25 +162,33 @@ fn get_abs_path(rel_path: &Path) -> PathBuf {
 }
 
 fn run(opts: Run, test_manager: &TestManager) -> Result<()> {
-    let runtime_path = get_abs_path(&opts.runtime);
+    if opts.list {
+        return list(test_manager);
+    }
+
+    // validate the selection before anything is set up for the tests
+    let tests_to_run = match &opts.tests {
+        Some(tests) => Some(test_manager.select(tests)?),
+        None => None,
+    };
+
+    let runtime_path = get_abs_path(opts.runtime.as_ref().context("no runtime given")?);
     set_runtime_path(&runtime_path);
 
-    let runtimetest_path = get_abs_path(&opts.runtimetest);
+    let runtimetest_path = get_abs_path(opts.runtimetest.as_ref().context("no runtimetest given")?);
     set_runtimetest_path(&runtimetest_path);
 
-    if let Some(tests) = opts.tests {
-        let tests_to_run = parse_tests(&tests);
-        test_manager.run_selected(tests_to_run);
-    } else {
-        test_manager.run_all();
-    }
+    match tests_to_run {
+        Some(tests_to_run) => test_manager.run_selected(tests_to_run),
+        None => test_manager.run_all(),
+    };
 
     Ok(())
 }
 
 fn list(test_manager: &TestManager) -> Result<()> {
-    for test_group in test_manager.tests_groups() {
-        println!("{}", test_group);
+    for name in test_manager.test_names() {
+        println!("{}", name);
     }
 
     Ok(())
@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use libcgroups::common::{get_cgroup_hierarchy_paths, get_cgroup_setup, CgroupSetup, CGROUP_PROCS};
+use oci_spec::runtime::{LinuxBuilder, ProcessBuilder, Spec};
+use test_framework::{test_result, ConditionalTest, TestGroup, TestResult};
+
+use crate::utils::{
+    create_container, generate_uuid, get_runtime_path, kill_container, prepare_bundle, set_config,
+    test_utils::start_container,
+};
+
+const CGROUP_PATH: &str = "/runtime-test/exec_sub_cgroup";
+const SUB_CGROUP: &str = "debug";
+
+fn create_spec() -> Result<Spec> {
+    let mut spec = Spec::default();
+    let linux = spec.linux().clone().unwrap_or_default();
+    let linux = LinuxBuilder::default()
+        .namespaces(linux.namespaces().clone().unwrap_or_default())
+        .cgroups_path(Path::new(CGROUP_PATH))
+        .build()
+        .context("failed to build linux spec")?;
+    let process = ProcessBuilder::default()
+        .args(vec!["sleep".to_owned(), "3600".to_owned()])
+        .build()
+        .context("failed to build process spec")?;
+    spec.set_linux(Some(linux)).set_process(Some(process));
+
+    Ok(spec)
+}
+
+// Tests if a process exec'd with --cgroup is placed in the sub-cgroup, which
+// does not exist before, of the cgroup of the container. On cgroup v1 every
+// sub-cgroup has the controller of its hierarchy.
+fn test_exec_sub_cgroup() -> TestResult {
+    let sub_cgroup_paths = test_result!(get_cgroup_hierarchy_paths(
+        &Path::new(CGROUP_PATH).join(SUB_CGROUP)
+    ));
+    let spec = test_result!(create_spec());
+
+    let id = generate_uuid();
+    let id_str = id.to_string();
+    let bundle = test_result!(prepare_bundle(&id));
+    test_result!(set_config(&bundle, &spec));
+    test_result!(run(create_container(&id_str, &bundle)));
+    test_result!(run(start_container(&id_str, &bundle)));
+
+    let pid_file = bundle.as_ref().join("exec.pid");
+    let result = exec_in_sub_cgroup(bundle.as_ref(), &id_str, &pid_file)
+        .and_then(|_| check_exec_in_sub_cgroup(&sub_cgroup_paths, &pid_file));
+
+    kill_container(&id_str, &bundle).unwrap().wait().unwrap();
+    let deleted = delete(bundle.as_ref(), &id_str);
+    test_result!(result);
+    test_result!(deleted);
+    // delete removes the sub-cgroup together with the cgroup of the container
+    test_result!(check_removed(
+        &get_cgroup_hierarchy_paths(Path::new(CGROUP_PATH)).unwrap()
+    ));
+    TestResult::Passed
+}
+
+// On cgroup v2 the cgroup of the container contains the container process,
+// so it can not enable controllers for a sub-cgroup anymore. Rather than
+// creating a sub-cgroup without controllers, the exec is rejected.
+fn test_exec_sub_cgroup_without_controllers() -> TestResult {
+    let sub_cgroup_paths = test_result!(get_cgroup_hierarchy_paths(
+        &Path::new(CGROUP_PATH).join(SUB_CGROUP)
+    ));
+    let spec = test_result!(create_spec());
+
+    let id = generate_uuid();
+    let id_str = id.to_string();
+    let bundle = test_result!(prepare_bundle(&id));
+    test_result!(set_config(&bundle, &spec));
+    test_result!(run(create_container(&id_str, &bundle)));
+    test_result!(run(start_container(&id_str, &bundle)));
+
+    let pid_file = bundle.as_ref().join("exec.pid");
+    let result = match exec_in_sub_cgroup(bundle.as_ref(), &id_str, &pid_file) {
+        Ok(()) => Err(anyhow::anyhow!(
+            "expected exec into a sub-cgroup without controllers to fail"
+        )),
+        Err(err) if !format!("{:?}", err).contains("no controllers enabled") => Err(err),
+        Err(_) => check_removed(&sub_cgroup_paths),
+    };
+
+    kill_container(&id_str, &bundle).unwrap().wait().unwrap();
+    let deleted = delete(bundle.as_ref(), &id_str);
+    test_result!(result);
+    test_result!(deleted);
+    TestResult::Passed
+}
+
+fn run(child: Result<std::process::Child>) -> Result<()> {
+    let output = child?.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "runtime failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+fn exec_in_sub_cgroup(project_path: &Path, id: &str, pid_file: &Path) -> Result<()> {
+    let output = Command::new(get_runtime_path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--root")
+        .arg(project_path.join("runtime"))
+        .arg("exec")
+        .arg("--detach")
+        .arg("--cgroup")
+        .arg(SUB_CGROUP)
+        .arg("--pid-file")
+        .arg(pid_file)
+        .arg(id)
+        .args(["sleep", "3600"])
+        .output()
+        .context("failed to exec into container")?;
+    if !output.status.success() {
+        bail!(
+            "exec into sub-cgroup failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+fn check_exec_in_sub_cgroup(sub_cgroup_paths: &[PathBuf], pid_file: &Path) -> Result<()> {
+    let pid =
+        fs::read_to_string(pid_file).with_context(|| format!("failed to read {:?}", pid_file))?;
+    for path in sub_cgroup_paths {
+        let procs = fs::read_to_string(path.join(CGROUP_PROCS))
+            .with_context(|| format!("failed to read cgroup.procs of {:?}", path))?;
+        if !procs.lines().any(|p| p.trim() == pid.trim()) {
+            bail!("expected exec'd process {} in {:?}", pid.trim(), path);
+        }
+    }
+
+    Ok(())
+}
+
+fn delete(project_path: &Path, id: &str) -> Result<()> {
+    let output = Command::new(get_runtime_path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--root")
+        .arg(project_path.join("runtime"))
+        .arg("delete")
+        .arg("--force")
+        .arg(id)
+        .output()
+        .context("failed to delete container")?;
+    if !output.status.success() {
+        bail!(
+            "delete of container with a sub-cgroup failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+fn check_removed(cgroup_paths: &[PathBuf]) -> Result<()> {
+    for path in cgroup_paths {
+        if path.exists() {
+            bail!("expected {:?} to be removed", path);
+        }
+    }
+
+    Ok(())
+}
+
+fn can_run(unified: bool) -> bool {
+    let has_paths = get_cgroup_hierarchy_paths(Path::new(CGROUP_PATH))
+        .map(|paths| !paths.is_empty())
+        .unwrap_or(false);
+    has_paths && matches!(get_cgroup_setup(), Ok(CgroupSetup::Unified)) == unified
+}
+
+pub fn get_test_group() -> TestGroup {
+    let mut test_group = TestGroup::new("cgroup_exec");
+    let exec_sub_cgroup = ConditionalTest::new(
+        "exec_sub_cgroup",
+        Box::new(|| can_run(false)),
+        Box::new(test_exec_sub_cgroup),
+    );
+    let exec_sub_cgroup_without_controllers = ConditionalTest::new(
+        "exec_sub_cgroup_without_controllers",
+        Box::new(|| can_run(true)),
+        Box::new(test_exec_sub_cgroup_without_controllers),
+    );
+
+    test_group.add(vec![
+        Box::new(exec_sub_cgroup),
+        Box::new(exec_sub_cgroup_without_controllers),
+    ]);
+    test_group
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use libcgroups::common::{get_cgroup_hierarchy_paths, CGROUP_PROCS};
+use oci_spec::runtime::{LinuxBuilder, LinuxPidsBuilder, LinuxResourcesBuilder, Spec, SpecBuilder};
+use test_framework::{test_result, ConditionalTest, TestGroup, TestResult};
+
+use crate::utils::{test_outside_container, test_utils::check_container_created};
+
+const CGROUP_PATH: &str = "/runtime-test/join_existing_cgroup";
+
+fn create_spec(join: bool) -> Result<Spec> {
+    let spec = SpecBuilder::default()
+        .annotations(HashMap::from([(
+            "org.youki.cgroup.join".to_string(),
+            join.to_string(),
+        )]))
+        .linux(
+            LinuxBuilder::default()
+                .cgroups_path(Path::new(CGROUP_PATH))
+                .resources(
+                    LinuxResourcesBuilder::default()
+                        .pids(
+                            LinuxPidsBuilder::default()
+                                .limit(50)
+                                .build()
+                                .context("failed to build pids spec")?,
+                        )
+                        .build()
+                        .context("failed to build resource spec")?,
+                )
+                .build()
+                .context("failed to build linux spec")?,
+        )
+        .build()
+        .context("failed to build spec")?;
+
+    Ok(spec)
+}
+
+// Tests if the container process is moved into a cgroup which was created
+// before the container, without its controllers being set or it being removed
+fn test_join_existing_cgroup() -> TestResult {
+    let cgroup_paths = test_result!(create_cgroups());
+    let spec = test_result!(create_spec(true));
+
+    let result = test_outside_container(spec, &|data| {
+        test_result!(check_container_created(&data));
+        let pid = match data.state.as_ref().and_then(|state| state.pid) {
+            Some(pid) => pid,
+            None => return TestResult::Failed(anyhow!("container has no pid")),
+        };
+        test_result!(check_cgroup(&cgroup_paths, pid, "max"));
+        TestResult::Passed
+    });
+
+    let kept = cgroup_paths.iter().all(|path| path.exists());
+    test_result!(remove_cgroups(&cgroup_paths));
+    if let TestResult::Passed = result {
+        if !kept {
+            return TestResult::Failed(anyhow!(
+                "joined cgroup was removed together with the container"
+            ));
+        }
+    }
+    result
+}
+
+// Tests if an existing cgroup is used like a new one unless joining it was
+// asked for, i.e. its controllers are set
+fn test_existing_cgroup_not_joined() -> TestResult {
+    let cgroup_paths = test_result!(create_cgroups());
+    let spec = test_result!(create_spec(false));
+
+    let result = test_outside_container(spec, &|data| {
+        test_result!(check_container_created(&data));
+        let pid = match data.state.as_ref().and_then(|state| state.pid) {
+            Some(pid) => pid,
+            None => return TestResult::Failed(anyhow!("container has no pid")),
+        };
+        test_result!(check_cgroup(&cgroup_paths, pid, "50"));
+        TestResult::Passed
+    });
+
+    test_result!(remove_cgroups(&cgroup_paths));
+    result
+}
+
+fn create_cgroups() -> Result<Vec<PathBuf>> {
+    let cgroup_paths = get_cgroup_hierarchy_paths(Path::new(CGROUP_PATH))?;
+    for path in &cgroup_paths {
+        fs::create_dir_all(path).with_context(|| format!("failed to create {:?}", path))?;
+    }
+
+    Ok(cgroup_paths)
+}
+
+fn check_cgroup(cgroup_paths: &[PathBuf], pid: i32, pids_limit: &str) -> Result<()> {
+    for path in cgroup_paths {
+        let procs = fs::read_to_string(path.join(CGROUP_PROCS))
+            .with_context(|| format!("failed to read cgroup.procs of {:?}", path))?;
+        if !procs.lines().any(|p| p.trim() == pid.to_string()) {
+            bail!("expected container process {} in {:?}", pid, path);
+        }
+
+        // the controllers of a joined cgroup belong to whoever created it
+        let pids_max = path.join("pids.max");
+        if pids_max.exists() {
+            let limit = fs::read_to_string(&pids_max)
+                .with_context(|| format!("failed to read {:?}", pids_max))?;
+            if limit.trim() != pids_limit {
+                bail!(
+                    "expected {:?} to be {}, but it was {}",
+                    pids_max,
+                    pids_limit,
+                    limit
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn remove_cgroups(cgroup_paths: &[PathBuf]) -> Result<()> {
+    for path in cgroup_paths {
+        if path.exists() {
+            fs::remove_dir(path).with_context(|| format!("failed to remove {:?}", path))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn can_run() -> bool {
+    get_cgroup_hierarchy_paths(Path::new(CGROUP_PATH))
+        .map(|paths| !paths.is_empty())
+        .unwrap_or(false)
+}
+
+pub fn get_test_group() -> TestGroup {
+    let mut test_group = TestGroup::new("cgroup_join");
+    let join_existing = ConditionalTest::new(
+        "join_existing_cgroup",
+        Box::new(can_run),
+        Box::new(test_join_existing_cgroup),
+    );
+
+    let not_joined = ConditionalTest::new(
+        "existing_cgroup_not_joined",
+        Box::new(can_run),
+        Box::new(test_existing_cgroup_not_joined),
+    );
+
+    test_group.add(vec![Box::new(join_existing), Box::new(not_joined)]);
+    test_group
+}
@@ -5,6 +5,8 @@ use procfs::process::Process;
 use std::fs;
 pub mod blkio;
 pub mod cpu;
+pub mod exec;
+pub mod join;
 pub mod memory;
 pub mod network;
 pub mod pids;
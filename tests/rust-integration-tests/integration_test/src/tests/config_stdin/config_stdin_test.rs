@@ -0,0 +1,95 @@
+use crate::utils::{
+    delete_container, generate_uuid, get_runtime_path, get_state, kill_container, prepare_bundle,
+    State, TempDir,
+};
+use anyhow::{anyhow, Context, Result};
+use oci_spec::runtime::{ProcessBuilder, Spec, SpecBuilder};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use test_framework::{test_result, Test, TestGroup, TestResult};
+use uuid::Uuid;
+
+#[inline]
+fn cleanup(id: &Uuid, bundle: &TempDir) {
+    let str_id = id.to_string();
+    kill_container(&str_id, bundle).unwrap().wait().unwrap();
+    delete_container(&str_id, bundle).unwrap().wait().unwrap();
+}
+
+fn create_spec() -> Result<Spec> {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["sleep".to_string(), "1000".to_string()])
+                .build()?,
+        )
+        .build()
+        .context("failed to create spec")
+}
+
+// the spec is piped to the runtime, the bundle only provides the rootfs
+fn test_config_stdin() -> TestResult {
+    let container_id = generate_uuid();
+    let bundle = prepare_bundle(&container_id).unwrap();
+    let config_path = bundle.as_ref().join("bundle").join("config.json");
+    if config_path.exists() {
+        std::fs::remove_file(&config_path).unwrap();
+    }
+    let spec = test_result!(create_spec());
+
+    let mut create = Command::new(get_runtime_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .arg("--root")
+        .arg(bundle.as_ref().join("runtime"))
+        .arg("create")
+        .arg(container_id.to_string())
+        .arg("--bundle")
+        .arg(bundle.as_ref().join("bundle"))
+        .arg("--config")
+        .arg("-")
+        .spawn()
+        .unwrap();
+    let config = serde_json::to_string(&spec).unwrap();
+    // dropping stdin closes it, so that the runtime stops reading
+    create
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(config.as_bytes())
+        .unwrap();
+    let output = create.wait_with_output().unwrap();
+    if !output.status.success() {
+        cleanup(&container_id, &bundle);
+        return TestResult::Failed(anyhow!(
+            "failed to create container from stdin: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let (out, err) = get_state(&container_id.to_string(), &bundle).unwrap();
+    if !err.is_empty() {
+        cleanup(&container_id, &bundle);
+        return TestResult::Failed(anyhow!("error in state : {}", err));
+    }
+
+    let state: State = serde_json::from_str(&out).unwrap();
+    if state.status != "created" {
+        cleanup(&container_id, &bundle);
+        return TestResult::Failed(anyhow!(
+            "error in state : status not matched ,expected 'created' got {}",
+            state.status
+        ));
+    }
+
+    cleanup(&container_id, &bundle);
+    TestResult::Passed
+}
+
+pub fn get_config_stdin_test() -> TestGroup {
+    let config_stdin = Test::new("config_stdin", Box::new(test_config_stdin));
+    let mut tg = TestGroup::new("config_stdin");
+    tg.add(vec![Box::new(config_stdin)]);
+    tg
+}
@@ -0,0 +1,2 @@
+mod config_stdin_test;
+pub use config_stdin_test::get_config_stdin_test;
@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Context, Result};
+use oci_spec::runtime::{ProcessBuilder, Spec, SpecBuilder};
+use std::panic::{self, AssertUnwindSafe};
+use test_framework::{test_result, ContainerGuard, Test, TestGroup, TestResult};
+
+use crate::utils::{
+    create_container, generate_uuid, get_runtime_path, get_state, prepare_bundle, set_config, State,
+};
+
+fn create_spec() -> Result<Spec> {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["sleep".to_string(), "1000".to_string()])
+                .build()
+                .context("failed to build process")?,
+        )
+        .build()
+        .context("failed to build spec")
+}
+
+// A test which panics while its container exists must not leave the
+// container behind
+fn guard_deletes_on_panic_test() -> TestResult {
+    let id = generate_uuid();
+    let id_str = id.to_string();
+    let bundle = prepare_bundle(&id).unwrap();
+    set_config(&bundle, &test_result!(create_spec())).unwrap();
+
+    let mut created = None;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard =
+            ContainerGuard::new(get_runtime_path(), bundle.as_ref().join("runtime"), &id_str);
+        create_container(&id_str, &bundle).unwrap().wait().unwrap();
+        let (out, _) = get_state(&id_str, &bundle).unwrap();
+        created = serde_json::from_str::<State>(&out)
+            .ok()
+            .map(|state| state.status);
+        panic!("test failed after creating the container");
+    }));
+    if result.is_ok() {
+        return TestResult::Failed(anyhow!("expected the test to panic"));
+    }
+    if created.as_deref() != Some("created") {
+        return TestResult::Failed(anyhow!(
+            "expected the container to be created, but its status was {:?}",
+            created
+        ));
+    }
+
+    let (out, err) = get_state(&id_str, &bundle).unwrap();
+    if err.is_empty() {
+        return TestResult::Failed(anyhow!(
+            "expected the container to be deleted, but got its state {}",
+            out
+        ));
+    }
+
+    TestResult::Passed
+}
+
+pub fn get_container_guard_test() -> TestGroup {
+    let mut test_group = TestGroup::new("container_guard");
+    let deletes_on_panic = Test::new(
+        "guard_deletes_on_panic",
+        Box::new(guard_deletes_on_panic_test),
+    );
+    test_group.add(vec![Box::new(deletes_on_panic)]);
+
+    test_group
+}
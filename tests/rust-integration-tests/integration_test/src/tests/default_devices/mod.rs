@@ -0,0 +1,54 @@
+use oci_spec::runtime::{get_default_mounts, ProcessBuilder, RootBuilder, Spec, SpecBuilder};
+use test_framework::{Test, TestGroup, TestResult};
+
+use crate::utils::test_inside_container;
+
+fn create_spec(readonly: bool) -> Spec {
+    // With a read only root and no mount for /dev, the runtime has to provide
+    // a tmpfs for the devices itself.
+    let mounts = get_default_mounts()
+        .into_iter()
+        .filter(|m| !readonly || m.destination().to_str() != Some("/dev"))
+        .collect::<Vec<_>>();
+
+    SpecBuilder::default()
+        .root(
+            RootBuilder::default()
+                .path("rootfs")
+                .readonly(readonly)
+                .build()
+                .expect("error in building root config"),
+        )
+        .mounts(mounts)
+        .process(
+            ProcessBuilder::default()
+                .args(vec![
+                    "runtimetest".to_string(),
+                    "default_devices".to_string(),
+                ])
+                .build()
+                .expect("error in creating process config"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn default_devices_test() -> TestResult {
+    test_inside_container(create_spec(false), &|_| Ok(()))
+}
+
+fn default_devices_readonly_root_test() -> TestResult {
+    test_inside_container(create_spec(true), &|_| Ok(()))
+}
+
+pub fn get_default_devices_test() -> TestGroup {
+    let mut test_group = TestGroup::new("default_devices");
+    let default_devices = Test::new("default_devices_test", Box::new(default_devices_test));
+    let readonly_root = Test::new(
+        "default_devices_readonly_root_test",
+        Box::new(default_devices_readonly_root_test),
+    );
+    test_group.add(vec![Box::new(default_devices), Box::new(readonly_root)]);
+
+    test_group
+}
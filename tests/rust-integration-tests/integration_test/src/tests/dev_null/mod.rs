@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Context, Result};
+use oci_spec::runtime::{ProcessBuilder, Spec, SpecBuilder};
+use std::process::{Command, Stdio};
+use test_framework::{test_result, ContainerGuard, Test, TestGroup, TestResult};
+
+use crate::utils::test_utils::start_container;
+use crate::utils::{
+    generate_uuid, get_runtime_path, get_runtimetest_path, prepare_bundle, set_config,
+};
+
+fn create_spec() -> Result<Spec> {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["runtimetest".to_string(), "dev_null".to_string()])
+                .build()
+                .context("failed to build process")?,
+        )
+        .build()
+        .context("failed to build spec")
+}
+
+// The container inherits stdin and stderr pointing to /dev/null of the host
+// from the runtime, which has to replace them with /dev/null of the container
+fn dev_null_test() -> TestResult {
+    let uuid = generate_uuid();
+    let id = uuid.to_string();
+    let bundle = prepare_bundle(&uuid).unwrap();
+    let spec = test_result!(create_spec());
+    set_config(&bundle, &spec).unwrap();
+    let rootfs = bundle.as_ref().join("bundle").join("rootfs");
+    spec.save(rootfs.join("config.json")).unwrap();
+    std::fs::copy(
+        get_runtimetest_path(),
+        rootfs.join("bin").join("runtimetest"),
+    )
+    .unwrap();
+
+    let runtime_root = bundle.as_ref().join("runtime");
+    let _guard = ContainerGuard::new(get_runtime_path(), &runtime_root, &id);
+    let mut create = Command::new(get_runtime_path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .env("YOUKI_LOG_LEVEL", "error")
+        .arg("--root")
+        .arg(&runtime_root)
+        .arg("create")
+        .arg(&id)
+        .arg("--bundle")
+        .arg(bundle.as_ref().join("bundle"))
+        .spawn()
+        .unwrap();
+    // the output of the container is only complete once it has exited, so the
+    // creation is not waited for before starting it
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let start = start_container(&id, &bundle)
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+    if !start.status.success() {
+        let _ = create.kill();
+        let _ = create.wait();
+        return TestResult::Failed(anyhow!(
+            "container start failed : {}",
+            String::from_utf8_lossy(&start.stderr)
+        ));
+    }
+
+    let create = create.wait_with_output().unwrap();
+    if !create.status.success() {
+        return TestResult::Failed(anyhow!("container creation failed"));
+    }
+    let stdout = String::from_utf8_lossy(&create.stdout);
+    if !stdout.is_empty() {
+        return TestResult::Failed(anyhow!("{}", stdout));
+    }
+
+    TestResult::Passed
+}
+
+pub fn get_dev_null_test() -> TestGroup {
+    let mut test_group = TestGroup::new("dev_null");
+    let dev_null = Test::new("dev_null_test", Box::new(dev_null_test));
+    test_group.add(vec![Box::new(dev_null)]);
+
+    test_group
+}
@@ -0,0 +1,40 @@
+use oci_spec::runtime::{get_default_mounts, ProcessBuilder, Spec, SpecBuilder};
+use test_framework::{Test, TestGroup, TestResult};
+
+use crate::utils::test_inside_container;
+
+fn create_spec(spec_devpts: bool) -> Spec {
+    // Without a /dev/pts mount in the spec, the runtime mounts one itself.
+    let mounts = get_default_mounts()
+        .into_iter()
+        .filter(|m| spec_devpts || m.destination().to_str() != Some("/dev/pts"))
+        .collect::<Vec<_>>();
+
+    SpecBuilder::default()
+        .mounts(mounts)
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["runtimetest".to_string(), "devpts".to_string()])
+                .build()
+                .expect("error in creating process config"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn devpts_test() -> TestResult {
+    test_inside_container(create_spec(true), &|_| Ok(()))
+}
+
+fn devpts_default_test() -> TestResult {
+    test_inside_container(create_spec(false), &|_| Ok(()))
+}
+
+pub fn get_devpts_test() -> TestGroup {
+    let mut test_group = TestGroup::new("devpts");
+    let devpts = Test::new("devpts_test", Box::new(devpts_test));
+    let devpts_default = Test::new("devpts_default_test", Box::new(devpts_default_test));
+    test_group.add(vec![Box::new(devpts), Box::new(devpts_default)]);
+
+    test_group
+}
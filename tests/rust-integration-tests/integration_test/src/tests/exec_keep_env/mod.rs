@@ -0,0 +1,92 @@
+use anyhow::{anyhow, Context, Result};
+use oci_spec::runtime::{ProcessBuilder, Spec, SpecBuilder};
+use std::process::{Command, Stdio};
+use test_framework::{test_result, ContainerGuard, Test, TestGroup, TestResult};
+
+use crate::utils::{
+    create_container, generate_uuid, get_runtime_path, prepare_bundle, set_config,
+    test_utils::start_container,
+};
+
+// The variables are only set for the runtime, so that they are not shared
+// with other tests running in parallel
+const KEPT_VAR: &str = "YOUKI_TEST_EXEC_KEEP_ENV";
+const CONFLICTING_VAR: &str = "YOUKI_TEST_EXEC_KEEP_ENV_CONFLICT";
+
+fn create_spec() -> Result<Spec> {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["sleep".to_string(), "3600".to_string()])
+                .env(vec![
+                    "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string(),
+                    format!("{}=spec", CONFLICTING_VAR),
+                ])
+                .build()
+                .context("failed to build process")?,
+        )
+        .build()
+        .context("failed to build spec")
+}
+
+// Tests if the host variables given with --keep-env are passed to the exec'd
+// process in addition to the environment of the container, which takes
+// precedence on conflicts
+fn exec_keep_env_test() -> TestResult {
+    let id = generate_uuid();
+    let id_str = id.to_string();
+    let bundle = test_result!(prepare_bundle(&id));
+    test_result!(set_config(&bundle, &test_result!(create_spec())));
+
+    let runtime_root = bundle.as_ref().join("runtime");
+    let _guard = ContainerGuard::new(get_runtime_path(), &runtime_root, &id_str);
+    create_container(&id_str, &bundle).unwrap().wait().unwrap();
+    start_container(&id_str, &bundle).unwrap().wait().unwrap();
+
+    let output = test_result!(Command::new(get_runtime_path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env(KEPT_VAR, "host")
+        .env(CONFLICTING_VAR, "host")
+        .arg("--root")
+        .arg(&runtime_root)
+        .arg("exec")
+        .arg("--keep-env")
+        .arg(KEPT_VAR)
+        .arg("--keep-env")
+        .arg(CONFLICTING_VAR)
+        .arg(&id_str)
+        .arg("env")
+        .output()
+        .context("failed to exec into container"));
+    if !output.status.success() {
+        return TestResult::Failed(anyhow!(
+            "exec failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let env = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        format!("{}=host", KEPT_VAR),
+        format!("{}=spec", CONFLICTING_VAR),
+    ] {
+        if !env.lines().any(|var| var == expected) {
+            return TestResult::Failed(anyhow!(
+                "expected {} in the environment of the exec'd process, got {}",
+                expected,
+                env
+            ));
+        }
+    }
+
+    TestResult::Passed
+}
+
+pub fn get_exec_keep_env_test() -> TestGroup {
+    let mut test_group = TestGroup::new("exec_keep_env");
+    let exec_keep_env = Test::new("exec_keep_env", Box::new(exec_keep_env_test));
+    test_group.add(vec![Box::new(exec_keep_env)]);
+
+    test_group
+}
@@ -0,0 +1,126 @@
+use crate::utils::{
+    create_container, generate_uuid, get_runtime_path, prepare_bundle, set_config,
+    test_utils::start_container,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use oci_spec::runtime::{ProcessBuilder, Spec, SpecBuilder};
+use procfs::process::all_processes;
+use std::path::Path;
+use std::process::{Child, Command};
+use test_framework::{test_result, ContainerGuard, Test, TestGroup, TestResult};
+
+// an argument no other process is started with, to find the probe
+const PROBE_SLEEP: &str = "4711";
+
+fn create_spec() -> Result<Spec> {
+    SpecBuilder::default()
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["sleep".to_string(), "1000".to_string()])
+                .build()?,
+        )
+        .build()
+        .context("failed to create spec")
+}
+
+fn run(child: Result<Child>) -> Result<()> {
+    let output = child?.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "runtime failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+// Runs the probe in a running container and returns the report of the
+// health command
+fn probe(timeout: u64, command: &[&str]) -> Result<serde_json::Value> {
+    let id = generate_uuid();
+    let id_str = id.to_string();
+    let bundle = prepare_bundle(&id)?;
+    set_config(&bundle, &create_spec()?)?;
+    let _guard = ContainerGuard::new(get_runtime_path(), bundle.as_ref().join("runtime"), &id_str);
+    run(create_container(&id_str, &bundle))?;
+    run(start_container(&id_str, &bundle))?;
+
+    let output = Command::new(get_runtime_path())
+        .arg("--root")
+        .arg(bundle.as_ref().join("runtime"))
+        .arg("health")
+        .arg("--timeout")
+        .arg(timeout.to_string())
+        .arg(&id_str)
+        .args(command)
+        .output()
+        .context("failed to run health")?;
+    if !output.status.success() {
+        bail!("health failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    serde_json::from_slice(&output.stdout).context("failed to parse health report")
+}
+
+fn check_report(report: &serde_json::Value, status: &str, exit_code: Option<i64>) -> Result<()> {
+    if report["status"] != status || report["exit_code"].as_i64() != exit_code {
+        bail!(
+            "expected status {} and exit code {:?}, got {}",
+            status,
+            exit_code,
+            report
+        );
+    }
+
+    Ok(())
+}
+
+fn test_probe_healthy() -> TestResult {
+    let report = test_result!(probe(10, &["true"]));
+    test_result!(check_report(&report, "healthy", Some(0)));
+    TestResult::Passed
+}
+
+fn test_probe_unhealthy() -> TestResult {
+    let report = test_result!(probe(10, &["sh", "-c", "exit 3"]));
+    test_result!(check_report(&report, "unhealthy", Some(3)));
+    TestResult::Passed
+}
+
+// Tests if a probe which runs for too long is killed itself, not only the
+// process of the runtime waiting for it
+fn test_probe_timeout() -> TestResult {
+    let report = test_result!(probe(1, &["sleep", PROBE_SLEEP]));
+    test_result!(check_report(&report, "unhealthy", None));
+    if report["timed_out"] != true {
+        return TestResult::Failed(anyhow!("expected the probe to time out, got {}", report));
+    }
+
+    let probes = test_result!(running_probes());
+    if probes > 0 {
+        return TestResult::Failed(anyhow!("the probe is still running after its timeout"));
+    }
+    TestResult::Passed
+}
+
+fn running_probes() -> Result<usize> {
+    Ok(all_processes()?
+        .filter_map(|process| process.ok()?.cmdline().ok())
+        .filter(|cmdline| {
+            cmdline.len() == 2
+                && Path::new(&cmdline[0]).ends_with("sleep")
+                && cmdline[1] == PROBE_SLEEP
+        })
+        .count())
+}
+
+pub fn get_health_test() -> TestGroup {
+    let mut tg = TestGroup::new("health");
+    tg.add(vec![
+        Box::new(Test::new("probe_healthy", Box::new(test_probe_healthy))),
+        Box::new(Test::new("probe_unhealthy", Box::new(test_probe_unhealthy))),
+        Box::new(Test::new("probe_timeout", Box::new(test_probe_timeout))),
+    ]);
+    tg
+}
@@ -0,0 +1,2 @@
+mod health_test;
+pub use health_test::get_health_test;
@@ -1,7 +1,7 @@
 use super::{create, delete, kill};
 use crate::utils::TempDir;
 use crate::utils::{generate_uuid, prepare_bundle};
-use test_framework::{TestResult, TestableGroup};
+use test_framework::{TestReport, TestResult, TestableGroup};
 
 pub struct ContainerCreate {
     project_path: TempDir,
@@ -28,9 +28,11 @@ impl ContainerCreate {
     fn create_empty_id(&self) -> TestResult {
         let temp = create::create(&self.project_path, "");
         match temp {
-            TestResult::Passed => TestResult::Failed(anyhow::anyhow!(
-                "Container should not have been created with empty id, but was created."
-            )),
+            TestResult::Passed | TestResult::PassedAfterRetries(_) => {
+                TestResult::Failed(anyhow::anyhow!(
+                    "Container should not have been created with empty id, but was created."
+                ))
+            }
             TestResult::Failed(_) => TestResult::Passed,
             TestResult::Skipped => TestResult::Skipped,
         }
@@ -54,9 +56,11 @@ impl ContainerCreate {
         kill::kill(&self.project_path, &id);
         delete::delete(&self.project_path, &id);
         match temp {
-            TestResult::Passed => TestResult::Failed(anyhow::anyhow!(
-                "Container should not have been created with same id, but was created."
-            )),
+            TestResult::Passed | TestResult::PassedAfterRetries(_) => {
+                TestResult::Failed(anyhow::anyhow!(
+                    "Container should not have been created with same id, but was created."
+                ))
+            }
             TestResult::Failed(_) => TestResult::Passed,
             TestResult::Skipped => TestResult::Skipped,
         }
@@ -68,21 +72,27 @@ impl TestableGroup for ContainerCreate {
         "create"
     }
 
-    fn run_all(&self) -> Vec<(&'static str, TestResult)> {
+    fn get_test_names(&self) -> Vec<&'static str> {
+        vec!["empty_id", "valid_id", "duplicate_id"]
+    }
+
+    fn run_all(&self) -> Vec<TestReport> {
         vec![
-            ("empty_id", self.create_empty_id()),
-            ("valid_id", self.create_valid_id()),
-            ("duplicate_id", self.create_duplicate_id()),
+            TestReport::measure("empty_id", || self.create_empty_id()),
+            TestReport::measure("valid_id", || self.create_valid_id()),
+            TestReport::measure("duplicate_id", || self.create_duplicate_id()),
         ]
     }
 
-    fn run_selected(&self, selected: &[&str]) -> Vec<(&'static str, TestResult)> {
+    fn run_selected(&self, selected: &[&str]) -> Vec<TestReport> {
         let mut ret = Vec::new();
         for name in selected {
             match *name {
-                "empty_id" => ret.push(("empty_id", self.create_empty_id())),
-                "valid_id" => ret.push(("valid_id", self.create_valid_id())),
-                "duplicate_id" => ret.push(("duplicate_id", self.create_duplicate_id())),
+                "empty_id" => ret.push(TestReport::measure("empty_id", || self.create_empty_id())),
+                "valid_id" => ret.push(TestReport::measure("valid_id", || self.create_valid_id())),
+                "duplicate_id" => ret.push(TestReport::measure("duplicate_id", || {
+                    self.create_duplicate_id()
+                })),
                 _ => eprintln!("No test named {} in lifecycle", name),
             };
         }
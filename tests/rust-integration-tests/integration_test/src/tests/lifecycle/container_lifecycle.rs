@@ -1,7 +1,7 @@
 use crate::utils::{generate_uuid, prepare_bundle, TempDir};
 use std::thread::sleep;
 use std::time::Duration;
-use test_framework::{TestResult, TestableGroup};
+use test_framework::{TestReport, TestResult, TestableGroup};
 
 use super::{checkpoint, create, delete, exec, kill, start, state};
 
@@ -74,42 +74,52 @@ impl TestableGroup for ContainerLifecycle {
         "lifecycle"
     }
 
-    fn run_all(&self) -> Vec<(&'static str, TestResult)> {
+    fn get_test_names(&self) -> Vec<&'static str> {
         vec![
-            ("create", self.create()),
-            ("start", self.start()),
-            // ("exec", self.exec(vec!["echo", "Hello"], Some("Hello\n"))),
-            (
-                "checkpoint and leave running with --work-path /tmp",
-                self.checkpoint_leave_running_work_path_tmp(),
-            ),
-            (
-                "checkpoint and leave running",
-                self.checkpoint_leave_running(),
-            ),
-            ("kill", self.kill()),
-            ("state", self.state()),
-            ("delete", self.delete()),
+            "create",
+            "start",
+            "checkpoint_leave_running_work_path_tmp",
+            "checkpoint_leave_running",
+            "kill",
+            "state",
+            "delete",
         ]
     }
 
-    fn run_selected(&self, selected: &[&str]) -> Vec<(&'static str, TestResult)> {
+    fn run_all(&self) -> Vec<TestReport> {
+        vec![
+            TestReport::measure("create", || self.create()),
+            TestReport::measure("start", || self.start()),
+            // TestReport::measure("exec", || self.exec(vec!["echo", "Hello"], Some("Hello\n"))),
+            TestReport::measure("checkpoint and leave running with --work-path /tmp", || {
+                self.checkpoint_leave_running_work_path_tmp()
+            }),
+            TestReport::measure("checkpoint and leave running", || {
+                self.checkpoint_leave_running()
+            }),
+            TestReport::measure("kill", || self.kill()),
+            TestReport::measure("state", || self.state()),
+            TestReport::measure("delete", || self.delete()),
+        ]
+    }
+
+    fn run_selected(&self, selected: &[&str]) -> Vec<TestReport> {
         let mut ret = Vec::new();
         for name in selected {
             match *name {
-                "create" => ret.push(("create", self.create())),
-                "start" => ret.push(("start", self.start())),
-                "checkpoint_leave_running_work_path_tmp" => ret.push((
+                "create" => ret.push(TestReport::measure("create", || self.create())),
+                "start" => ret.push(TestReport::measure("start", || self.start())),
+                "checkpoint_leave_running_work_path_tmp" => ret.push(TestReport::measure(
                     "checkpoint and leave running with --work-path /tmp",
-                    self.checkpoint_leave_running_work_path_tmp(),
-                )),
-                "checkpoint_leave_running" => ret.push((
-                    "checkpoint and leave running",
-                    self.checkpoint_leave_running(),
+                    || self.checkpoint_leave_running_work_path_tmp(),
                 )),
-                "kill" => ret.push(("kill", self.kill())),
-                "state" => ret.push(("state", self.state())),
-                "delete" => ret.push(("delete", self.delete())),
+                "checkpoint_leave_running" => ret
+                    .push(TestReport::measure("checkpoint and leave running", || {
+                        self.checkpoint_leave_running()
+                    })),
+                "kill" => ret.push(TestReport::measure("kill", || self.kill())),
+                "state" => ret.push(TestReport::measure("state", || self.state())),
+                "delete" => ret.push(TestReport::measure("delete", || self.delete())),
                 _ => eprintln!("No test named {} in lifecycle", name),
             };
         }
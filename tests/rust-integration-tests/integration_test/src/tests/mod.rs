@@ -1,10 +1,21 @@
 pub mod cgroups;
+pub mod config_stdin;
+pub mod container_guard;
+pub mod default_devices;
+pub mod dev_null;
+pub mod devpts;
+pub mod exec_keep_env;
+pub mod health;
 pub mod hooks;
 pub mod hostname;
 pub mod lifecycle;
 pub mod linux_ns_itype;
 pub mod mounts_recursive;
+pub mod overlay;
 pub mod pidfile;
+pub mod process_umask;
 pub mod readonly_paths;
+pub mod reexec;
+pub mod root_readonly;
 pub mod seccomp_notify;
 pub mod tlb;
@@ -0,0 +1,59 @@
+use anyhow::Context;
+use oci_spec::runtime::{get_default_mounts, MountBuilder, ProcessBuilder, Spec, SpecBuilder};
+use std::fs;
+use std::path::Path;
+use test_framework::{Test, TestGroup, TestResult};
+
+use crate::utils::test_inside_container;
+
+fn create_spec() -> Spec {
+    let mut mounts = get_default_mounts();
+    // the directories are relative to the bundle
+    mounts.push(
+        MountBuilder::default()
+            .destination("/overlay")
+            .typ("overlay")
+            .source("overlay")
+            .options(vec![
+                "lowerdir=overlay/lower".to_string(),
+                "upperdir=overlay/upper".to_string(),
+                "workdir=overlay/work".to_string(),
+            ])
+            .build()
+            .expect("error in building overlay mount"),
+    );
+
+    SpecBuilder::default()
+        .mounts(mounts)
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["runtimetest".to_string(), "overlay".to_string()])
+                .build()
+                .expect("error in creating process config"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn overlay_test() -> TestResult {
+    let spec = create_spec();
+    test_inside_container(spec, &|rootfs: &Path| {
+        let overlay = rootfs.parent().unwrap().join("overlay");
+        for dir in ["lower", "upper", "work"] {
+            fs::create_dir_all(overlay.join(dir))
+                .with_context(|| format!("failed to create overlay {}", dir))?;
+        }
+        fs::write(overlay.join("lower").join("lower_file"), "lower")?;
+        fs::write(overlay.join("lower").join("shadowed_file"), "lower")?;
+        fs::write(overlay.join("upper").join("shadowed_file"), "upper")?;
+        Ok(())
+    })
+}
+
+pub fn get_overlay_test() -> TestGroup {
+    let mut test_group = TestGroup::new("overlay");
+    let overlay = Test::new("overlay_test", Box::new(overlay_test));
+    test_group.add(vec![Box::new(overlay)]);
+
+    test_group
+}
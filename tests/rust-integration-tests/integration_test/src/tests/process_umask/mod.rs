@@ -0,0 +1,46 @@
+use oci_spec::runtime::{LinuxBuilder, ProcessBuilder, Spec, SpecBuilder, UserBuilder};
+use test_framework::{Test, TestGroup, TestResult};
+
+use crate::utils::test_inside_container;
+
+fn create_spec(umask: u32) -> Spec {
+    SpecBuilder::default()
+        .linux(
+            // Need to reset the read-only paths
+            LinuxBuilder::default()
+                .readonly_paths(vec![])
+                .build()
+                .expect("error in building linux config"),
+        )
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["runtimetest".to_string(), "process_umask".to_string()])
+                .user(
+                    UserBuilder::default()
+                        .umask(umask)
+                        .build()
+                        .expect("error in creating user config"),
+                )
+                .build()
+                .expect("error in creating process config"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn process_umask_test() -> TestResult {
+    let spec = create_spec(0o027);
+    test_inside_container(spec, &|_| {
+        // The umask is applied by the runtime according to the spec,
+        // so nothing to prepare prior.
+        Ok(())
+    })
+}
+
+pub fn get_process_umask_test() -> TestGroup {
+    let mut test_group = TestGroup::new("process_umask");
+    let umask_test = Test::new("process_umask_test", Box::new(process_umask_test));
+    test_group.add(vec![Box::new(umask_test)]);
+
+    test_group
+}
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Context, Result};
+use oci_spec::runtime::{
+    get_default_mounts, get_default_namespaces, LinuxBuilder, LinuxIdMappingBuilder,
+    LinuxNamespaceBuilder, LinuxNamespaceType, MountBuilder, Spec, SpecBuilder,
+};
+use test_framework::{test_result, Test, TestGroup, TestResult};
+
+use crate::utils::{test_outside_container, test_utils::check_container_created};
+
+// the hangs after a fork from a multithreaded runtime were intermittent, so a
+// single successful creation proves little
+const RUNS: usize = 10;
+
+// A new user namespace and additional mounts is where the setup used to hang
+fn create_spec() -> Result<Spec> {
+    let mut namespaces = get_default_namespaces();
+    namespaces.push(
+        LinuxNamespaceBuilder::default()
+            .typ(LinuxNamespaceType::User)
+            .build()
+            .context("failed to build user namespace")?,
+    );
+    let id_mapping = LinuxIdMappingBuilder::default()
+        .host_id(0u32)
+        .container_id(0u32)
+        .size(65536u32)
+        .build()
+        .context("failed to build id mapping")?;
+
+    let mut mounts = get_default_mounts();
+    mounts.push(
+        MountBuilder::default()
+            .destination("/tmp")
+            .typ("tmpfs")
+            .source("tmpfs")
+            .options(vec![
+                "nosuid".to_string(),
+                "nodev".to_string(),
+                "mode=1777".to_string(),
+            ])
+            .build()
+            .context("failed to build tmpfs mount")?,
+    );
+
+    SpecBuilder::default()
+        .mounts(mounts)
+        .linux(
+            LinuxBuilder::default()
+                .namespaces(namespaces)
+                .uid_mappings(vec![id_mapping])
+                .gid_mappings(vec![id_mapping])
+                .build()
+                .context("failed to build linux spec")?,
+        )
+        .build()
+        .context("failed to build spec")
+}
+
+fn test_repeated_create_with_userns_and_mounts() -> TestResult {
+    let spec = test_result!(create_spec());
+    for run in 1..=RUNS {
+        let result = test_outside_container(spec.clone(), &|data| {
+            test_result!(check_container_created(&data));
+            TestResult::Passed
+        });
+        if let TestResult::Failed(err) = result {
+            return TestResult::Failed(anyhow!("run {} of {} failed: {:?}", run, RUNS, err));
+        }
+    }
+
+    TestResult::Passed
+}
+
+pub fn get_reexec_test() -> TestGroup {
+    let mut test_group = TestGroup::new("reexec");
+    let repeated_create = Test::new(
+        "repeated_create_with_userns_and_mounts",
+        Box::new(test_repeated_create_with_userns_and_mounts),
+    );
+    test_group.add(vec![Box::new(repeated_create)]);
+
+    test_group
+}
@@ -0,0 +1,61 @@
+use oci_spec::runtime::{
+    get_default_mounts, LinuxBuilder, MountBuilder, ProcessBuilder, RootBuilder, Spec, SpecBuilder,
+};
+use test_framework::{Test, TestGroup, TestResult};
+
+use crate::utils::test_inside_container;
+
+fn create_spec() -> Spec {
+    let mut mounts = get_default_mounts();
+    mounts.push(
+        MountBuilder::default()
+            .destination("/writable")
+            .typ("tmpfs")
+            .source("tmpfs")
+            .options(vec!["nosuid".to_string(), "nodev".to_string()])
+            .build()
+            .expect("error in building tmpfs mount"),
+    );
+
+    SpecBuilder::default()
+        .root(
+            RootBuilder::default()
+                .path("rootfs")
+                .readonly(true)
+                .build()
+                .expect("error in building root config"),
+        )
+        .mounts(mounts)
+        .linux(
+            // Need to reset the read-only paths
+            LinuxBuilder::default()
+                .readonly_paths(vec![])
+                .build()
+                .expect("error in building linux config"),
+        )
+        .process(
+            ProcessBuilder::default()
+                .args(vec!["runtimetest".to_string(), "root_readonly".to_string()])
+                .build()
+                .expect("error in creating process config"),
+        )
+        .build()
+        .unwrap()
+}
+
+fn root_readonly_test() -> TestResult {
+    let spec = create_spec();
+    test_inside_container(spec, &|_| {
+        // The rootfs is remounted read only by the runtime, while the tmpfs
+        // mount is created by the runtime as well, so nothing to prepare prior.
+        Ok(())
+    })
+}
+
+pub fn get_root_readonly_test() -> TestGroup {
+    let mut test_group = TestGroup::new("root_readonly");
+    let root_readonly = Test::new("root_readonly_test", Box::new(root_readonly_test));
+    test_group.add(vec![Box::new(root_readonly)]);
+
+    test_group
+}
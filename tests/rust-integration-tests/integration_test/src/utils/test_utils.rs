@@ -10,7 +10,7 @@ use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Stdio};
 use std::thread::sleep;
 use std::time::Duration;
-use test_framework::{test_result, TestResult};
+use test_framework::{test_result, ContainerGuard, TestResult};
 
 const SLEEP_TIME: Duration = Duration::from_millis(150);
 pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
@@ -123,6 +123,7 @@ pub fn test_outside_container(
     let id_str = id.to_string();
     let bundle = prepare_bundle(&id).unwrap();
     set_config(&bundle, &spec).unwrap();
+    let _guard = ContainerGuard::new(get_runtime_path(), bundle.as_ref().join("runtime"), &id_str);
     let create_result = create_container(&id_str, &bundle).unwrap().wait();
     let (out, err) = get_state(&id_str, &bundle).unwrap();
     let state: Option<State> = match serde_json::from_str(&out) {
@@ -135,10 +136,7 @@ pub fn test_outside_container(
         state_err: err,
         create_result,
     };
-    let test_result = execute_test(data);
-    kill_container(&id_str, &bundle).unwrap().wait().unwrap();
-    delete_container(&id_str, &bundle).unwrap().wait().unwrap();
-    test_result
+    execute_test(data)
 }
 
 // mostly needs a name that better expresses what this actually does
@@ -178,6 +176,7 @@ pub fn test_inside_container(
             .join("runtimetest"),
     )
     .unwrap();
+    let _guard = ContainerGuard::new(get_runtime_path(), bundle.as_ref().join("runtime"), &id_str);
     let create_process = create_container(&id_str, &bundle).unwrap();
     // here we do not wait for the process by calling wait() as in the test_outside_container
     // function because we need the output of the runtimetest. If we call wait, it will return
@@ -222,8 +221,6 @@ pub fn test_inside_container(
     if state.status != "stopped" {
         return TestResult::Failed(anyhow!("error : unexpected container status in test_inside_runtime : expected stopped, got {}, container state : {:?}",state.status,state));
     }
-    kill_container(&id_str, &bundle).unwrap().wait().unwrap();
-    delete_container(&id_str, &bundle).unwrap().wait().unwrap();
     TestResult::Passed
 }
 
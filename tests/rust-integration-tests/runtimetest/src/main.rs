@@ -30,6 +30,12 @@ fn main() {
         "readonly_paths" => tests::validate_readonly_paths(&spec),
         "set_host_name" => tests::validate_hostname(&spec),
         "mounts_recursive" => tests::validate_mounts_recursive(&spec),
+        "process_umask" => tests::validate_process_umask(&spec),
+        "root_readonly" => tests::validate_root_readonly(&spec),
+        "default_devices" => tests::validate_default_devices(&spec),
+        "devpts" => tests::validate_devpts(&spec),
+        "overlay" => tests::validate_overlay(&spec),
+        "dev_null" => tests::validate_dev_null(&spec),
         _ => eprintln!(
             "error due to unexpected execute test name: {}",
             execute_test
@@ -2,7 +2,9 @@ use crate::utils::{self, test_read_access, test_write_access};
 use anyhow::{bail, Result};
 use nix::errno::Errno;
 use oci_spec::runtime::Spec;
-use std::fs::read_dir;
+use std::fs::{metadata, read_dir, read_link, symlink_metadata, OpenOptions};
+use std::os::unix::fs::{FileTypeExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
 use std::path::Path;
 
 pub fn validate_readonly_paths(spec: &Spec) {
@@ -144,3 +146,262 @@ pub fn validate_mounts_recursive(spec: &Spec) {
         }
     }
 }
+
+pub fn validate_process_umask(spec: &Spec) {
+    let umask = match spec.process().as_ref().and_then(|p| p.user().umask()) {
+        Some(umask) => umask,
+        None => {
+            eprintln!("in process umask, expected umask to be set, found none");
+            return;
+        }
+    };
+
+    let path = Path::new("/tmp/umask_test");
+    if let Err(e) = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o666)
+        .open(path)
+    {
+        eprintln!(
+            "in process umask, error in creating file {:?} : {:?}",
+            path, e
+        );
+        return;
+    }
+
+    match metadata(path) {
+        Ok(m) => {
+            let expected = 0o666 & !umask;
+            let actual = m.permissions().mode() & 0o777;
+            if actual != expected {
+                eprintln!(
+                    "in process umask, expected file mode {:o}, found {:o}",
+                    expected, actual
+                );
+            }
+        }
+        Err(e) => eprintln!(
+            "in process umask, error in reading metadata of {:?} : {:?}",
+            path, e
+        ),
+    }
+}
+
+pub fn validate_root_readonly(spec: &Spec) {
+    let root = spec.root().as_ref().unwrap();
+    if root.readonly() != Some(true) {
+        eprintln!("in root readonly, expected root to be read only, found writable in spec");
+        return;
+    }
+
+    match test_write_access("/") {
+        Ok(_) => {
+            eprintln!("in root readonly, root expected to not be writable, found writable");
+            return;
+        }
+        Err(e) => {
+            let errno = Errno::from_i32(e.raw_os_error().unwrap());
+            if errno != Errno::EROFS {
+                eprintln!(
+                    "in root readonly, error in testing write access for root : {:?}",
+                    e
+                );
+                return;
+            }
+        }
+    }
+
+    // writable mounts layered on top of the root must not be affected
+    if let Some(mounts) = spec.mounts() {
+        for mount in mounts {
+            let is_tmpfs = mount.typ().as_deref() == Some("tmpfs");
+            let is_ro = mount
+                .options()
+                .as_ref()
+                .map(|options| options.iter().any(|o| o == "ro"))
+                .unwrap_or(false);
+            if !is_tmpfs || is_ro {
+                continue;
+            }
+
+            let destination = mount.destination().to_str().unwrap();
+            if let Err(e) = test_write_access(destination) {
+                eprintln!(
+                    "in root readonly, mount {} expected to be writable, found error : {:?}",
+                    destination, e
+                );
+            }
+        }
+    }
+}
+
+pub fn validate_default_devices(spec: &Spec) {
+    // path, major and minor of the character devices every container gets
+    let default_devices = [
+        ("/dev/null", 1, 3),
+        ("/dev/zero", 1, 5),
+        ("/dev/full", 1, 7),
+        ("/dev/tty", 5, 0),
+        ("/dev/urandom", 1, 9),
+        ("/dev/random", 1, 8),
+    ];
+    let configured = spec
+        .linux()
+        .as_ref()
+        .and_then(|l| l.devices().as_ref())
+        .cloned()
+        .unwrap_or_default();
+
+    for (path, major, minor) in default_devices {
+        // devices of the spec take precedence over the defaults
+        if configured.iter().any(|d| d.path() == Path::new(path)) {
+            continue;
+        }
+
+        match metadata(path) {
+            Ok(m) => {
+                if !m.file_type().is_char_device() {
+                    eprintln!(
+                        "in default devices, expected {} to be a character device",
+                        path
+                    );
+                    continue;
+                }
+                let rdev = m.rdev();
+                let (got_major, got_minor) =
+                    (nix::sys::stat::major(rdev), nix::sys::stat::minor(rdev));
+                if (got_major, got_minor) != (major, minor) {
+                    eprintln!(
+                        "in default devices, expected {} to be {}:{}, found {}:{}",
+                        path, major, minor, got_major, got_minor
+                    );
+                }
+            }
+            Err(e) => eprintln!(
+                "in default devices, error in reading metadata of {} : {:?}",
+                path, e
+            ),
+        }
+    }
+
+    validate_ptmx_symlink("default devices");
+
+    let terminal = spec.process().as_ref().and_then(|p| p.terminal()) == Some(true);
+    if terminal {
+        if let Err(e) = metadata("/dev/console") {
+            eprintln!(
+                "in default devices, expected /dev/console for a terminal : {:?}",
+                e
+            );
+        }
+    }
+}
+
+fn validate_ptmx_symlink(test: &str) {
+    match symlink_metadata("/dev/ptmx") {
+        Ok(m) if m.file_type().is_symlink() => match read_link("/dev/ptmx") {
+            Ok(target) if target == Path::new("pts/ptmx") => {}
+            Ok(target) => eprintln!(
+                "in {}, expected /dev/ptmx to link to pts/ptmx, found {:?}",
+                test, target
+            ),
+            Err(e) => eprintln!("in {}, error in reading /dev/ptmx : {:?}", test, e),
+        },
+        Ok(_) => eprintln!("in {}, expected /dev/ptmx to be a symlink", test),
+        Err(e) => eprintln!(
+            "in {}, error in reading metadata of /dev/ptmx : {:?}",
+            test, e
+        ),
+    }
+}
+
+pub fn validate_devpts(_spec: &Spec) {
+    validate_ptmx_symlink("devpts");
+
+    // allocating a pty goes through /dev/ptmx, i.e. the ptmx of the devpts instance
+    match nix::pty::openpty(None, None) {
+        Ok(pty) => {
+            let _ = nix::unistd::close(pty.master);
+            let _ = nix::unistd::close(pty.slave);
+        }
+        Err(e) => eprintln!("in devpts, error in allocating a pty : {:?}", e),
+    }
+}
+
+pub fn validate_overlay(_spec: &Spec) {
+    // the merged view contains the files of both layers, the upper one winning
+    for (path, expected) in [
+        ("/overlay/lower_file", "lower"),
+        ("/overlay/shadowed_file", "upper"),
+    ] {
+        match std::fs::read_to_string(path) {
+            Ok(content) if content == expected => {}
+            Ok(content) => eprintln!(
+                "in overlay, expected {} to contain {:?}, found {:?}",
+                path, expected, content
+            ),
+            Err(e) => eprintln!("in overlay, error in reading {} : {:?}", path, e),
+        }
+    }
+
+    if let Err(e) = test_write_access("/overlay") {
+        eprintln!("in overlay, expected /overlay to be writable : {:?}", e);
+    }
+}
+
+// The runtime runs with stdin and stderr pointing to /dev/null of the host,
+// so failures are reported on stdout instead.
+pub fn validate_dev_null(_spec: &Spec) {
+    let dev_null = match metadata("/dev/null") {
+        Ok(m) => m,
+        Err(e) => {
+            return println!(
+                "in dev_null, error in reading metadata of /dev/null : {:?}",
+                e
+            )
+        }
+    };
+    let container_mnt_id = match mnt_id("/dev/null") {
+        Ok(id) => id,
+        Err(e) => return println!("in dev_null, {:?}", e),
+    };
+    for fd in [0, 2] {
+        // reopening the descriptor yields a file on the same mount
+        let fd_path = format!("/proc/self/fd/{}", fd);
+        match metadata(&fd_path) {
+            Ok(m) if m.rdev() == dev_null.rdev() => {}
+            Ok(_) => {
+                println!("in dev_null, expected fd {} to point to /dev/null", fd);
+                continue;
+            }
+            Err(e) => {
+                println!(
+                    "in dev_null, error in reading metadata of fd {} : {:?}",
+                    fd, e
+                );
+                continue;
+            }
+        }
+        match mnt_id(&fd_path) {
+            Ok(id) if id == container_mnt_id => {}
+            Ok(id) => println!(
+                "in dev_null, expected fd {} to be /dev/null of the container on mount {}, found mount {}",
+                fd, container_mnt_id, id
+            ),
+            Err(e) => println!("in dev_null, {:?}", e),
+        }
+    }
+}
+
+// Reads the id of the mount the file is on from the fdinfo of the opened file
+fn mnt_id(path: &str) -> Result<String> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let fdinfo = format!("/proc/self/fdinfo/{}", file.as_raw_fd());
+    let info = std::fs::read_to_string(&fdinfo)?;
+    match info.lines().find_map(|l| l.strip_prefix("mnt_id:")) {
+        Some(id) => Ok(id.trim().to_string()),
+        None => bail!("no mount id found in {}", fdinfo),
+    }
+}
@@ -0,0 +1,271 @@
+//! Contains assertions commonly needed by tests, which fail the test with a
+//! message describing what was expected and what was found instead
+use crate::testable::TestResult;
+use anyhow::anyhow;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Asserts that the runtime reports the container with the given status, e.g.
+/// `created`, `running` or `stopped`, in the output of its state command
+pub fn assert_state(runtime: &Path, root: &Path, id: &str, expected_status: &str) -> TestResult {
+    let output = match Command::new(runtime)
+        .arg("--root")
+        .arg(root)
+        .arg("state")
+        .arg(id)
+        .output()
+    {
+        Ok(output) => output,
+        Err(err) => {
+            return TestResult::Failed(anyhow!("failed to get state of container {}: {}", id, err))
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        return TestResult::Failed(anyhow!(
+            "failed to get state of container {}: {}",
+            id,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let state: serde_json::Value = match serde_json::from_str(&stdout) {
+        Ok(state) => state,
+        Err(err) => {
+            return TestResult::Failed(anyhow!(
+                "invalid state of container {} ({}): {}",
+                id,
+                err,
+                stdout.trim()
+            ))
+        }
+    };
+    match (state["id"].as_str(), state["status"].as_str()) {
+        (Some(actual_id), _) if actual_id != id => TestResult::Failed(anyhow!(
+            "expected state of container {}, but got the state of container {}",
+            id,
+            actual_id
+        )),
+        (_, Some(status)) if status == expected_status => TestResult::Passed,
+        (_, Some(status)) => TestResult::Failed(anyhow!(
+            "expected container {} to be {}, but it is {}",
+            id,
+            expected_status,
+            status
+        )),
+        (_, None) => TestResult::Failed(anyhow!(
+            "state of container {} has no status: {}",
+            id,
+            stdout.trim()
+        )),
+    }
+}
+
+/// Asserts that the file contains the given string
+pub fn assert_file_contains<P: AsRef<Path>>(path: P, substr: &str) -> TestResult {
+    let path = path.as_ref();
+    match fs::read_to_string(path) {
+        Ok(content) if content.contains(substr) => TestResult::Passed,
+        Ok(content) => TestResult::Failed(anyhow!(
+            "expected {:?} to contain {:?}, but its content is {:?}",
+            path,
+            substr,
+            content
+        )),
+        Err(err) => TestResult::Failed(anyhow!("failed to read {:?}: {}", path, err)),
+    }
+}
+
+/// Asserts the value of an interface file of a controller, e.g. `pids.max` of
+/// the pids controller, for the cgroup at the path relative to the cgroup
+/// root. Surrounding whitespace of the value is ignored.
+pub fn assert_cgroup_value<P: AsRef<Path>>(
+    cgroup_path: P,
+    controller: &str,
+    key: &str,
+    expected: &str,
+) -> TestResult {
+    assert_cgroup_value_in(
+        Path::new(CGROUP_ROOT),
+        cgroup_path.as_ref(),
+        controller,
+        key,
+        expected,
+    )
+}
+
+fn assert_cgroup_value_in(
+    root: &Path,
+    cgroup_path: &Path,
+    controller: &str,
+    key: &str,
+    expected: &str,
+) -> TestResult {
+    // on cgroup v1 every controller is mounted at a hierarchy of its own
+    let hierarchy = if root.join("cgroup.controllers").exists() {
+        root.to_path_buf()
+    } else {
+        root.join(controller)
+    };
+    let path = hierarchy
+        .join(cgroup_path.strip_prefix("/").unwrap_or(cgroup_path))
+        .join(key);
+
+    match fs::read_to_string(&path) {
+        Ok(value) if value.trim() == expected => TestResult::Passed,
+        Ok(value) => TestResult::Failed(anyhow!(
+            "expected {} of cgroup {:?} to be {:?}, but it is {:?}",
+            key,
+            cgroup_path,
+            expected,
+            value.trim()
+        )),
+        Err(err) => TestResult::Failed(anyhow!(
+            "failed to read {} of cgroup {:?} from {:?}: {}",
+            key,
+            cgroup_path,
+            path,
+            err
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fake_runtime, TempDir};
+
+    fn failure(result: TestResult) -> String {
+        match result {
+            TestResult::Failed(err) => err.to_string(),
+            other => panic!("expected the assertion to fail, but got {:?}", other),
+        }
+    }
+
+    fn assert_passed(result: TestResult) {
+        if !matches!(result, TestResult::Passed) {
+            panic!("expected the assertion to pass, but got {:?}", result);
+        }
+    }
+
+    // A stand-in for the runtime, which prints the state it is given for
+    // every container. All cases run in one test, so that they share the script.
+    #[test]
+    fn test_assert_state() {
+        let tmp = TempDir::new("assert_state");
+        let runtime = fake_runtime(
+            tmp.path(),
+            "case \"$4\" in\n\
+             missing) echo \"container $4 does not exist\" >&2; exit 1 ;;\n\
+             garbage) echo 'not json' ;;\n\
+             other) echo '{\"id\": \"another\", \"status\": \"running\"}' ;;\n\
+             *) echo \"{\\\"id\\\": \\\"$4\\\", \\\"status\\\": \\\"running\\\"}\" ;;\n\
+             esac\n",
+        );
+        let root = tmp.path().join("root");
+
+        assert_passed(assert_state(&runtime, &root, "test", "running"));
+        assert_eq!(
+            failure(assert_state(&runtime, &root, "test", "stopped")),
+            "expected container test to be stopped, but it is running"
+        );
+        assert_eq!(
+            failure(assert_state(&runtime, &root, "missing", "running")),
+            "failed to get state of container missing: container missing does not exist"
+        );
+        assert_eq!(
+            failure(assert_state(&runtime, &root, "other", "running")),
+            "expected state of container other, but got the state of container another"
+        );
+        assert!(failure(assert_state(&runtime, &root, "garbage", "running"))
+            .starts_with("invalid state of container garbage ("));
+        assert!(failure(assert_state(
+            &tmp.path().join("none"),
+            &root,
+            "test",
+            "running"
+        ))
+        .starts_with("failed to get state of container test: "));
+    }
+
+    #[test]
+    fn test_assert_file_contains() {
+        let tmp = TempDir::new("assert_file_contains");
+        let path = tmp.path().join("hostname");
+        fs::write(&path, "youki\n").unwrap();
+
+        assert_passed(assert_file_contains(&path, "youki"));
+        assert_eq!(
+            failure(assert_file_contains(&path, "runc")),
+            format!(
+                "expected {:?} to contain \"runc\", but its content is \"youki\\n\"",
+                path
+            )
+        );
+        assert!(
+            failure(assert_file_contains(tmp.path().join("none"), "youki"))
+                .starts_with(&format!("failed to read {:?}: ", tmp.path().join("none")))
+        );
+    }
+
+    #[test]
+    fn test_assert_cgroup_value() {
+        let tmp = TempDir::new("assert_cgroup_value");
+        let v1 = tmp.path().join("v1");
+        fs::create_dir_all(v1.join("pids/runtime-test/container")).unwrap();
+        fs::write(v1.join("pids/runtime-test/container/pids.max"), "10\n").unwrap();
+        let v2 = tmp.path().join("v2");
+        fs::create_dir_all(v2.join("runtime-test/container")).unwrap();
+        fs::write(v2.join("cgroup.controllers"), "cpu pids\n").unwrap();
+        fs::write(v2.join("runtime-test/container/pids.max"), "max\n").unwrap();
+        let cgroup = Path::new("/runtime-test/container");
+
+        assert_passed(assert_cgroup_value_in(
+            &v1, cgroup, "pids", "pids.max", "10",
+        ));
+        assert_passed(assert_cgroup_value_in(
+            &v2, cgroup, "pids", "pids.max", "max",
+        ));
+        assert_eq!(
+            failure(assert_cgroup_value_in(
+                &v2, cgroup, "pids", "pids.max", "10"
+            )),
+            "expected pids.max of cgroup \"/runtime-test/container\" to be \"10\", but it is \"max\""
+        );
+        assert!(failure(assert_cgroup_value_in(
+            &v1,
+            cgroup,
+            "memory",
+            "memory.limit_in_bytes",
+            "1024"
+        ))
+        .starts_with(&format!(
+            "failed to read memory.limit_in_bytes of cgroup \"/runtime-test/container\" from {:?}: ",
+            v1.join("memory/runtime-test/container/memory.limit_in_bytes")
+        )));
+    }
+
+    #[test]
+    fn test_assertions_chain() {
+        let tmp = TempDir::new("assertions_chain");
+        let path = tmp.path().join("hostname");
+        fs::write(&path, "youki\n").unwrap();
+
+        assert_passed(
+            assert_file_contains(&path, "you").and_then(|| assert_file_contains(&path, "ki")),
+        );
+        assert_eq!(
+            failure(
+                assert_file_contains(&path, "runc")
+                    .and_then(|| panic!("must not run after a failed assertion"))
+            ),
+            format!(
+                "expected {:?} to contain \"runc\", but its content is \"youki\\n\"",
+                path
+            )
+        );
+    }
+}
@@ -14,6 +14,8 @@ pub struct ConditionalTest {
     test_fn: Box<TestFn>,
     /// function to check if a test can be run or not
     check_fn: Box<CheckFn>,
+    /// how often the test is retried if it fails
+    max_retries: u32,
 }
 
 impl ConditionalTest {
@@ -23,8 +25,15 @@ impl ConditionalTest {
             name,
             check_fn,
             test_fn,
+            max_retries: 0,
         }
     }
+
+    /// retry the test up to the given number of times if it fails
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
 }
 
 impl Testable for ConditionalTest {
@@ -36,6 +45,10 @@ impl Testable for ConditionalTest {
         (self.check_fn)()
     }
 
+    fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
     fn run(&self) -> TestResult {
         (self.test_fn)()
     }
@@ -0,0 +1,98 @@
+//! Contains the guard which tears down a container once a test is done with
+//! it, also when the test returns early or panics
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Deletes the container with the runtime when dropped, killing it first if
+/// it is still running. Create the guard as soon as the container has been
+/// created, e.g. within `Testable::run`, and keep it alive until the test is
+/// done with the container. Errors of the deletion do not fail the test, they
+/// are only logged.
+#[derive(Debug)]
+pub struct ContainerGuard {
+    runtime: PathBuf,
+    root: PathBuf,
+    id: String,
+}
+
+impl ContainerGuard {
+    /// Guards the container with the given id, which was created by the
+    /// runtime with the given root directory
+    pub fn new<P: AsRef<Path>, R: AsRef<Path>>(runtime: P, root: R, id: &str) -> Self {
+        ContainerGuard {
+            runtime: runtime.as_ref().to_path_buf(),
+            root: root.as_ref().to_path_buf(),
+            id: id.to_owned(),
+        }
+    }
+
+    /// Id of the guarded container
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Drop for ContainerGuard {
+    fn drop(&mut self) {
+        let output = Command::new(&self.runtime)
+            .stdin(Stdio::null())
+            .arg("--root")
+            .arg(&self.root)
+            .arg("delete")
+            .arg("--force")
+            .arg(&self.id)
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => eprintln!(
+                "Error : failed to delete container {}: {}",
+                self.id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(err) => eprintln!("Error : failed to delete container {}: {}", self.id, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{fake_runtime, TempDir};
+    use std::panic;
+
+    // A stand-in for the runtime, which keeps a directory in its root for
+    // every container that exists
+    const RUNTIME: &str = "root=$2\n\
+                           case \"$3\" in\n\
+                           create) mkdir -p \"$root/$4\" ;;\n\
+                           delete) [ \"$4\" = --force ] && rm -r \"$root/$5\" ;;\n\
+                           esac\n";
+
+    #[test]
+    fn test_guard_deletes_container_when_test_panics() {
+        let tmp = TempDir::new("guard");
+        let runtime = fake_runtime(tmp.path(), RUNTIME);
+        let root = tmp.path().join("root");
+
+        let result = panic::catch_unwind(|| {
+            let status = Command::new(&runtime)
+                .arg("--root")
+                .arg(&root)
+                .arg("create")
+                .arg("test")
+                .status()
+                .unwrap();
+            assert!(status.success());
+            let guard = ContainerGuard::new(&runtime, &root, "test");
+            assert!(root.join(guard.id()).exists());
+            panic!("test failed with the container still around");
+        });
+        assert!(result.is_err());
+        assert!(!root.join("test").exists());
+
+        // the container is gone already, so the deletion fails, which must
+        // not fail the test
+        drop(ContainerGuard::new(&runtime, &root, "test"));
+        drop(ContainerGuard::new(tmp.path().join("none"), &root, "test"));
+    }
+}
@@ -1,10 +1,16 @@
+pub mod assert;
 mod conditional_test;
+pub mod guard;
+pub mod reporter;
 mod test;
 mod test_group;
 mod test_manager;
+#[cfg(test)]
+mod test_utils;
 pub mod testable;
 pub use conditional_test::ConditionalTest;
+pub use guard::ContainerGuard;
 pub use test::Test;
 pub use test_group::TestGroup;
 pub use test_manager::TestManager;
-pub use testable::{TestResult, Testable, TestableGroup};
+pub use testable::{TestReport, TestResult, Testable, TestableGroup};
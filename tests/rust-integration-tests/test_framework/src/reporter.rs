@@ -0,0 +1,179 @@
+//! Contains the reporters, which present the results of the tests
+use crate::testable::{TestReport, TestResult};
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// Counts of the results of all tests which were run, together with
+/// their durations
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub total: usize,
+    pub passed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// name and duration of every test, the slowest first
+    pub durations: Vec<(String, Duration)>,
+}
+
+impl Summary {
+    /// Creates the summary of the reports of the test groups
+    pub fn new(groups: &[(&str, Vec<TestReport>)]) -> Self {
+        let mut summary = Summary::default();
+        for (group, reports) in groups {
+            for report in reports {
+                summary.total += 1;
+                match report.result {
+                    TestResult::Passed | TestResult::PassedAfterRetries(_) => summary.passed += 1,
+                    TestResult::Skipped => summary.skipped += 1,
+                    TestResult::Failed(_) => summary.failed += 1,
+                }
+                summary
+                    .durations
+                    .push((format!("{}::{}", group, report.name), report.duration));
+            }
+        }
+        summary
+            .durations
+            .sort_by_key(|(_, duration)| Reverse(*duration));
+        summary
+    }
+
+    /// The given number of slowest tests
+    pub fn slowest(&self, count: usize) -> &[(String, Duration)] {
+        &self.durations[..count.min(self.durations.len())]
+    }
+}
+
+/// Presents the results of the tests, so that the output format can vary
+pub trait Reporter {
+    /// Reports the results of a group once all of its tests have run
+    fn report_group(&self, name: &str, reports: &[TestReport]);
+    /// Reports the summary once all tests have run
+    fn report_summary(&self, summary: &Summary);
+}
+
+/// Prints the results as text to stdout
+pub struct TextReporter {
+    /// how many of the slowest tests are listed in the summary
+    slowest: usize,
+}
+
+impl Default for TextReporter {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl TextReporter {
+    /// Create a reporter which lists the given number of slowest tests
+    pub fn new(slowest: usize) -> Self {
+        TextReporter { slowest }
+    }
+}
+
+impl Reporter for TextReporter {
+    fn report_group(&self, name: &str, reports: &[TestReport]) {
+        println!("# Start group {}", name);
+        let len = reports.len();
+        for (idx, report) in reports.iter().enumerate() {
+            print!(
+                "{} / {} : {} ({:.2}s) : ",
+                idx + 1,
+                len,
+                report.name,
+                report.duration.as_secs_f64()
+            );
+            match &report.result {
+                TestResult::Passed => {
+                    println!("ok");
+                }
+                TestResult::PassedAfterRetries(retries) => {
+                    println!("ok, after {} attempts", retries + 1);
+                }
+                TestResult::Skipped => {
+                    println!("skipped");
+                }
+                TestResult::Failed(e) => {
+                    println!("not ok\n\t{}", e);
+                }
+            }
+        }
+        println!("# End group {}\n", name);
+    }
+
+    fn report_summary(&self, summary: &Summary) {
+        println!(
+            "# Summary: {} tests, {} passed, {} skipped, {} failed",
+            summary.total, summary.passed, summary.skipped, summary.failed
+        );
+        let slowest = summary.slowest(self.slowest);
+        if !slowest.is_empty() {
+            println!("# Slowest tests:");
+            for (name, duration) in slowest {
+                println!("{:>8.2}s  {}", duration.as_secs_f64(), name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn report(name: &'static str, result: TestResult, millis: u64) -> TestReport {
+        TestReport {
+            name,
+            result,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let groups = vec![
+            (
+                "lifecycle",
+                vec![
+                    report("create", TestResult::Passed, 30),
+                    report("start", TestResult::PassedAfterRetries(1), 200),
+                    report("kill", TestResult::Failed(anyhow!("failed")), 10),
+                ],
+            ),
+            (
+                "tlb",
+                vec![
+                    report("tlb", TestResult::Skipped, 0),
+                    report("huge", TestResult::Passed, 100),
+                ],
+            ),
+        ];
+
+        let summary = Summary::new(&groups);
+        assert_eq!(
+            (
+                summary.total,
+                summary.passed,
+                summary.skipped,
+                summary.failed
+            ),
+            (5, 3, 1, 1)
+        );
+        assert_eq!(
+            summary.slowest(3),
+            &[
+                ("lifecycle::start".to_owned(), Duration::from_millis(200)),
+                ("tlb::huge".to_owned(), Duration::from_millis(100)),
+                ("lifecycle::create".to_owned(), Duration::from_millis(30)),
+            ]
+        );
+        assert_eq!(summary.slowest(10).len(), 5);
+    }
+
+    #[test]
+    fn test_summary_without_tests() {
+        let summary = Summary::new(&[]);
+        assert_eq!(summary.total, 0);
+        assert!(summary.slowest(5).is_empty());
+    }
+}
@@ -10,12 +10,24 @@ pub struct Test {
     name: &'static str,
     /// Actual test function
     test_fn: Box<TestFn>,
+    /// how often the test is retried if it fails
+    max_retries: u32,
 }
 
 impl Test {
     /// create new test
     pub fn new(name: &'static str, test_fn: Box<TestFn>) -> Self {
-        Test { name, test_fn }
+        Test {
+            name,
+            test_fn,
+            max_retries: 0,
+        }
+    }
+
+    /// retry the test up to the given number of times if it fails
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
     }
 }
 
@@ -24,6 +36,10 @@ impl Testable for Test {
         self.name
     }
 
+    fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
     fn run(&self) -> TestResult {
         (self.test_fn)()
     }
@@ -1,5 +1,5 @@
 ///! Contains structure for a test group
-use crate::testable::{TestResult, Testable, TestableGroup};
+use crate::testable::{TestReport, TestResult, Testable, TestableGroup};
 use crossbeam::thread;
 use std::collections::BTreeMap;
 
@@ -34,19 +34,19 @@ impl TestableGroup for TestGroup {
         self.name
     }
 
+    /// get names of the tests in the group
+    fn get_test_names(&self) -> Vec<&'static str> {
+        self.tests.keys().copied().collect()
+    }
+
     /// run all the test from the test group
-    fn run_all(&self) -> Vec<(&'static str, TestResult)> {
+    fn run_all(&self) -> Vec<TestReport> {
         let mut ret = Vec::with_capacity(self.tests.len());
         thread::scope(|s| {
             let mut collector = Vec::with_capacity(self.tests.len());
             for (_, t) in self.tests.iter() {
-                let _t = s.spawn(move |_| {
-                    if t.can_run() {
-                        (t.get_name(), t.run())
-                    } else {
-                        (t.get_name(), TestResult::Skipped)
-                    }
-                });
+                let _t =
+                    s.spawn(move |_| TestReport::measure(t.get_name(), || run_test(t.as_ref())));
                 collector.push(_t);
             }
             for handle in collector {
@@ -58,7 +58,7 @@ impl TestableGroup for TestGroup {
     }
 
     /// run selected test from the group
-    fn run_selected(&self, selected: &[&str]) -> Vec<(&'static str, TestResult)> {
+    fn run_selected(&self, selected: &[&str]) -> Vec<TestReport> {
         let selected_tests = self
             .tests
             .iter()
@@ -67,13 +67,8 @@ impl TestableGroup for TestGroup {
         thread::scope(|s| {
             let mut collector = Vec::with_capacity(selected.len());
             for (_, t) in selected_tests {
-                let _t = s.spawn(move |_| {
-                    if t.can_run() {
-                        (t.get_name(), t.run())
-                    } else {
-                        (t.get_name(), TestResult::Skipped)
-                    }
-                });
+                let _t =
+                    s.spawn(move |_| TestReport::measure(t.get_name(), || run_test(t.as_ref())));
                 collector.push(_t);
             }
             for handle in collector {
@@ -84,3 +79,119 @@ impl TestableGroup for TestGroup {
         ret
     }
 }
+
+// Runs the test, a failed test is run again as often as it allows. A test
+// which passes on a retry reports how many retries it needed.
+fn run_test(t: &(dyn Testable + Sync + Send)) -> TestResult {
+    if !t.can_run() {
+        return TestResult::Skipped;
+    }
+
+    let mut result = t.run();
+    let mut retries = 0;
+    while matches!(result, TestResult::Failed(_)) && retries < t.max_retries() {
+        retries += 1;
+        result = t.run();
+    }
+
+    match result {
+        TestResult::Passed if retries > 0 => TestResult::PassedAfterRetries(retries),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConditionalTest, Test};
+    use anyhow::anyhow;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    // a test which fails the given number of times before it passes
+    fn flaky_test(
+        name: &'static str,
+        failures: u32,
+        max_retries: u32,
+    ) -> (Box<Test>, Arc<AtomicU32>) {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        let test = Test::new(
+            name,
+            Box::new(move || {
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    TestResult::Failed(anyhow!("failed in run {}", counter.load(Ordering::SeqCst)))
+                } else {
+                    TestResult::Passed
+                }
+            }),
+        )
+        .with_max_retries(max_retries);
+        (Box::new(test), runs)
+    }
+
+    fn result_of(tg: &TestGroup, name: &str) -> String {
+        let results = tg.run_selected(&[name]);
+        assert_eq!(results.len(), 1);
+        match &results[0].result {
+            TestResult::Failed(err) => format!("failed: {}", err),
+            other => format!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_passed_after_retry() {
+        let (test, runs) = flaky_test("flaky", 1, 3);
+        let mut tg = TestGroup::new("retry");
+        tg.add(vec![test]);
+
+        assert_eq!(result_of(&tg, "flaky"), "PassedAfterRetries(1)");
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_failed_after_all_retries() {
+        let (test, runs) = flaky_test("flaky", 5, 2);
+        let mut tg = TestGroup::new("retry");
+        tg.add(vec![test]);
+
+        assert_eq!(result_of(&tg, "flaky"), "failed: failed in run 3");
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_no_retry_by_default() {
+        let (test, runs) = flaky_test("flaky", 1, 0);
+        let mut tg = TestGroup::new("retry");
+        tg.add(vec![test]);
+
+        assert_eq!(result_of(&tg, "flaky"), "failed: failed in run 1");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_passed_and_skipped_are_not_retried() {
+        let (test, runs) = flaky_test("passing", 0, 3);
+        let skipped_runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&skipped_runs);
+        let skipped = ConditionalTest::new(
+            "skipped",
+            Box::new(|| true),
+            Box::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                TestResult::Skipped
+            }),
+        )
+        .with_max_retries(3);
+        let mut tg = TestGroup::new("retry");
+        tg.add(vec![test]);
+        tg.add(vec![Box::new(skipped)]);
+
+        assert_eq!(result_of(&tg, "passing"), "Passed");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(result_of(&tg, "skipped"), "Skipped");
+        assert_eq!(skipped_runs.load(Ordering::SeqCst), 1);
+    }
+}
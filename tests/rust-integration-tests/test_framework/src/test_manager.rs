@@ -1,15 +1,19 @@
 ///! This exposes the main control wrapper to control the tests
-use crate::testable::{TestResult, TestableGroup};
-use anyhow::Result;
-use crossbeam::thread;
+use crate::reporter::{Reporter, Summary, TextReporter};
+use crate::testable::{TestReport, TestableGroup};
+use anyhow::{bail, Result};
+use crossbeam::thread::{self, ScopedJoinHandle};
 use std::collections::BTreeMap;
 
 type TestableGroupType = dyn TestableGroup + Sync + Send;
+/// Selected test groups, with the selected tests of each or None for all
+type Selection<'a> = Vec<(&'a str, Option<Vec<&'a str>>)>;
 
 /// This manages all test groups, and thus the tests
 pub struct TestManager {
     test_groups: BTreeMap<&'static str, Box<TestableGroupType>>,
     cleanup: Vec<Box<dyn Fn() -> Result<()>>>,
+    reporter: Box<dyn Reporter>,
 }
 
 impl Default for TestManager {
@@ -24,6 +28,7 @@ impl TestManager {
         TestManager {
             test_groups: BTreeMap::new(),
             cleanup: Vec::new(),
+            reporter: Box::new(TextReporter::default()),
         }
     }
 
@@ -32,54 +37,33 @@ impl TestManager {
         self.test_groups.insert(tg.get_name(), tg);
     }
 
+    /// set the reporter which presents the results of the tests
+    pub fn set_reporter(&mut self, reporter: Box<dyn Reporter>) {
+        self.reporter = reporter;
+    }
+
     pub fn add_cleanup(&mut self, cleaner: Box<dyn Fn() -> Result<()>>) {
         self.cleanup.push(cleaner)
     }
 
-    /// Prints the given test results, usually used to print
-    /// results of a test group
-    fn print_test_result(&self, name: &str, res: &[(&'static str, TestResult)]) {
-        println!("# Start group {}", name);
-        let len = res.len();
-        for (idx, (name, res)) in res.iter().enumerate() {
-            print!("{} / {} : {} : ", idx + 1, len, name);
-            match res {
-                TestResult::Passed => {
-                    println!("ok");
-                }
-                TestResult::Skipped => {
-                    println!("skipped");
-                }
-                TestResult::Failed(e) => {
-                    println!("not ok\n\t{}", e);
-                }
-            }
-        }
-        println!("# End group {}\n", name);
-    }
     /// Run all tests from all tests group
-    pub fn run_all(&self) {
-        thread::scope(|s| {
+    pub fn run_all(&self) -> Summary {
+        let groups = thread::scope(|s| {
             let mut collector = Vec::with_capacity(self.test_groups.len());
             for (name, tg) in &self.test_groups {
                 let r = s.spawn(move |_| tg.run_all());
-                collector.push((name, r));
-            }
-            for (name, handle) in collector {
-                self.print_test_result(name, &handle.join().unwrap());
+                collector.push((*name, r));
             }
+            self.collect(collector)
         })
         .unwrap();
-        for cleaner in &self.cleanup {
-            if let Err(e) = cleaner() {
-                print!("Failed to cleanup: {}", e);
-            }
-        }
+        self.cleanup();
+        self.summarize(&groups)
     }
 
     /// Run only selected tests
-    pub fn run_selected(&self, tests: Vec<(&str, Option<Vec<&str>>)>) {
-        thread::scope(|s| {
+    pub fn run_selected(&self, tests: Vec<(&str, Option<Vec<&str>>)>) -> Summary {
+        let groups = thread::scope(|s| {
             let mut collector = Vec::with_capacity(tests.len());
             for (test_group_name, tests) in &tests {
                 if let Some(tg) = self.test_groups.get(test_group_name) {
@@ -87,17 +71,34 @@ impl TestManager {
                         None => s.spawn(move |_| tg.run_all()),
                         Some(tests) => s.spawn(move |_| tg.run_selected(tests)),
                     };
-                    collector.push((test_group_name, r));
+                    collector.push((*test_group_name, r));
                 } else {
                     eprintln!("Error : Test Group {} not found, skipping", test_group_name);
                 }
             }
-            for (name, handle) in collector {
-                self.print_test_result(name, &handle.join().unwrap());
-            }
+            self.collect(collector)
         })
         .unwrap();
+        self.cleanup();
+        self.summarize(&groups)
+    }
+
+    // waits for the test groups and reports the results of each one of them
+    fn collect<'a>(
+        &self,
+        collector: Vec<(&'a str, ScopedJoinHandle<Vec<TestReport>>)>,
+    ) -> Vec<(&'a str, Vec<TestReport>)> {
+        collector
+            .into_iter()
+            .map(|(name, handle)| {
+                let reports = handle.join().unwrap();
+                self.reporter.report_group(name, &reports);
+                (name, reports)
+            })
+            .collect()
+    }
 
+    fn cleanup(&self) {
         for cleaner in &self.cleanup {
             if let Err(e) = cleaner() {
                 print!("Failed to cleanup: {}", e);
@@ -105,7 +106,291 @@ impl TestManager {
         }
     }
 
+    fn summarize(&self, groups: &[(&str, Vec<TestReport>)]) -> Summary {
+        let summary = Summary::new(groups);
+        self.reporter.report_summary(&summary);
+        summary
+    }
+
     pub fn tests_groups(&self) -> Vec<String> {
         self.test_groups.iter().map(|tg| tg.0.to_string()).collect()
     }
+
+    /// Names of all test groups, each one followed by the names of its tests
+    /// in the form group::test
+    pub fn test_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for (group_name, tg) in &self.test_groups {
+            names.push(group_name.to_string());
+            for test_name in tg.get_test_names() {
+                names.push(format!("{}::{}", group_name, test_name));
+            }
+        }
+        names
+    }
+
+    /// Parses the selection of tests to be run, which is given as a test group
+    /// name to select the whole group or as group::test1,test2 to select only
+    /// some tests of the group. Unknown names are rejected with suggestions of
+    /// similar names.
+    pub fn select<'a>(&self, tests: &'a [String]) -> Result<Selection<'a>> {
+        let mut selected = Vec::with_capacity(tests.len());
+        for test in tests {
+            let (group_name, test_names) = match test.split_once("::") {
+                Some((group_name, test_names)) => {
+                    (group_name, Some(test_names.split(',').collect::<Vec<_>>()))
+                }
+                None => (test.as_str(), None),
+            };
+
+            let tg = match self.test_groups.get(group_name) {
+                Some(tg) => tg,
+                None => bail!(
+                    "unknown test group {}{}",
+                    group_name,
+                    self.suggest(group_name)
+                ),
+            };
+            if let Some(test_names) = &test_names {
+                let known = tg.get_test_names();
+                for test_name in test_names {
+                    if !known.contains(test_name) {
+                        let name = format!("{}::{}", group_name, test_name);
+                        bail!("unknown test {}{}", name, self.suggest(&name));
+                    }
+                }
+            }
+            selected.push((group_name, test_names));
+        }
+        Ok(selected)
+    }
+
+    // names of groups and tests which are close to the unknown name
+    fn suggest(&self, name: &str) -> String {
+        let mut close: Vec<_> = self
+            .test_names()
+            .into_iter()
+            .map(|known| (edit_distance(name, &known), known))
+            .filter(|(distance, known)| *distance <= 3.max(known.len() / 3))
+            .collect();
+        close.sort();
+        match close.len() {
+            0 => String::new(),
+            _ => {
+                let close: Vec<_> = close.into_iter().take(3).map(|(_, known)| known).collect();
+                format!(", did you mean {}?", close.join(" or "))
+            }
+        }
+    }
+}
+
+// The Levenshtein distance, i.e. the number of characters which have to be
+// inserted, deleted or substituted to turn one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Test, TestGroup, TestResult};
+    use anyhow::anyhow;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Recorder {
+        groups: Mutex<Vec<(String, usize)>>,
+        summaries: Mutex<Vec<(usize, usize, usize, usize)>>,
+    }
+
+    impl Reporter for Arc<Recorder> {
+        fn report_group(&self, name: &str, reports: &[TestReport]) {
+            self.groups
+                .lock()
+                .unwrap()
+                .push((name.to_owned(), reports.len()));
+        }
+
+        fn report_summary(&self, summary: &Summary) {
+            self.summaries.lock().unwrap().push((
+                summary.total,
+                summary.passed,
+                summary.skipped,
+                summary.failed,
+            ));
+        }
+    }
+
+    fn test_manager(recorder: &Arc<Recorder>) -> TestManager {
+        let mut tg = TestGroup::new("group");
+        tg.add(vec![
+            Box::new(Test::new(
+                "slow",
+                Box::new(|| {
+                    std::thread::sleep(Duration::from_millis(20));
+                    TestResult::Passed
+                }),
+            )),
+            Box::new(Test::new("passed", Box::new(|| TestResult::Passed))),
+            Box::new(Test::new("skipped", Box::new(|| TestResult::Skipped))),
+            Box::new(Test::new(
+                "failed",
+                Box::new(|| TestResult::Failed(anyhow!("failed"))),
+            )),
+        ]);
+        let mut other = TestGroup::new("other");
+        other.add(vec![Box::new(Test::new(
+            "passed",
+            Box::new(|| TestResult::Passed),
+        ))]);
+
+        let mut tm = TestManager::new();
+        tm.add_test_group(Box::new(tg));
+        tm.add_test_group(Box::new(other));
+        tm.set_reporter(Box::new(Arc::clone(recorder)));
+        tm
+    }
+
+    #[test]
+    fn test_run_all_summary() {
+        let recorder = Arc::new(Recorder::default());
+        let summary = test_manager(&recorder).run_all();
+
+        assert_eq!(
+            (
+                summary.total,
+                summary.passed,
+                summary.skipped,
+                summary.failed
+            ),
+            (5, 3, 1, 1)
+        );
+        assert_eq!(summary.durations.len(), 5);
+        assert_eq!(summary.durations[0].0, "group::slow");
+        assert!(summary.durations[0].1 >= Duration::from_millis(20));
+        assert!(summary
+            .durations
+            .windows(2)
+            .all(|pair| pair[0].1 >= pair[1].1));
+        assert!(summary
+            .durations
+            .iter()
+            .all(|(_, duration)| *duration >= Duration::ZERO));
+
+        assert_eq!(
+            *recorder.groups.lock().unwrap(),
+            vec![("group".to_owned(), 4), ("other".to_owned(), 1)]
+        );
+        assert_eq!(*recorder.summaries.lock().unwrap(), vec![(5, 3, 1, 1)]);
+    }
+
+    #[test]
+    fn test_run_selected_summary() {
+        let recorder = Arc::new(Recorder::default());
+        let summary = test_manager(&recorder).run_selected(vec![
+            ("group", Some(vec!["passed", "failed"])),
+            ("missing", None),
+        ]);
+
+        assert_eq!(
+            (
+                summary.total,
+                summary.passed,
+                summary.skipped,
+                summary.failed
+            ),
+            (2, 1, 0, 1)
+        );
+        assert_eq!(
+            *recorder.groups.lock().unwrap(),
+            vec![("group".to_owned(), 2)]
+        );
+        assert_eq!(*recorder.summaries.lock().unwrap(), vec![(2, 1, 0, 1)]);
+    }
+
+    #[test]
+    fn test_run_selected_runs_only_the_selected_test() {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let mut tg = TestGroup::new("group");
+        for name in ["first", "second", "third"] {
+            let runs = Arc::clone(&runs);
+            tg.add(vec![Box::new(Test::new(
+                name,
+                Box::new(move || {
+                    runs.lock().unwrap().push(name);
+                    TestResult::Passed
+                }),
+            ))]);
+        }
+        let mut tm = TestManager::new();
+        tm.add_test_group(Box::new(tg));
+        tm.set_reporter(Box::new(Arc::new(Recorder::default())));
+
+        let tests = vec!["group::second".to_owned()];
+        let summary = tm.run_selected(tm.select(&tests).unwrap());
+        assert_eq!(summary.total, 1);
+        assert_eq!(*runs.lock().unwrap(), vec!["second"]);
+    }
+
+    #[test]
+    fn test_test_names() {
+        let recorder = Arc::new(Recorder::default());
+        assert_eq!(
+            test_manager(&recorder).test_names(),
+            vec![
+                "group",
+                "group::failed",
+                "group::passed",
+                "group::skipped",
+                "group::slow",
+                "other",
+                "other::passed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let recorder = Arc::new(Recorder::default());
+        let tm = test_manager(&recorder);
+
+        let tests = vec!["group::slow,passed".to_owned(), "other".to_owned()];
+        assert_eq!(
+            tm.select(&tests).unwrap(),
+            vec![("group", Some(vec!["slow", "passed"])), ("other", None)]
+        );
+
+        for (test, err) in [
+            ("gruop", "unknown test group gruop, did you mean group?"),
+            (
+                "group::pased",
+                "unknown test group::pased, did you mean group::passed or group::failed?",
+            ),
+            ("group::unrelated", "unknown test group::unrelated"),
+            ("missing", "unknown test group missing"),
+        ] {
+            let tests = vec![test.to_owned()];
+            assert_eq!(tm.select(&tests).unwrap_err().to_string(), err);
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("group", "group"), 0);
+        assert_eq!(edit_distance("gruop", "group"), 2);
+        assert_eq!(edit_distance("pased", "passed"), 1);
+        assert_eq!(edit_distance("", "tlb"), 3);
+    }
 }
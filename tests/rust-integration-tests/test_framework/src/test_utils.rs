@@ -0,0 +1,40 @@
+//! Helpers shared by the tests of the framework itself
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// Directory in the temp dir which is unique to the test and the test run,
+/// it is removed on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("test_framework_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes a shell script which stands in for the runtime into the directory.
+/// The script is called like the runtime, i.e. `$1` is `--root`, `$2` the
+/// root, `$3` the command and the arguments of the command follow. A test
+/// should create one script only, as it can not be executed while another test
+/// forks with a script still open for writing.
+pub fn fake_runtime(dir: &Path, script: &str) -> PathBuf {
+    let runtime = dir.join("runtime");
+    fs::write(&runtime, format!("#!/bin/sh\n{}", script)).unwrap();
+    fs::set_permissions(&runtime, fs::Permissions::from_mode(0o755)).unwrap();
+    runtime
+}
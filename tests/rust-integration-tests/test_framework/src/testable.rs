@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
 ///! Contains Basic setup for testing, testable trait and its result type
 use anyhow::{bail, Error, Result};
@@ -9,12 +10,25 @@ use anyhow::{bail, Error, Result};
 pub enum TestResult {
     /// Test was ok
     Passed,
+    /// Test was ok after it failed, the number of retries it needed
+    PassedAfterRetries(u32),
     /// Test needed to be skipped
     Skipped,
     /// Test was error
     Failed(Error),
 }
 
+impl TestResult {
+    /// Runs the next check only if this one has passed, so that a test can
+    /// consist of several assertions of which the first failure is reported
+    pub fn and_then<F: FnOnce() -> TestResult>(self, next: F) -> TestResult {
+        match self {
+            TestResult::Passed | TestResult::PassedAfterRetries(_) => next(),
+            other => other,
+        }
+    }
+}
+
 impl<T> From<Result<T>> for TestResult {
     fn from(result: Result<T>) -> Self {
         match result {
@@ -24,6 +38,30 @@ impl<T> From<Result<T>> for TestResult {
     }
 }
 
+/// Result of a test together with the time it took to run it
+#[derive(Debug)]
+pub struct TestReport {
+    /// name of the test
+    pub name: &'static str,
+    /// result of the test
+    pub result: TestResult,
+    /// time the test took, including all of its retries
+    pub duration: Duration,
+}
+
+impl TestReport {
+    /// Runs the test function and measures how long it takes
+    pub fn measure<F: FnOnce() -> TestResult>(name: &'static str, test_fn: F) -> Self {
+        let start = Instant::now();
+        let result = test_fn();
+        TestReport {
+            name,
+            result,
+            duration: start.elapsed(),
+        }
+    }
+}
+
 /// This trait indicates that something can be run as a test, or is 'testable'
 /// This forms the basis of the framework, as all places where tests are done,
 /// expect structs which implement this
@@ -32,6 +70,11 @@ pub trait Testable {
     fn can_run(&self) -> bool {
         true
     }
+    /// How often a failed test is run again before it counts as failed,
+    /// e.g. for a test which is known to be flaky because of timing
+    fn max_retries(&self) -> u32 {
+        0
+    }
     fn run(&self) -> TestResult;
 }
 
@@ -39,8 +82,10 @@ pub trait Testable {
 /// Test groups are used to group tests in sensible manner as well as provide namespacing to tests
 pub trait TestableGroup {
     fn get_name(&self) -> &'static str;
-    fn run_all(&self) -> Vec<(&'static str, TestResult)>;
-    fn run_selected(&self, selected: &[&str]) -> Vec<(&'static str, TestResult)>;
+    /// names of the tests of the group, which can be selected to run
+    fn get_test_names(&self) -> Vec<&'static str>;
+    fn run_all(&self) -> Vec<TestReport>;
+    fn run_selected(&self, selected: &[&str]) -> Vec<TestReport>;
 }
 
 #[macro_export]